@@ -1,5 +1,8 @@
 use clap::{Parser, Subcommand};
 use std::path::PathBuf;
+use cargo_pystubgen::stubgen::check::Checker;
+use cargo_pystubgen::stubgen::config::{DocsFormat, DocstringStyle, PythonVersion};
+use cargo_pystubgen::stubgen::logging::MessageFormat;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None, subcommand_negates_reqs = true)]
@@ -17,12 +20,123 @@ pub enum Commands {
         #[arg(short, long)]
         project_dir: Option<PathBuf>,
 
-        /// 出力ディレクトリ
+        /// 出力ディレクトリ（[tool.pystubgen] output-dirより優先。プロジェクトが1つの場合だけ）
         #[arg(short, long)]
         output_dir: Option<PathBuf>,
 
-        /// デバッグモード
+        /// 詳細に表示する（-vでプロジェクトの構成、-vvでモジュールごとの処理とキャッシュ）
+        #[arg(short, long, action = clap::ArgAction::Count, conflicts_with = "quiet")]
+        verbose: u8,
+
+        /// 警告とエラーだけを表示する
+        #[arg(short, long)]
+        quiet: bool,
+
+        /// 警告（Anyにした型など）があれば終了コード1にする（スタブは書き込む）
+        #[arg(long)]
+        deny_warnings: bool,
+
+        /// メッセージの出力形式（human、json。jsonは1行に1つのJSONを標準出力に書く）
+        #[arg(long, default_value_t = MessageFormat::Human)]
+        message_format: MessageFormat,
+
+        /// 有効にするフィーチャー（カンマ区切り）
+        #[arg(short = 'F', long, value_delimiter = ',')]
+        features: Vec<String>,
+
+        /// すべてのフィーチャーを有効にする
+        #[arg(long)]
+        all_features: bool,
+
+        /// defaultフィーチャーを無効にする
+        #[arg(long)]
+        no_default_features: bool,
+
+        /// #[cfg]の評価に使うターゲットトリプル（既定はホスト）
+        #[arg(long)]
+        target: Option<String>,
+
+        /// マクロ展開後のソースを解析する（cargo expand または nightly の rustc が必要）
+        #[arg(long)]
+        expand: bool,
+
+        /// 解析結果のキャッシュ（target/pystubgen/）を使わない
+        #[arg(long)]
+        no_cache: bool,
+
+        /// 並列に処理するスレッド数（既定はCPUの数）
+        #[arg(short, long)]
+        jobs: Option<usize>,
+
+        /// ソースの変更を監視し、変更のあったプロジェクトのスタブを再生成し続ける
         #[arg(short, long)]
-        debug: bool,
+        watch: bool,
+
+        /// 拡張モジュールを含むパッケージにpy.typedを作成する
+        #[arg(long)]
+        py_typed: bool,
+
+        /// 拡張モジュールの公開名を再エクスポートする__init__.pyiを生成する（手で管理されている場合は検証する）
+        #[arg(long)]
+        init_stub: bool,
+
+        /// ワークスペースの定義を使わず、配下のPyO3拡張モジュールを持つプロジェクトをすべて探す（target・.venv・node_modulesは除く）
+        #[arg(long)]
+        scan: bool,
+
+        /// ドキュメントコメントの出力形式（comment、docstring、none。[tool.pystubgen] docstring-styleより優先）
+        #[arg(long)]
+        docstring_style: Option<DocstringStyle>,
+
+        /// 対象とするPythonのバージョン（3.9など。[tool.pystubgen] python-versionより優先）
+        #[arg(long)]
+        python_version: Option<PythonVersion>,
+
+        /// スタブの先頭に書くテキスト（[tool.pystubgen] headerより優先）
+        #[arg(long)]
+        header: Option<String>,
+
+        /// スタブと一緒にAPIリファレンスを出力する（markdown、rst。[tool.pystubgen] docsより優先）
+        #[arg(long)]
+        docs: Option<DocsFormat>,
+
+        #[command(subcommand)]
+        action: Option<GenerateAction>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum GenerateAction {
+    /// 拡張モジュールをビルドしてインポートし、生成済みのスタブと食い違う名前・シグネチャを報告する
+    Verify {
+        /// 使うPythonインタプリタ（既定はVIRTUAL_ENV、.venv、python3の順）
+        #[arg(long)]
+        python: Option<PathBuf>,
+
+        /// ビルドせず、インストール済みのモジュールを検証する
+        #[arg(long)]
+        no_build: bool,
+    },
+
+    /// スタブを生成し、ビルドした拡張モジュールに対してstubtest・pyright --verifytypesを実行する（問題はRustのソースの場所とともに表示する）
+    Check {
+        /// 使う検査ツール（stubtest、pyright。カンマ区切り、既定はインストールされているものすべて）
+        #[arg(long, value_delimiter = ',')]
+        tool: Vec<Checker>,
+
+        /// 使うPythonインタプリタ（既定はVIRTUAL_ENV、.venv、python3の順）
+        #[arg(long)]
+        python: Option<PathBuf>,
+
+        /// ビルドせず、インストール済みのモジュールを検査する
+        #[arg(long)]
+        no_build: bool,
+    },
+
+    /// 解析結果（関数・定数・クラス・型エイリアスとRustのソースの場所）をバージョン付きのJSONで出力する（スタブは書き込まない）
+    DumpIr {
+        /// 出力先のファイル（既定は標準出力）
+        #[arg(long)]
+        file: Option<PathBuf>,
     },
 }
\ No newline at end of file
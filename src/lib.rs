@@ -0,0 +1,11 @@
+//! cargo-pystubgenのライブラリ
+//! 
+//! `cargo pystubgen`コマンドと同じスタブ生成を、`build.rs`やxtaskなどから呼び出せるようにします。
+//! 通常は[`StubGenerator`]を使い、`build.rs`からは[`generate_in_build_script`]を呼び出します。
+
+pub mod stubgen;
+
+pub use stubgen::builder::{ProjectStubs, StubGenerator, StubSet};
+pub use stubgen::buildscript::{generate_in_build_script, generate_in_build_script_with};
+pub use stubgen::diagnostic::{Diagnostic, Location, Severity};
+pub use stubgen::generator::{FileKind, StubFile};
@@ -1,20 +1,90 @@
 mod cli;
 use clap::Parser;
-use cli::{Cli, Commands};
-mod stubgen;
+use cli::{Cli, Commands, GenerateAction};
+use cargo_pystubgen::stubgen;
+use cargo_pystubgen::stubgen::logging::{Logger, Verbosity};
 fn main() {
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::Generate { project_dir, output_dir, debug } => {
-            println!("Generating Python stubs...");
-            if let Some(dir) = project_dir.clone() {
-                println!("Project directory: {}", dir.display());
+        Commands::Generate {
+            project_dir,
+            output_dir,
+            verbose,
+            quiet,
+            deny_warnings,
+            message_format,
+            features,
+            all_features,
+            no_default_features,
+            target,
+            expand,
+            no_cache,
+            jobs,
+            watch,
+            py_typed,
+            init_stub,
+            scan,
+            docstring_style,
+            python_version,
+            header,
+            docs,
+            action,
+        } => {
+            let verbosity = Verbosity::from_flags(quiet, verbose);
+            let logger = Logger { verbosity, format: message_format };
+            // dump-irは標準出力にJSONだけを書く
+            if matches!(action, None | Some(GenerateAction::Check { .. })) {
+                logger.log(Verbosity::Normal, "Generating Python stubs...");
+                if let Some(dir) = &project_dir {
+                    logger.log(Verbosity::Verbose, &format!("Project directory: {}", dir.display()));
+                }
+                if let Some(dir) = &output_dir {
+                    logger.log(Verbosity::Verbose, &format!("Output directory: {}", dir.display()));
+                }
             }
-            if let Some(dir) = output_dir.clone() {
-                println!("Output directory: {}", dir.display());
+            let options = stubgen::GenerateOptions {
+                project_dir,
+                verbosity,
+                message_format,
+                cfg: stubgen::cfg::CfgOptions {
+                    features,
+                    all_features,
+                    no_default_features,
+                    target,
+                },
+                expand,
+                no_cache,
+                cache_dir: None,
+                jobs,
+                package: stubgen::package::PackageOptions { py_typed, init_stub },
+                scan,
+                deny_warnings,
+                overrides: stubgen::config::ConfigOverrides {
+                    output_dir,
+                    docstring_style,
+                    python_version,
+                    header,
+                    docs,
+                },
+            };
+            let result = match action {
+                Some(GenerateAction::Verify { python, no_build }) => {
+                    let verify_options = stubgen::verify::VerifyOptions { python, no_build };
+                    stubgen::verify::verify_stubs(&options, &verify_options)
+                }
+                Some(GenerateAction::Check { tool, python, no_build }) => {
+                    let check_options = stubgen::check::CheckOptions { checkers: tool, python, no_build };
+                    stubgen::check::check_stubs(&options, &check_options)
+                }
+                Some(GenerateAction::DumpIr { file }) => stubgen::ir::dump_ir(&options, file.as_deref()),
+                None if watch => stubgen::watch::watch_projects(&options),
+                None => stubgen::generate_stubs(&options),
+            };
+            if let Err(e) = result {
+                logger.error(e.to_string());
+                std::process::exit(1);
             }
-            stubgen::generate_stubs(debug);
         }
     }
 }
\ No newline at end of file
@@ -1,55 +1,652 @@
 //! Pythonスタブファイル生成ツール
 //! 
 //! このモジュールは、RustのソースコードからPythonの型ヒント付きスタブファイル（.pyi）を生成します。
-//! maturinを使用したPythonプロジェクトの型情報を自動的に生成します。
+//! maturin・setuptools-rust・scikit-build-coreを使用したPythonプロジェクトの型情報を自動的に生成します。
 
 pub mod parser;    // Rustソースコードのパース
 pub mod analyzer;  // パースされたRustコードの解析
 pub mod typemap;   // Rust型からPython型への変換
 pub mod generator; // スタブファイルの生成
 pub mod locator;   // Pythonプロジェクトの検出
+pub mod backend;   // ビルドバックエンドの設定の読み込み
+pub mod config;    // [tool.pystubgen]の設定
+pub mod modgraph;  // クレートのモジュール構成の収集
+pub mod cfg;       // #[cfg]・#[cfg_attr]の評価
+pub mod expand;    // マクロ展開後のソースの取得
+pub mod symbols;   // クレート全体の型定義の収集
+pub mod cache;     // ファイルごとの解析結果のキャッシュ
+pub mod watch;     // ソースの変更を監視して再生成
+pub mod package;   // py.typed・__init__.pyiの生成
+pub mod signature; // #[pyo3(signature)]・text_signatureの解析
+pub mod ir;        // 解析結果の中間表現（JSON）の出力
+pub mod docs;      // APIリファレンス（Markdown・reST）の生成
+pub mod verify;    // ビルドした拡張モジュールとスタブの照合
+pub mod check;     // stubtest・pyrightによるスタブの検査
+pub mod diagnostic; // 警告などの診断
+pub mod builder;   // ライブラリとして使うためのビルダー
+pub mod buildscript; // build.rsからのスタブ生成
+pub mod logging;   // 詳細度・出力形式に合わせたメッセージの表示
+
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use rayon::prelude::*;
+
+/// スレッドをまたいで返せるエラー
+type SendError = Box<dyn Error + Send + Sync>;
+
+/// スタブ生成のオプション
+#[derive(Debug, Default)]
+pub struct GenerateOptions {
+    /// プロジェクトを探し始めるディレクトリ（未指定ならカレントディレクトリ）
+    pub project_dir: Option<PathBuf>,
+    /// 表示の詳細度
+    pub verbosity: logging::Verbosity,
+    /// メッセージの出力形式
+    pub message_format: logging::MessageFormat,
+    /// `#[cfg]`の評価に使うフィーチャーとターゲット
+    pub cfg: cfg::CfgOptions,
+    /// マクロ展開後のソースを解析する
+    pub expand: bool,
+    /// 解析結果のキャッシュを使わない
+    pub no_cache: bool,
+    /// 解析結果のキャッシュを置くディレクトリ（未指定なら`target/pystubgen`）
+    pub cache_dir: Option<PathBuf>,
+    /// 並列に処理するスレッド数（未指定ならCPUの数）
+    pub jobs: Option<usize>,
+    /// py.typed・__init__.pyiの生成オプション
+    pub package: package::PackageOptions,
+    /// ワークスペースの定義を使わず、配下のPyO3拡張モジュールを持つプロジェクトをすべて探す
+    pub scan: bool,
+    /// 警告があればエラーにする
+    pub deny_warnings: bool,
+    /// [tool.pystubgen]の設定より優先するコマンドラインの指定
+    pub overrides: config::ConfigOverrides,
+}
+
+impl GenerateOptions {
+    /// 詳細度と出力形式に合わせてメッセージを表示するロガー
+    pub fn logger(&self) -> logging::Logger {
+        logging::Logger { verbosity: self.verbosity, format: self.message_format }
+    }
+}
+
+/// 並列に処理した結果を、宣言順に並べ直すための出力
+#[derive(Debug, Default)]
+struct ParseOutput {
+    /// パースされたモジュール（宣言順）
+    rust_src_data: Vec<parser::RustSrcData>,
+    /// 表示するメッセージ
+    log: Vec<logging::LogMessage>,
+    /// 警告
+    warnings: Vec<diagnostic::Diagnostic>,
+    /// 無効として扱った未知の`cfg`の名前
+    unknown_cfgs: BTreeSet<String>,
+}
+
+impl ParseOutput {
+    fn extend(&mut self, other: ParseOutput) {
+        self.rust_src_data.extend(other.rust_src_data);
+        self.log.extend(other.log);
+        self.warnings.extend(other.warnings);
+        self.unknown_cfgs.extend(other.unknown_cfgs);
+    }
+}
+
+/// プロジェクトごとのスタブ生成の結果
+#[derive(Debug, Default)]
+pub struct ProjectReport {
+    /// 表示するメッセージ
+    pub log: Vec<logging::LogMessage>,
+    /// 警告（解決できなかった型など）
+    pub warnings: Vec<diagnostic::Diagnostic>,
+    /// スタブファイルのパス
+    pub output_path: PathBuf,
+    /// スタブファイルを書き込んだかどうか（内容が同じなら書き込まない）
+    pub written: bool,
+    /// py.typed・__init__.pyiのパスと、書き込んだかどうか
+    pub package_files: Vec<(PathBuf, bool)>,
+    /// 関数・定数が定義されているRustのソースの場所
+    pub sources: Vec<analyzer::ItemSource>,
+    /// APIリファレンスのパスと、書き込んだかどうか
+    pub docs_file: Option<(PathBuf, bool)>,
+    /// スタブに出力した関数・定数・クラス・型エイリアスの数
+    pub emitted_items: usize,
+    /// 設定で除外した関数・定数・クラスの数
+    pub skipped_items: usize,
+    /// 生成に使ったファイル（ビルドバックエンドの設定・Cargo.toml・Rustのソース）
+    pub source_files: Vec<PathBuf>,
+}
 
 /// Pythonスタブファイルを生成する
 /// 
+/// プロジェクトとファイルは並列に処理するが、メッセージはプロジェクトの順にまとめて表示し、最後にまとめを表示する。
+/// 
 /// # Arguments
 /// 
-/// * `project_dir` - プロジェクトのルートディレクトリ（オプション）
-/// * `output_dir` - 出力ディレクトリ（オプション）
-/// * `debug` - デバッグモードの有効/無効
-pub fn generate_stubs(debug: bool) {
+/// * `options` - スタブ生成のオプション
+/// 
+/// # Returns
+/// 
+/// * `Result<(), Box<dyn Error>>` - 処理結果（失敗したプロジェクトがあれば、`deny_warnings`なら警告があってもエラー）
+pub fn generate_stubs(options: &GenerateOptions) -> Result<(), Box<dyn Error>> {
+    let logger = options.logger();
     // プロジェクト情報の取得
-    let project_infos = locator::locate_python_project().unwrap();
+    let project_infos = locate_projects(options)?;
 
-    for project_info in project_infos {
-        println!("Processing project: {}", project_info.project_name);
-        println!("Output directory: {}", project_info.output_dir.display());
-        println!("Module name: {}", project_info.module_name);
+    let mut summary = logging::Summary { projects: project_infos.len(), ..Default::default() };
+    for (result, project_info) in generate_projects(&project_infos, options)?.into_iter().zip(&project_infos) {
+        match result {
+            Ok(report) => {
+                print_report(&report, &logger);
+                summary.emitted_items += report.emitted_items;
+                summary.skipped_items += report.skipped_items;
+                summary.warnings += report.warnings.len();
+            }
+            Err(e) => {
+                logger.error(format!("failed to generate stubs for {}: {}", project_info.project_name, e));
+                summary.failed += 1;
+            }
+        }
+    }
+    logger.summary(&summary);
+    if summary.failed > 0 {
+        return Err(format!("{} project(s) failed", summary.failed).into());
+    }
+    deny_warnings(options, summary.warnings)
+}
+
+/// `deny_warnings`が指定されていて警告があればエラーにする
+/// 
+/// # Arguments
+/// 
+/// * `options` - スタブ生成のオプション
+/// * `warnings` - 警告の数
+/// 
+/// # Returns
+/// 
+/// * `Result<(), Box<dyn Error>>` - 警告を許さない場合に警告があればエラー
+pub fn deny_warnings(options: &GenerateOptions, warnings: usize) -> Result<(), Box<dyn Error>> {
+    if options.deny_warnings && warnings > 0 {
+        return Err(format!("{} warning(s) treated as errors (--deny-warnings)", warnings).into());
+    }
+    Ok(())
+}
+
+/// オプションで指定されたディレクトリ（未指定ならカレントディレクトリ）からプロジェクトを探す
+/// 
+/// # Arguments
+/// 
+/// * `options` - スタブ生成のオプション
+/// 
+/// # Returns
+/// 
+/// * `Result<Vec<locator::ProjectInfo>, Box<dyn Error>>` - 見つかったプロジェクト
+pub fn locate_projects(options: &GenerateOptions) -> Result<Vec<locator::ProjectInfo>, Box<dyn Error>> {
+    match &options.project_dir {
+        Some(dir) => locator::locate_python_project_in(&std::env::current_dir()?.join(dir), options.scan),
+        None => locator::locate_python_project(options.scan),
+    }
+}
 
-        // 既存のスタブファイルを削除
-        let stub_file = project_info.output_dir.join(format!("{}.pyi", project_info.module_name));
-        if stub_file.exists() {
-            std::fs::remove_file(&stub_file)
-                .unwrap_or_else(|e| println!("Failed to remove existing stub file: {}", e));
+/// プロジェクトごとのスタブ生成の結果を表示する
+/// 
+/// # Arguments
+/// 
+/// * `report` - スタブ生成の結果
+/// * `logger` - メッセージを表示するロガー
+pub fn print_report(report: &ProjectReport, logger: &logging::Logger) {
+    logger.log_all(&report.log);
+    for warning in &report.warnings {
+        logger.diagnostic(warning);
+    }
+    logger.file(generator::FileKind::Stub, &report.output_path, report.written);
+    for (path, written) in &report.package_files {
+        let kind = if path.ends_with("py.typed") { generator::FileKind::PyTyped } else { generator::FileKind::InitStub };
+        logger.file(kind, path, *written);
+    }
+    if let Some((path, written)) = &report.docs_file {
+        logger.file(generator::FileKind::Docs, path, *written);
+    }
+}
+
+/// 複数のプロジェクトのスタブファイルを並列に生成する
+/// 
+/// # Arguments
+/// 
+/// * `project_infos` - プロジェクト情報のリスト
+/// * `options` - スタブ生成のオプション
+/// 
+/// # Returns
+/// 
+/// * `Result<Vec<Result<ProjectReport, String>>, Box<dyn Error>>` - プロジェクトごとの結果（`project_infos`の順）
+pub fn generate_projects(
+    project_infos: &[locator::ProjectInfo],
+    options: &GenerateOptions,
+) -> Result<Vec<Result<ProjectReport, String>>, Box<dyn Error>> {
+    for_each_project(project_infos, options, generate_project_stub)
+}
+
+/// 複数のプロジェクトのスタブファイルなどを並列に組み立てる（書き込みはしない）
+/// 
+/// # Arguments
+/// 
+/// * `project_infos` - プロジェクト情報のリスト
+/// * `options` - スタブ生成のオプション
+/// 
+/// # Returns
+/// 
+/// * `Result<Vec<Result<RenderedProject, String>>, Box<dyn Error>>` - プロジェクトごとの結果（`project_infos`の順）
+pub fn render_projects(
+    project_infos: &[locator::ProjectInfo],
+    options: &GenerateOptions,
+) -> Result<Vec<Result<RenderedProject, String>>, Box<dyn Error>> {
+    for_each_project(project_infos, options, render_project)
+}
+
+/// プロジェクトごとの処理を、`options.jobs`のスレッドで並列に実行する
+fn for_each_project<T, F>(
+    project_infos: &[locator::ProjectInfo],
+    options: &GenerateOptions,
+    process: F,
+) -> Result<Vec<Result<T, String>>, Box<dyn Error>>
+where
+    T: Send,
+    F: Fn(&locator::ProjectInfo, &GenerateOptions) -> Result<T, SendError> + Sync,
+{
+    options.overrides.check_projects(project_infos)?;
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(options.jobs.unwrap_or(0))
+        .build()?;
+    Ok(pool.install(|| {
+        project_infos
+            .par_iter()
+            .map(|project_info| process(project_info, options).map_err(|e| e.to_string()))
+            .collect()
+    }))
+}
+
+/// 解析したプロジェクト
+#[derive(Debug, Default)]
+pub struct ProjectAnalysis {
+    /// 表示するメッセージ
+    pub log: Vec<logging::LogMessage>,
+    /// 警告（解決できなかった型など）
+    pub warnings: Vec<diagnostic::Diagnostic>,
+    /// 対象のファイルのパース結果（宣言順）
+    pub rust_src_data: Vec<parser::RustSrcData>,
+    /// 変換されたPythonの型情報
+    pub python_src_data: analyzer::PythonSrcData,
+    /// 解析に使ったファイル（ビルドバックエンドの設定・Cargo.tomlと、対象外のものを含むすべてのRustのソース）
+    pub source_files: Vec<PathBuf>,
+    /// 対象外のファイルにあるもの・exclude-itemsに一致したものなど、設定で除外した関数・定数・クラスの数
+    pub skipped_items: usize,
+}
+
+/// 1つのプロジェクトについて組み立てたファイル（まだ書き込んでいないもの）
+#[derive(Debug)]
+pub struct RenderedProject {
+    /// プロジェクト情報（コマンドラインの指定を反映したもの）
+    pub project_info: locator::ProjectInfo,
+    /// 解析結果（警告にはパッケージファイルの検証結果も含む）
+    pub analysis: ProjectAnalysis,
+    /// スタブ・パッケージファイル・APIリファレンス（この順）
+    pub files: Vec<generator::StubFile>,
+}
+
+/// 1つのプロジェクトのスタブファイルを生成する
+/// 
+/// # Arguments
+/// 
+/// * `project_info` - プロジェクト情報
+/// * `options` - スタブ生成のオプション
+/// 
+/// # Returns
+/// 
+/// * `Result<ProjectReport, SendError>` - 処理結果
+fn generate_project_stub(
+    project_info: &locator::ProjectInfo,
+    options: &GenerateOptions,
+) -> Result<ProjectReport, SendError> {
+    let rendered = render_project(project_info, options)?;
+    let project_info = &rendered.project_info;
+    let mut report = ProjectReport {
+        log: rendered.analysis.log,
+        warnings: rendered.analysis.warnings,
+        output_path: project_info.output_dir.join(format!("{}.pyi", project_info.module_name)),
+        emitted_items: emitted_items(&rendered.analysis.python_src_data, &project_info.module_name),
+        skipped_items: rendered.analysis.skipped_items,
+        sources: rendered.analysis.python_src_data.sources,
+        source_files: rendered.analysis.source_files,
+        ..Default::default()
+    };
+
+    // 内容が変わらないファイルは書き込まない
+    for file in &rendered.files {
+        let written = file.write().map_err(|e| e.to_string())?;
+        match file.kind {
+            generator::FileKind::Stub => report.written = written,
+            generator::FileKind::PyTyped | generator::FileKind::InitStub => {
+                report.package_files.push((file.path.clone(), written));
+            }
+            generator::FileKind::Docs => report.docs_file = Some((file.path.clone(), written)),
         }
+    }
+    Ok(report)
+}
 
-        // 各Rustソースファイルを処理
-        for rust_src_file in project_info.rust_src_file {
-            if debug {
-                println!("Processing file: {}", rust_src_file.display());
+/// 1つのプロジェクトを解析し、スタブ・パッケージファイル・APIリファレンスの内容を組み立てる
+/// 
+/// ファイルの書き込みと表示はしない。
+/// 
+/// # Arguments
+/// 
+/// * `project_info` - プロジェクト情報
+/// * `options` - スタブ生成のオプション
+/// 
+/// # Returns
+/// 
+/// * `Result<RenderedProject, SendError>` - 組み立てたファイルと解析結果
+pub fn render_project(
+    project_info: &locator::ProjectInfo,
+    options: &GenerateOptions,
+) -> Result<RenderedProject, SendError> {
+    // コマンドラインの指定は[tool.pystubgen]より優先する
+    let project_info = options.overrides.apply(project_info);
+    let config = &project_info.config;
+    let mut analysis = analyze_project(&project_info, options)?;
+    let python_src_data = &analysis.python_src_data;
+
+    let mut files = vec![generator::render_stub(
+        python_src_data,
+        &project_info.output_dir,
+        &project_info.module_name,
+        config,
+    ).map_err(|e| e.to_string())?];
+
+    // 拡張モジュールを含むパッケージのpy.typedと__init__.pyi
+    let package_report = package::render_package_files(&project_info, python_src_data, options.package);
+    files.extend(package_report.files);
+
+    // スタブと同じ解析結果から作るAPIリファレンス
+    if let Some(format) = config.docs {
+        files.push(generator::StubFile {
+            path: project_info.output_dir.join(format!("{}.{}", project_info.module_name, format.extension())),
+            kind: generator::FileKind::Docs,
+            content: docs::render_docs(&ir::build_project_ir(&project_info, &analysis), format),
+        });
+    }
+    analysis.warnings.extend(package_report.warnings);
+    Ok(RenderedProject { project_info, analysis, files })
+}
+
+/// 1つのプロジェクトのRustソースを解析し、Pythonの型情報に変換する
+/// 
+/// # Arguments
+/// 
+/// * `project_info` - プロジェクト情報（コマンドラインの指定を反映したもの）
+/// * `options` - スタブ生成のオプション
+/// 
+/// # Returns
+/// 
+/// * `Result<ProjectAnalysis, SendError>` - 解析結果
+pub fn analyze_project(
+    project_info: &locator::ProjectInfo,
+    options: &GenerateOptions,
+) -> Result<ProjectAnalysis, SendError> {
+    let config = &project_info.config;
+    let mut analysis = ProjectAnalysis::default();
+    let log = &mut analysis.log;
+    log.push(logging::LogMessage::new(logging::Verbosity::Normal, format!("Processing project: {}", project_info.project_name)));
+    let mut details = vec![
+        format!("Output directory: {}", project_info.output_dir.display()),
+        format!("Module name: {}", project_info.python_module),
+        format!("Layout: {}", match project_info.layout {
+            locator::ProjectLayout::Mixed => "mixed Rust/Python",
+            locator::ProjectLayout::PureRust => "pure Rust (top-level module)",
+        }),
+    ];
+    if !project_info.python_packages.is_empty() {
+        details.push(format!("Python packages: {}", project_info.python_packages.join(", ")));
+    }
+    if let Some(python_version) = config.python_version {
+        details.push(format!("Target Python: {}", python_version));
+    }
+    log.extend(details.into_iter().map(|detail| logging::LogMessage::new(logging::Verbosity::Verbose, detail)));
+
+    // 選択されたフィーチャー（[tool.maturin] features・[tool.pystubgen] featuresを含む）とターゲットで#[cfg]を評価する
+    let mut cfg_options = options.cfg.clone();
+    cfg_options.features.extend(project_info.features.iter().cloned());
+    cfg_options.features.extend(config.features.iter().cloned());
+    cfg_options.all_features |= config.all_features;
+    cfg_options.no_default_features |= config.no_default_features;
+    let cargo_toml = std::fs::read_to_string(&project_info.manifest_path)?;
+    let cargo_doc = cargo_toml.parse::<toml_edit::DocumentMut>()?;
+    let cfg = cfg::CfgContext::new(&cargo_doc, &cfg_options);
+
+    // クレートルートからmod宣言をたどり、各モジュールをパース
+    let parsed = if options.expand {
+        match expand::expand_crate_items(&project_info.manifest_path, &cfg_options) {
+            Ok(expanded) => {
+                if !expanded.missing_signatures.is_empty() {
+                    let names: Vec<String> = expanded.missing_signatures.iter().map(|name| format!("`{}`", name)).collect();
+                    analysis.warnings.push(diagnostic::Diagnostic::warning(format!(
+                        "signatures of {} were not found in the expanded source; defaults and `*`/`/` markers may be missing",
+                        names.join(", ")
+                    )));
+                }
+                let modules = modgraph::collect_crate_modules_from_items(&project_info.crate_root, expanded.items, &cfg)
+                    .map_err(|e| e.to_string())?;
+                let mut parsed = ParseOutput { unknown_cfgs: cfg.take_unknown().into_iter().collect(), ..Default::default() };
+                for module in &modules {
+                    parsed.rust_src_data.push(parse_module(module, &mut parsed.log));
+                }
+                parsed
+            }
+            Err(e) => {
+                analysis.warnings.push(diagnostic::Diagnostic::warning(format!(
+                    "macro expansion is unavailable ({}); falling back to plain parsing",
+                    e
+                )));
+                parse_crate(project_info, &cfg, options)?
+            }
+        }
+    } else {
+        parse_crate(project_info, &cfg, options)?
+    };
+    analysis.log.extend(parsed.log);
+    analysis.warnings.extend(parsed.warnings);
+    // 未知のcfgは名前ごとに一度だけ警告する
+    for name in &parsed.unknown_cfgs {
+        analysis.warnings.push(diagnostic::Diagnostic::warning(format!(
+            "cfg `{}` is not known to pystubgen and was evaluated as false",
+            name
+        )));
+    }
+
+    for name in ["pyproject.toml", "setup.py", "CMakeLists.txt"] {
+        let path = project_info.project_root.join(name);
+        if path.is_file() {
+            analysis.source_files.push(path);
+        }
+    }
+    analysis.source_files.push(project_info.manifest_path.clone());
+    for rust_src_data in &parsed.rust_src_data {
+        if !analysis.source_files.contains(&rust_src_data.file) {
+            analysis.source_files.push(rust_src_data.file.clone());
+        }
+    }
+
+    // クレート全体の型定義を集めてから、対象のファイルをPython関数データへ変換する
+    let symbols = symbols::SymbolTable::build(&parsed.rust_src_data);
+    let module_name = &project_info.module_name;
+    let python_src_data = &mut analysis.python_src_data;
+    for rust_src_data in parsed.rust_src_data {
+        let analyzed = analyzer::analyze_rust_src_data(&rust_src_data, &symbols, config);
+        if !config.includes_file(&project_info.project_root, &rust_src_data.file) {
+            analysis.skipped_items += emitted_items(&analyzed, module_name);
+            continue;
+        }
+        python_src_data.merge(analyzed);
+        analysis.rust_src_data.push(rust_src_data);
+    }
+    let before = emitted_items(python_src_data, module_name);
+    python_src_data.retain(|name| config.includes_item(name));
+    analysis.skipped_items += before - emitted_items(python_src_data, module_name);
+    report_unresolved_types(&python_src_data.unresolved_types, &mut analysis.warnings);
+    report_analyzer_warnings(&python_src_data.warnings, &mut analysis.warnings);
+    report_signature_mismatches(&python_src_data.signature_mismatches, &mut analysis.warnings);
+    Ok(analysis)
+}
+
+/// スタブに出力される関数・定数・クラス・型エイリアスの数（モジュールを定義する関数は除く）
+fn emitted_items(python_src_data: &analyzer::PythonSrcData, module_name: &str) -> usize {
+    let functions = python_src_data.functions.iter().filter(|function| function.name != module_name).count();
+    functions + python_src_data.constants.len() + python_src_data.classes.len() + python_src_data.type_aliases.len()
+}
+
+/// 1つのモジュールをパースする
+fn parse_module(module: &modgraph::RustModule, log: &mut Vec<logging::LogMessage>) -> parser::RustSrcData {
+    log.push(logging::LogMessage::new(
+        logging::Verbosity::Debug,
+        format!("Processing module: {} ({})", module.module_path.join("::"), module.file.display()),
+    ));
+    parser::parse_rust_module(module)
+}
+
+/// クレートルートから`mod`宣言をたどってパースする
+/// 
+/// 別ファイルのモジュールは並列にパースし、内容が変わっていないファイルはキャッシュされたパース結果を使う。
+/// 
+/// # Arguments
+/// 
+/// * `project_info` - プロジェクト情報
+/// * `cfg` - `#[cfg]`の評価に使うコンテキスト
+/// * `options` - スタブ生成のオプション
+/// 
+/// # Returns
+/// 
+/// * `Result<ParseOutput, SendError>` - パースされたモジュール（宣言順）
+fn parse_crate(
+    project_info: &locator::ProjectInfo,
+    cfg: &cfg::CfgContext,
+    options: &GenerateOptions,
+) -> Result<ParseOutput, SendError> {
+    let root = modgraph::ModuleFile::root(&project_info.crate_root);
+    if options.no_cache {
+        return parse_module_file(&root, cfg, None);
+    }
+
+    let cache_dir = match &options.cache_dir {
+        Some(cache_dir) => cache_dir.clone(),
+        None => cache::cache_dir(&project_info.manifest_path),
+    };
+    let cache = Mutex::new(cache::StubCache::load(
+        &cache_dir,
+        &project_info.project_name,
+        &cfg.cache_key(),
+    ));
+    let mut parsed = parse_module_file(&root, cfg, Some(&cache))?;
+
+    let cache = cache.into_inner().unwrap();
+    parsed.log.push(logging::LogMessage::new(
+        logging::Verbosity::Debug,
+        format!("Cache: {} hit(s), {} miss(es)", cache.hits, cache.misses),
+    ));
+    if let Err(e) = cache.save() {
+        parsed.warnings.push(diagnostic::Diagnostic::warning(format!("failed to save cache: {}", e)));
+    }
+    Ok(parsed)
+}
+
+/// 1つのファイルをパースし、そこから宣言されている別ファイルのモジュールを並列にたどる
+fn parse_module_file(
+    module_file: &modgraph::ModuleFile,
+    cfg: &cfg::CfgContext,
+    cache: Option<&Mutex<cache::StubCache>>,
+) -> Result<ParseOutput, SendError> {
+    let content = modgraph::read_file(&module_file.file).map_err(|e| e.to_string())?;
+    let hash = cache::content_hash(&content);
+    let mut log = vec![];
+
+    let cached = cache.and_then(|cache| cache.lock().unwrap().get(module_file, &hash));
+    let (entries, unknown_cfgs) = match cached {
+        Some(cached) => {
+            log.push(logging::LogMessage::new(
+                logging::Verbosity::Debug,
+                format!("Cache hit: {} ({})", module_file.module_path.join("::"), module_file.file.display()),
+            ));
+            cached
+        }
+        None => {
+            // ファイルごとに未知のcfgを集め、キャッシュを使うときにも警告できるようにする
+            let file_cfg = cfg.scoped();
+            let items = modgraph::parse_source(&module_file.file, &content).map_err(|e| e.to_string())?;
+            let entries: Vec<cache::CachedEntry> = modgraph::collect_file_modules(module_file, items, &file_cfg)
+                .map_err(|e| e.to_string())?
+                .into_iter()
+                .map(|entry| match entry {
+                    modgraph::ModuleEntry::Module(module) => cache::CachedEntry::Module(parse_module(&module, &mut log)),
+                    modgraph::ModuleEntry::File(child) => cache::CachedEntry::File(child),
+                })
+                .collect();
+            let unknown_cfgs = file_cfg.take_unknown();
+            if let Some(cache) = cache {
+                cache.lock().unwrap().insert(module_file, hash, entries.clone(), unknown_cfgs.clone());
             }
+            (entries, unknown_cfgs)
+        }
+    };
 
-            // Rustソースコードの解析
-            let parsed_rust_data = parser::parse_rust_src_file(&rust_src_file);
-            
-            // Python関数データへの変換
-            let python_function_data = analyzer::analyze_rust_src_data(&parsed_rust_data);
-            
-            // スタブファイルの生成
-            generator::generate_stub(
-                &python_function_data,
-                &project_info.output_dir,
-                &project_info.module_name,
-            ).unwrap();
-        }
-    }
-}
\ No newline at end of file
+    let children: Vec<Result<ParseOutput, SendError>> = entries
+        .into_par_iter()
+        .map(|entry| match entry {
+            cache::CachedEntry::Module(rust_src_data) => Ok(ParseOutput { rust_src_data: vec![rust_src_data], ..Default::default() }),
+            cache::CachedEntry::File(child) => parse_module_file(&child, cfg, cache),
+        })
+        .collect();
+
+    let mut parsed = ParseOutput { log, unknown_cfgs: unknown_cfgs.into_iter().collect(), ..Default::default() };
+    for child in children {
+        parsed.extend(child?);
+    }
+    Ok(parsed)
+}
+
+/// 解決できずに`Any`とした型を、使われていた場所とともに警告にする
+fn report_unresolved_types(unresolved_types: &[analyzer::UnresolvedType], warnings: &mut Vec<diagnostic::Diagnostic>) {
+    let mut names: Vec<&str> = vec![];
+    for unresolved in unresolved_types {
+        if !names.contains(&unresolved.name.as_str()) {
+            names.push(&unresolved.name);
+        }
+    }
+    for name in names {
+        let mut warning = diagnostic::Diagnostic::warning(format!("unresolved type `{}` was replaced with `Any`", name));
+        for unresolved in unresolved_types.iter().filter(|u| u.name == name) {
+            warning = warning.with_location(&unresolved.file, unresolved.line, &unresolved.path);
+        }
+        warnings.push(warning);
+    }
+}
+
+/// 対応していない型・引数のパターンを、該当する場所とともに警告にする
+fn report_analyzer_warnings(analyzer_warnings: &[analyzer::AnalyzerWarning], warnings: &mut Vec<diagnostic::Diagnostic>) {
+    for warning in analyzer_warnings {
+        warnings.push(diagnostic::Diagnostic::warning(&warning.message).with_location(&warning.file, warning.line, &warning.path));
+    }
+}
+
+/// `text_signature`と引数から導いたシグネチャが食い違っている関数を、定義されている場所とともに警告にする
+fn report_signature_mismatches(mismatches: &[analyzer::SignatureMismatch], warnings: &mut Vec<diagnostic::Diagnostic>) {
+    for mismatch in mismatches {
+        warnings.push(
+            diagnostic::Diagnostic::warning(format!(
+                "text_signature of `{}` does not match its arguments: {} (text_signature) vs {}",
+                mismatch.item, mismatch.text_signature, mismatch.derived
+            ))
+            .with_location(&mismatch.file, mismatch.line, &mismatch.item),
+        );
+    }
+}
@@ -3,7 +3,11 @@
 //! このモジュールは、パースされたRustコードをPythonの型情報に変換します。
 //! Rustの型システムからPythonの型ヒントへの変換を行います。
 
+use std::path::PathBuf;
+use crate::stubgen::config::StubConfig;
 use crate::stubgen::parser::*;
+use crate::stubgen::signature::*;
+use crate::stubgen::symbols::*;
 use crate::stubgen::typemap::*;
 
 /// Python関数の型情報を保持する構造体
@@ -11,19 +15,157 @@ use crate::stubgen::typemap::*;
 pub struct PythonFunctionData {
     /// 関数名
     pub name: String,
-    /// 引数のリスト（名前と型のペア）
+    /// 引数のリスト（名前と型のペア。区切りの`*`・`/`は型が空、可変長引数は名前に`*`を含む）
     pub args: Vec<(String, String)>,
+    /// デフォルト値のある引数（名前とPythonの式のペア）
+    pub defaults: Vec<(String, String)>,
     /// 戻り値の型
     pub return_type: String,
     /// ドキュメントコメント
     pub doc: String,
 }
 
+/// モジュール変数・定数の型情報を保持する構造体
+#[derive(Debug, PartialEq, Eq)]
+pub struct PythonConstantData {
+    /// 変数名
+    pub name: String,
+    /// 型（`Final`は含まない）
+    pub ty: String,
+    /// `Final`として宣言するかどうか
+    pub is_final: bool,
+    /// ドキュメントコメント
+    pub doc: String,
+}
+
+/// `type`文で定義する型エイリアスの情報（Python 3.12以降）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PythonTypeAlias {
+    /// エイリアス名
+    pub name: String,
+    /// 型パラメータ（PEP 695）
+    pub params: Vec<String>,
+    /// 展開先の型
+    pub target: String,
+}
+
+/// 解決できずに`Any`として出力した型の情報
+#[derive(Debug, PartialEq, Eq)]
+pub struct UnresolvedType {
+    /// Rustの型名
+    pub name: String,
+    /// 使われていたアイテム名
+    pub item: String,
+    /// 使われていたアイテムのRustのパス（`crate::geometry::centroid`）
+    pub path: String,
+    /// 使われていたファイル
+    pub file: PathBuf,
+    /// 使われていた行（1始まり）
+    pub line: usize,
+}
+
+/// スタブに正しく出力できなかった箇所の警告（対応していない型・引数のパターン）
+#[derive(Debug, PartialEq, Eq)]
+pub struct AnalyzerWarning {
+    /// 警告の内容
+    pub message: String,
+    /// 関数・定数のPython側の名前
+    pub item: String,
+    /// 関数・定数のRustのパス（`crate::geometry::centroid`）
+    pub path: String,
+    /// 該当するファイル
+    pub file: PathBuf,
+    /// 該当する行（1始まり）
+    pub line: usize,
+}
+
+/// `text_signature`と、引数から導いたシグネチャとの食い違い
+#[derive(Debug, PartialEq, Eq)]
+pub struct SignatureMismatch {
+    /// 関数名
+    pub item: String,
+    /// 引数（`#[pyo3(signature)]`があればそれ）から導いたシグネチャ
+    pub derived: String,
+    /// `text_signature`に書かれたシグネチャ
+    pub text_signature: String,
+    /// 定義されているファイル
+    pub file: PathBuf,
+    /// 定義されている行（1始まり）
+    pub line: usize,
+}
+
+/// Pythonに公開される名前が定義されている場所
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ItemSource {
+    /// Python側の名前
+    pub name: String,
+    /// 定義されているファイル
+    pub file: PathBuf,
+    /// 定義されている行（1始まり）
+    pub line: usize,
+}
+
 /// Pythonモジュールの型情報を保持する構造体
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct PythonSrcData {
     /// 関数のリスト
     pub functions: Vec<PythonFunctionData>,
+    /// モジュール変数・定数のリスト
+    pub constants: Vec<PythonConstantData>,
+    /// `#[pyclass]`のクラス名（Python側の名前）のリスト
+    pub classes: Vec<String>,
+    /// 型エイリアスのリスト
+    pub type_aliases: Vec<PythonTypeAlias>,
+    /// 解決できなかった型のリスト
+    pub unresolved_types: Vec<UnresolvedType>,
+    /// 対応していない型・引数のパターンの警告のリスト
+    pub warnings: Vec<AnalyzerWarning>,
+    /// 関数・定数が定義されている場所のリスト
+    pub sources: Vec<ItemSource>,
+    /// `text_signature`が食い違っている関数のリスト
+    pub signature_mismatches: Vec<SignatureMismatch>,
+}
+
+impl PythonSrcData {
+    /// 別ファイルの解析結果を結合する
+    pub fn merge(&mut self, other: PythonSrcData) {
+        self.functions.extend(other.functions);
+        self.constants.extend(other.constants);
+        self.classes.extend(other.classes);
+        // 同じエイリアスは複数のファイルから使われる
+        for alias in other.type_aliases {
+            if !self.type_aliases.iter().any(|existing| existing.name == alias.name) {
+                self.type_aliases.push(alias);
+            }
+        }
+        self.unresolved_types.extend(other.unresolved_types);
+        self.warnings.extend(other.warnings);
+        self.sources.extend(other.sources);
+        self.signature_mismatches.extend(other.signature_mismatches);
+    }
+
+    /// 名前が条件を満たす関数・定数・クラスだけを残す（取り除いた関数・定数の未解決の型・警告・定義場所・シグネチャの食い違いも取り除く）
+    pub fn retain(&mut self, mut keep: impl FnMut(&str) -> bool) {
+        self.functions.retain(|function| keep(&function.name));
+        self.constants.retain(|constant| keep(&constant.name));
+        self.classes.retain(|class| keep(class));
+        let functions = &self.functions;
+        let constants = &self.constants;
+        self.unresolved_types.retain(|unresolved| {
+            functions.iter().any(|function| function.name == unresolved.item)
+                || constants.iter().any(|constant| constant.name == unresolved.item)
+        });
+        self.warnings.retain(|warning| {
+            functions.iter().any(|function| function.name == warning.item)
+                || constants.iter().any(|constant| constant.name == warning.item)
+        });
+        self.sources.retain(|source| {
+            functions.iter().any(|function| function.name == source.name)
+                || constants.iter().any(|constant| constant.name == source.name)
+                || self.classes.contains(&source.name)
+        });
+        self.signature_mismatches.retain(|mismatch| functions.iter().any(|function| function.name == mismatch.item));
+    }
 }
 
 /// Rustソースコードの型情報をPythonの型情報に変換する
@@ -31,17 +173,188 @@ pub struct PythonSrcData {
 /// # Arguments
 /// 
 /// * `rust_src_data` - パースされたRustソースコードの型情報
+/// * `symbols` - クレート全体のシンボルテーブル
+/// * `config` - [tool.pystubgen]の設定（型の上書きと対象のPythonのバージョン）
 /// 
 /// # Returns
 /// 
 /// * `PythonSrcData` - 変換されたPythonの型情報
-pub fn analyze_rust_src_data(rust_src_data: &RustSrcData) -> PythonSrcData {
-    let functions = rust_src_data.functions
-        .iter()
-        .map(analyze_function_data)
-        .collect();
+pub fn analyze_rust_src_data(rust_src_data: &RustSrcData, symbols: &SymbolTable, config: &StubConfig) -> PythonSrcData {
+    let mut mapper = TypeMapper::new(symbols);
+    mapper.set_module(&rust_src_data.module_path);
+    mapper.set_type_map(&config.type_map);
+    mapper.set_python_version(config.python_version);
+    let mut python_src_data = PythonSrcData::default();
+
+    // #[pyfunction]が付いた関数のみがPythonに公開される
+    for function_data in rust_src_data.functions.iter().filter(|f| f.attributes.iter().any(|a| a == "pyfunction")) {
+        let python_function = analyze_function_data(function_data, &mut mapper);
+        record_source(&mut python_src_data, rust_src_data, &python_function.name, function_data.line);
+        if let Some((derived, text_signature)) = check_text_signature(function_data) {
+            python_src_data.signature_mismatches.push(SignatureMismatch {
+                item: python_function.name.clone(),
+                derived,
+                text_signature,
+                file: rust_src_data.file.clone(),
+                line: function_data.line,
+            });
+        }
+        check_arg_patterns(&mut python_src_data, rust_src_data, function_data);
+        check_keyword_args(&mut python_src_data, rust_src_data, function_data, &python_function);
+        python_src_data.functions.push(python_function);
+        collect_unresolved(&mut python_src_data, &mut mapper, rust_src_data, &function_data.name, function_data.line);
+    }
+    for constant_data in &rust_src_data.constants {
+        python_src_data.constants.push(analyze_constant_data(constant_data, &mut mapper));
+        record_source(&mut python_src_data, rust_src_data, &constant_data.name, constant_data.line);
+        collect_unresolved(&mut python_src_data, &mut mapper, rust_src_data, &constant_data.name, constant_data.line);
+    }
+    // シグネチャから参照されるため、#[pyclass]はクラスとして宣言する
+    for type_data in &rust_src_data.types {
+        if let RustTypeKind::PyClass { python_name, .. } = &type_data.kind {
+            python_src_data.classes.push(python_name.clone());
+            record_source(&mut python_src_data, rust_src_data, python_name, type_data.line);
+        }
+    }
+    python_src_data.type_aliases = mapper.take_aliases();
     
-    PythonSrcData { functions }
+    python_src_data
+}
+
+/// Python側の名前とRustで定義されている場所を記録する
+fn record_source(python_src_data: &mut PythonSrcData, rust_src_data: &RustSrcData, name: &str, line: usize) {
+    python_src_data.sources.push(ItemSource {
+        name: name.to_string(),
+        file: rust_src_data.file.clone(),
+        line,
+    });
+}
+
+/// 関数・定数のRustのパス（`crate::geometry::centroid`）
+fn item_path(rust_src_data: &RustSrcData, item: &str) -> String {
+    rust_src_data.module_path.iter().map(String::as_str).chain([item]).collect::<Vec<_>>().join("::")
+}
+
+/// 変換中に解決できなかった型と対応していなかった型を、使われていた場所と一緒に記録する
+fn collect_unresolved(
+    python_src_data: &mut PythonSrcData,
+    mapper: &mut TypeMapper,
+    rust_src_data: &RustSrcData,
+    item: &str,
+    line: usize,
+) {
+    let path = item_path(rust_src_data, item);
+    for name in mapper.take_unresolved() {
+        python_src_data.unresolved_types.push(UnresolvedType {
+            name,
+            item: item.to_string(),
+            path: path.clone(),
+            file: rust_src_data.file.clone(),
+            line,
+        });
+    }
+    for ty in mapper.take_unsupported() {
+        python_src_data.warnings.push(AnalyzerWarning {
+            message: format!("unsupported type `{}` was replaced with `Any`", ty),
+            item: item.to_string(),
+            path: path.clone(),
+            file: rust_src_data.file.clone(),
+            line,
+        });
+    }
+}
+
+/// 名前を持たない引数のパターン（`_`・分割代入など）を、書かれている行と一緒に記録する
+fn check_arg_patterns(python_src_data: &mut PythonSrcData, rust_src_data: &RustSrcData, function_data: &RustFunctionData) {
+    for (i, ((name, ty), pattern)) in function_data.args.iter().zip(&function_data.arg_patterns).enumerate() {
+        let Some(pattern) = pattern else { continue };
+        if syn::parse_str::<syn::Type>(ty).is_ok_and(|ty| is_python_token(&ty)) {
+            continue;
+        }
+        python_src_data.warnings.push(AnalyzerWarning {
+            message: format!("argument pattern `{}` has no name; it was named `{}` in the stub", pattern, name),
+            item: function_data.name.clone(),
+            path: item_path(rust_src_data, &function_data.name),
+            file: rust_src_data.file.clone(),
+            line: function_data.arg_lines.get(i).copied().unwrap_or(function_data.line),
+        });
+    }
+}
+
+/// Pythonのキーワードと同じ名前の引数（`r#from`など）を、スタブでの扱いと一緒に記録する
+fn check_keyword_args(
+    python_src_data: &mut PythonSrcData,
+    rust_src_data: &RustSrcData,
+    function_data: &RustFunctionData,
+    python_function: &PythonFunctionData,
+) {
+    let slash = python_function.args.iter().position(|(arg, _)| arg == "/");
+    // `/`を後ろに移したことで、名前で渡せなくなった通常の引数
+    let positional_before: Vec<String> = function_data.signature.as_deref()
+        .map(parse_rust_signature)
+        .filter(|params| params.iter().any(|param| param.name == "/"))
+        .map(|params| params.into_iter().take_while(|param| param.name != "/").map(|param| param.name).collect())
+        .unwrap_or_default();
+    let affected: Vec<String> = python_function.args[..slash.unwrap_or(0)].iter()
+        .map(|(arg, _)| arg)
+        .filter(|arg| !arg.strip_suffix('_').is_some_and(is_python_keyword) && !positional_before.contains(arg))
+        .map(|arg| format!("`{}`", arg))
+        .collect();
+    for (i, (name, ty)) in function_data.args.iter().enumerate() {
+        if !is_python_keyword(name) || syn::parse_str::<syn::Type>(ty).is_ok_and(|ty| is_python_token(&ty)) {
+            continue;
+        }
+        let renamed = format!("{}_", name);
+        let Some(position) = python_function.args.iter().position(|(arg, _)| *arg == renamed) else { continue };
+        let keyword_only = python_function.args[..position].iter().any(|(arg, _)| arg.starts_with('*'));
+        let mut message = if keyword_only {
+            format!("argument `{}` is a Python keyword; it was renamed to `{}` in the stub, but callers must pass it as `{}`", name, renamed, name)
+        } else {
+            format!("argument `{}` is a Python keyword; it was emitted as positional-only `{}` in the stub", name, renamed)
+        };
+        // `/`の直前の引数の警告に、巻き込まれた引数をまとめる
+        if slash == Some(position + 1) && !affected.is_empty() {
+            message.push_str(&format!(", which also makes {} positional-only", affected.join(", ")));
+        }
+        python_src_data.warnings.push(AnalyzerWarning {
+            message,
+            item: function_data.name.clone(),
+            path: item_path(rust_src_data, &function_data.name),
+            file: rust_src_data.file.clone(),
+            line: function_data.arg_lines.get(i).copied().unwrap_or(function_data.line),
+        });
+    }
+}
+
+/// モジュールに追加される定数の型情報をPythonの型情報に変換する
+/// 
+/// 型が明示されていればそれを使い、なければ値の式から推論する。
+/// 
+/// # Arguments
+/// 
+/// * `constant_data` - パースされた定数の情報
+/// * `mapper` - 型の変換に使うマッパー
+/// 
+/// # Returns
+/// 
+/// * `PythonConstantData` - 変換された定数の型情報
+pub fn analyze_constant_data(constant_data: &RustConstantData, mapper: &mut TypeMapper) -> PythonConstantData {
+    let ty = match &constant_data.ty {
+        Some(ty) => {
+            let rust_type = syn::parse_str::<syn::Type>(ty)
+                .expect("Failed to parse Rust constant type");
+            mapper.map_type(&rust_type)
+        }
+        None => syn::parse_str::<syn::Expr>(&constant_data.value)
+            .map_or("Any".to_string(), |expr| mapper.infer_expr_type(&expr)),
+    };
+
+    PythonConstantData {
+        name: constant_data.name.clone(),
+        ty,
+        is_final: constant_data.is_final,
+        doc: constant_data.doc.clone(),
+    }
 }
 
 /// 個々のRust関数の型情報をPythonの型情報に変換する
@@ -49,42 +362,157 @@ pub fn analyze_rust_src_data(rust_src_data: &RustSrcData) -> PythonSrcData {
 /// # Arguments
 /// 
 /// * `function_data` - パースされたRust関数の型情報
+/// * `mapper` - 型の変換に使うマッパー
 /// 
 /// # Returns
 /// 
 /// * `PythonFunctionData` - 変換されたPython関数の型情報
-pub fn analyze_function_data(function_data: &RustFunctionData) -> PythonFunctionData {
+pub fn analyze_function_data(function_data: &RustFunctionData, mapper: &mut TypeMapper) -> PythonFunctionData {
+    let (args, defaults) = positional_only_keywords(apply_signature(function_data, analyze_function_args(function_data, mapper)));
     PythonFunctionData {
         name: analyze_function_name(function_data),
-        args: analyze_function_args(function_data),
-        return_type: analyze_function_return_type(function_data),
+        args,
+        defaults,
+        return_type: analyze_function_return_type(function_data, mapper),
         doc: analyze_function_doc(function_data),
     }
 }
 
+/// 名前と値（型・デフォルト値）のペアのリスト
+type NamedList = Vec<(String, String)>;
+
+/// シグネチャの指定を引数に反映する
+/// 
+/// `#[pyo3(signature)]`があれば、その順序・区切り・可変長引数・デフォルト値を使う。
+/// なければ`text_signature`のデフォルト値だけを使う。
+/// 
+/// # Arguments
+/// 
+/// * `function_data` - パースされたRust関数の型情報
+/// * `args` - Rustの引数から変換した引数のリスト
+/// 
+/// # Returns
+/// 
+/// * `(NamedList, NamedList)` - 引数のリストと、デフォルト値のある引数のリスト
+fn apply_signature(function_data: &RustFunctionData, args: NamedList) -> (NamedList, NamedList) {
+    if let Some(signature) = &function_data.signature {
+        let params = parse_rust_signature(signature);
+        let signature_args = params.iter().map(|param| {
+            let ty = if param.is_marker() {
+                String::new()
+            } else if param.name.starts_with('*') {
+                // 可変長引数の要素の型はRustの型からは分からない
+                "Any".to_string()
+            } else {
+                args.iter().find(|(name, _)| *name == param.name).map_or("Any".to_string(), |(_, ty)| ty.clone())
+            };
+            (param.name.clone(), ty)
+        }).collect();
+        return (signature_args, param_defaults(&params));
+    }
+
+    let defaults = function_data.text_signature.as_deref()
+        .and_then(parse_text_signature)
+        .map(|params| param_defaults(&params))
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|(name, _)| args.iter().any(|(arg, _)| arg == name))
+        .collect();
+    (args, defaults)
+}
+
+/// Pythonのキーワードと同じ名前の引数を、スタブに書ける名前にして位置専用にする
+/// 
+/// PyO3は`r#from`を`from`という名前で受け取るが、スタブにはその名前を書けない。
+/// 末尾に`_`を付け、名前で渡せないことを示すため`/`より前に置く（`*`より後の引数は名前を変えるだけ）。
+/// `/`より前にある通常の引数も位置専用になるため、`check_keyword_args`で警告に含める。
+/// 
+/// # Arguments
+/// 
+/// * `(args, defaults)` - 引数のリストと、デフォルト値のある引数のリスト
+/// 
+/// # Returns
+/// 
+/// * `(NamedList, NamedList)` - 名前を変えた引数のリストと、デフォルト値のある引数のリスト
+fn positional_only_keywords((args, defaults): (NamedList, NamedList)) -> (NamedList, NamedList) {
+    let rename = |name: String| {
+        let bare = name.trim_start_matches('*');
+        if is_python_keyword(bare) { format!("{}_", name) } else { name }
+    };
+    let keyword_only = args.iter().position(|(name, _)| name.starts_with('*')).unwrap_or(args.len());
+    let last_keyword = args[..keyword_only].iter().rposition(|(name, _)| is_python_keyword(name));
+    let mut args: NamedList = args.into_iter().map(|(name, ty)| (rename(name), ty)).collect();
+    let defaults = defaults.into_iter().map(|(name, default)| (rename(name), default)).collect();
+
+    if let Some(index) = last_keyword {
+        match args.iter().position(|(name, _)| name == "/") {
+            Some(slash) if slash > index => {}
+            Some(slash) => {
+                args.remove(slash);
+                args.insert(index, ("/".to_string(), String::new()));
+            }
+            None => args.insert(index + 1, ("/".to_string(), String::new())),
+        }
+    }
+    (args, defaults)
+}
+
+/// デフォルト値のある引数を取り出す
+fn param_defaults(params: &[SignatureParam]) -> Vec<(String, String)> {
+    params.iter()
+        .filter_map(|param| param.default.clone().map(|default| (param.name.clone(), default)))
+        .collect()
+}
+
+/// `text_signature`が、引数（`#[pyo3(signature)]`があればそれ）から導いたシグネチャと食い違っていないか調べる
+/// 
+/// # Arguments
+/// 
+/// * `function_data` - パースされたRust関数の型情報
+/// 
+/// # Returns
+/// 
+/// * `Option<(String, String)>` - 食い違っていれば、導いたシグネチャと`text_signature`
+fn check_text_signature(function_data: &RustFunctionData) -> Option<(String, String)> {
+    let text_params = parse_text_signature(function_data.text_signature.as_deref()?)?;
+    let derived = match &function_data.signature {
+        Some(signature) => parse_rust_signature(signature),
+        None => function_data.args.iter()
+            .filter(|(_, ty)| syn::parse_str::<syn::Type>(ty).is_ok_and(|ty| !is_python_token(&ty)))
+            .map(|(name, _)| SignatureParam { name: name.clone(), default: None })
+            .collect(),
+    };
+    (!same_params(&derived, &text_params)).then(|| (format_params(&derived), format_params(&text_params)))
+}
+
 /// 関数名を取得する
 fn analyze_function_name(function_data: &RustFunctionData) -> String {
     function_data.name.clone()
 }
 
 /// 関数の引数リストをPythonの型情報に変換する
-fn analyze_function_args(function_data: &RustFunctionData) -> Vec<(String, String)> {
+/// 
+/// `Python<'py>`はPyO3が渡すトークンのため、Python側の引数には現れない。
+fn analyze_function_args(function_data: &RustFunctionData, mapper: &mut TypeMapper) -> Vec<(String, String)> {
     function_data.args
         .iter()
-        .map(|(name, ty)| {
+        .filter_map(|(name, ty)| {
             let rust_type = syn::parse_str::<syn::Type>(ty)
                 .expect("Failed to parse Rust type");
-            let python_type = map_type(&rust_type);
-            (name.clone(), python_type)
+            if is_python_token(&rust_type) {
+                return None;
+            }
+            let python_type = mapper.map_type(&rust_type);
+            Some((name.clone(), python_type))
         })
         .collect()
 }
 
 /// 関数の戻り値の型をPythonの型情報に変換する
-fn analyze_function_return_type(function_data: &RustFunctionData) -> String {
+fn analyze_function_return_type(function_data: &RustFunctionData, mapper: &mut TypeMapper) -> String {
     let rust_type = syn::parse_str::<syn::Type>(&function_data.return_type)
         .expect("Failed to parse Rust return type");
-    map_type(&rust_type)
+    mapper.map_type(&rust_type)
 }
 
 /// 関数のドキュメントコメントを取得する
@@ -109,14 +537,14 @@ mod tests {
     #[test]
     fn test_analyze_function_name() {
         let function_data = parse_function_data(&syn::parse_str(TEST_CODE).unwrap());
-        let python_function_data = analyze_function_data(&function_data);
+        let python_function_data = analyze_function_data(&function_data, &mut TypeMapper::new(&SymbolTable::default()));
         assert_eq!(python_function_data.name, "add");
     }
 
     #[test]
     fn test_analyze_function_args() {
         let function_data = parse_function_data(&syn::parse_str(TEST_CODE).unwrap());
-        let python_function_data = analyze_function_data(&function_data);
+        let python_function_data = analyze_function_data(&function_data, &mut TypeMapper::new(&SymbolTable::default()));
         assert_eq!(
             python_function_data.args,
             vec![
@@ -129,14 +557,203 @@ mod tests {
     #[test]
     fn test_analyze_function_return_type() {
         let function_data = parse_function_data(&syn::parse_str(TEST_CODE).unwrap());
-        let python_function_data = analyze_function_data(&function_data);
+        let python_function_data = analyze_function_data(&function_data, &mut TypeMapper::new(&SymbolTable::default()));
         assert_eq!(python_function_data.return_type, "list[int]");
     }
 
     #[test]
     fn test_analyze_function_doc() {
         let function_data = parse_function_data(&syn::parse_str(TEST_CODE).unwrap());
-        let python_function_data = analyze_function_data(&function_data);
+        let python_function_data = analyze_function_data(&function_data, &mut TypeMapper::new(&SymbolTable::default()));
         assert_eq!(python_function_data.doc, " testcode add function second line");
     }
+
+    #[test]
+    fn test_analyze_function_python_token() {
+        // Python<'py>はPyO3が渡すため、スタブの引数には出力しない
+        let function_data = parse_function_data(&syn::parse_str(r#"
+            #[pyfunction]
+            fn now(py: Python<'_>, tz: &str) -> String { todo!() }
+        "#).unwrap());
+        let python_function_data = analyze_function_data(&function_data, &mut TypeMapper::new(&SymbolTable::default()));
+        assert_eq!(python_function_data.args, vec![("tz".to_string(), "str".to_string())]);
+    }
+
+    #[test]
+    fn test_analyze_rust_src_data() {
+        let file: syn::File = syn::parse_str(r#"
+            #[pyclass]
+            struct Point { x: f64 }
+
+            struct Internal;
+
+            type Points = Vec<Point>;
+
+            #[pyfunction]
+            fn centroid(py: Python<'_>, points: Points) -> PyResult<Point> {
+                todo!()
+            }
+
+            #[pyfunction]
+            fn leak(value: Internal) -> i32 {
+                0
+            }
+
+            fn helper(value: Internal) {}
+        "#).unwrap();
+        let rust_src_data = RustSrcData {
+            file: PathBuf::from("lib.rs"),
+            module_path: vec!["crate".to_string()],
+            functions: file.items.iter().filter_map(|item| match item {
+                syn::Item::Fn(item_fn) => Some(parse_function_data(item_fn)),
+                _ => None,
+            }).collect(),
+            constants: vec![],
+            types: file.items.iter().filter_map(parse_type_data).collect(),
+            imports: vec![],
+        };
+        let symbols = SymbolTable::build([&rust_src_data]);
+        let python_src_data = analyze_rust_src_data(&rust_src_data, &symbols, &StubConfig::default());
+
+        // #[pyfunction]の付いていないhelperは出力しない
+        assert_eq!(python_src_data.functions.len(), 2);
+        assert_eq!(python_src_data.classes, vec!["Point"]);
+        assert_eq!(python_src_data.functions[0].args, vec![("points".to_string(), "list[Point]".to_string())]);
+        assert_eq!(python_src_data.functions[0].return_type, "Point");
+        assert_eq!(python_src_data.functions[1].args, vec![("value".to_string(), "Any".to_string())]);
+        assert_eq!(python_src_data.unresolved_types, vec![UnresolvedType {
+            name: "Internal".to_string(),
+            item: "leak".to_string(),
+            path: "crate::leak".to_string(),
+            file: PathBuf::from("lib.rs"),
+            line: 15,
+        }]);
+        assert!(python_src_data.warnings.is_empty());
+        let sources: Vec<(&str, usize)> = python_src_data.sources.iter()
+            .map(|source| (source.name.as_str(), source.line))
+            .collect();
+        assert_eq!(sources, vec![("centroid", 10), ("leak", 15), ("Point", 3)]);
+    }
+
+    #[test]
+    fn test_analyze_warnings() {
+        let file: syn::File = syn::parse_str(r#"
+            #[pyfunction]
+            fn greet(
+                name: impl Into<String>,
+                (x, y): (i32, i32),
+            ) -> String {
+                todo!()
+            }
+            #[pyfunction]
+            fn copy(
+                r#from: &str,
+                to: &str,
+            ) {}
+            #[pyfunction]
+            #[pyo3(signature = (src, *, r#in = 0))]
+            fn find(src: &str, r#in: usize) {}
+            #[pyfunction]
+            fn shift(x: i32, r#from: i32, y: i32) {}
+        "#).unwrap();
+        let rust_src_data = RustSrcData {
+            file: PathBuf::from("src/greeting.rs"),
+            module_path: vec!["crate".to_string(), "greeting".to_string()],
+            functions: file.items.iter().filter_map(|item| match item {
+                syn::Item::Fn(item_fn) => Some(parse_function_data(item_fn)),
+                _ => None,
+            }).collect(),
+            ..Default::default()
+        };
+        let mut python_src_data = analyze_rust_src_data(&rust_src_data, &SymbolTable::default(), &StubConfig::default());
+        let warnings: Vec<(&str, &str, usize)> = python_src_data.warnings.iter()
+            .map(|warning| (warning.message.as_str(), warning.path.as_str(), warning.line))
+            .collect();
+        assert_eq!(warnings, vec![
+            ("argument pattern `(x , y)` has no name; it was named `arg1` in the stub", "crate::greeting::greet", 5),
+            ("unsupported type `impl Into<String>` was replaced with `Any`", "crate::greeting::greet", 3),
+            ("argument `from` is a Python keyword; it was emitted as positional-only `from_` in the stub", "crate::greeting::copy", 11),
+            ("argument `in` is a Python keyword; it was renamed to `in_` in the stub, but callers must pass it as `in`", "crate::greeting::find", 16),
+            ("argument `from` is a Python keyword; it was emitted as positional-only `from_` in the stub, which also makes `x` positional-only", "crate::greeting::shift", 18),
+        ]);
+
+        // キーワードと同じ名前の引数は、名前を変えて位置専用にする（`*`より後は名前を変えるだけ）
+        let args = |name: &str| -> Vec<(&str, &str)> {
+            let function = python_src_data.functions.iter().find(|function| function.name == name).unwrap();
+            function.args.iter().map(|(name, ty)| (name.as_str(), ty.as_str())).collect()
+        };
+        assert_eq!(args("copy"), vec![("from_", "str"), ("/", ""), ("to", "str")]);
+        assert_eq!(args("find"), vec![("src", "str"), ("*", ""), ("in_", "int")]);
+        // 前にある通常の引数も位置専用になる
+        assert_eq!(args("shift"), vec![("x", "int"), ("from_", "int"), ("/", ""), ("y", "int")]);
+        assert_eq!(python_src_data.functions[2].defaults, vec![("in_".to_string(), "0".to_string())]);
+
+        // 除外した関数の警告は取り除く
+        python_src_data.retain(|name| name == "copy");
+        let items: Vec<&str> = python_src_data.warnings.iter().map(|warning| warning.item.as_str()).collect();
+        assert_eq!(items, vec!["copy"]);
+    }
+
+    #[test]
+    fn test_analyze_function_signature() {
+        let symbols = SymbolTable::default();
+        let analyze = |code: &str| {
+            let function_data = parse_function_data(&syn::parse_str(code).unwrap());
+            let python_function_data = analyze_function_data(&function_data, &mut TypeMapper::new(&symbols));
+            (python_function_data, check_text_signature(&function_data))
+        };
+
+        let (with_signature, mismatch) = analyze(r#"
+            #[pyfunction]
+            #[pyo3(signature = (sep, *parts, limit = None), text_signature = "(sep, *parts, limit=None)")]
+            fn join(py: Python<'_>, sep: &str, parts: &Bound<'_, PyTuple>, limit: Option<usize>) -> String { todo!() }
+        "#);
+        assert_eq!(with_signature.args, vec![
+            ("sep".to_string(), "str".to_string()),
+            ("*parts".to_string(), "Any".to_string()),
+            ("limit".to_string(), "int | None".to_string()),
+        ]);
+        assert_eq!(with_signature.defaults, vec![("limit".to_string(), "None".to_string())]);
+        assert_eq!(mismatch, None);
+
+        let (from_text_signature, mismatch) = analyze(r#"
+            #[pyfunction]
+            #[pyo3(text_signature = "(a, b=2)")]
+            fn scale(a: f64, b: f64) -> f64 { a * b }
+        "#);
+        assert_eq!(from_text_signature.defaults, vec![("b".to_string(), "2".to_string())]);
+        assert_eq!(mismatch, Some(("(a, b)".to_string(), "(a, b=2)".to_string())));
+
+        // キーワードと同じ名前の引数より後ろに`/`を移す
+        let (keyword, _) = analyze(r#"
+            #[pyfunction]
+            #[pyo3(signature = (a, /, r#from, b = 1))]
+            fn span(a: i32, r#from: i32, b: i32) -> i32 { a + r#from + b }
+        "#);
+        let names: Vec<&str> = keyword.args.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["a", "from_", "/", "b"]);
+    }
+
+    #[test]
+    fn test_analyze_constant_data() {
+        let symbols = SymbolTable::default();
+        let mut mapper = TypeMapper::new(&symbols);
+        let version = analyze_constant_data(&RustConstantData {
+            name: "__version__".to_string(),
+            value: r#"env ! ("CARGO_PKG_VERSION")"#.to_string(),
+            ..Default::default()
+        }, &mut mapper);
+        assert_eq!(version.ty, "str");
+        assert!(!version.is_final);
+
+        let factor = analyze_constant_data(&RustConstantData {
+            name: "FACTOR".to_string(),
+            ty: Some("u32".to_string()),
+            value: "3".to_string(),
+            is_final: true,
+            ..Default::default()
+        }, &mut mapper);
+        assert_eq!(factor.ty, "int");
+        assert!(factor.is_final);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,570 @@
+//! ビルドバックエンド設定モジュール
+//! 
+//! pyproject.tomlの`[build-system]`からPyO3の拡張モジュールをビルドするバックエンドを判定し、
+//! 拡張モジュールの名前、クレートのCargo.toml、Pythonパッケージの置き場所を読み取ります。
+//! maturinのほか、setuptools-rust（`[tool.setuptools-rust]`または`setup.py`）と
+//! scikit-build-core（CMakeLists.txtのCorrosion）に対応します。
+
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use toml_edit::DocumentMut;
+use crate::stubgen::locator::get_module_name;
+
+/// 拡張モジュールをビルドするバックエンド
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BuildBackend {
+    /// `build-backend = "maturin"`
+    #[default]
+    Maturin,
+    /// setuptoolsとsetuptools-rust
+    SetuptoolsRust,
+    /// scikit-build-coreとCorrosion
+    ScikitBuild,
+}
+
+/// バックエンドの設定から読み取った拡張モジュール
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExtensionModule {
+    /// 拡張モジュールの完全な名前（未指定ならCargo.tomlのライブラリ名）
+    pub python_module: Option<String>,
+    /// Cargo.tomlのパス
+    pub manifest_path: PathBuf,
+    /// Pythonパッケージ名とそのディレクトリの対応（`""`はパッケージのルート）
+    pub package_dirs: Vec<(String, PathBuf)>,
+    /// ビルド時に有効にするフィーチャー
+    pub features: Vec<String>,
+    /// 同梱されるPythonパッケージ
+    pub python_packages: Vec<String>,
+}
+
+impl ExtensionModule {
+    /// Pythonパッケージのディレクトリを求める
+    /// 
+    /// setuptoolsの`package_dir`と同じく、最も長く一致するパッケージのディレクトリに残りの要素をつなげる。
+    /// 
+    /// # Arguments
+    /// 
+    /// * `package` - ドット区切りのパッケージ名（`""`はルート）
+    /// 
+    /// # Returns
+    /// 
+    /// * `Option<PathBuf>` - パッケージのディレクトリ（対応するものがなければ`None`）
+    pub fn package_dir(&self, package: &str) -> Option<PathBuf> {
+        let segments: Vec<&str> = package.split('.').filter(|segment| !segment.is_empty()).collect();
+        (0..=segments.len()).rev().find_map(|length| {
+            let prefix = segments[..length].join(".");
+            let (_, dir) = self.package_dirs.iter().find(|(name, _)| *name == prefix)?;
+            Some(segments[length..].iter().fold(dir.clone(), |dir, segment| dir.join(segment)))
+        })
+    }
+}
+
+/// pyproject.tomlと周辺のファイルから、PyO3の拡張モジュールをビルドするバックエンドを判定する
+/// 
+/// # Arguments
+/// 
+/// * `pyproject_doc` - pyproject.tomlのDocumentMut（`setup.py`だけのプロジェクトでは空）
+/// * `project_root` - pyproject.tomlのあるディレクトリ
+/// 
+/// # Returns
+/// 
+/// * `Option<BuildBackend>` - 拡張モジュールをビルドするバックエンド（Rustを使わないプロジェクトは`None`）
+pub fn detect_backend(pyproject_doc: &DocumentMut, project_root: &Path) -> Option<BuildBackend> {
+    let build_backend = pyproject_doc
+        .get("build-system")
+        .and_then(|build_system| build_system.get("build-backend"))
+        .and_then(|build_backend| build_backend.as_str());
+    match build_backend {
+        Some("maturin") => Some(BuildBackend::Maturin),
+        Some(backend) if backend.starts_with("scikit_build_core") => {
+            let cmake_lists = std::fs::read_to_string(cmake_source_dir(pyproject_doc, project_root).join("CMakeLists.txt"));
+            cmake_lists
+                .is_ok_and(|content| !cmake_commands(&content, "corrosion_import_crate").is_empty())
+                .then_some(BuildBackend::ScikitBuild)
+        }
+        // build-backendがないプロジェクトはsetuptoolsでビルドされる
+        Some(backend) if !backend.starts_with("setuptools") => None,
+        _ => {
+            let in_pyproject = setuptools_rust_table(pyproject_doc).is_some_and(|table| table.get("ext-modules").is_some());
+            let in_setup_py = std::fs::read_to_string(project_root.join("setup.py"))
+                .is_ok_and(|content| !python_calls(&content, "RustExtension").is_empty());
+            (in_pyproject || in_setup_py).then_some(BuildBackend::SetuptoolsRust)
+        }
+    }
+}
+
+/// バックエンドの設定から拡張モジュールを読み取る
+/// 
+/// # Arguments
+/// 
+/// * `backend` - ビルドバックエンド
+/// * `pyproject_doc` - pyproject.tomlのDocumentMut
+/// * `project_root` - pyproject.tomlのあるディレクトリ
+/// 
+/// # Returns
+/// 
+/// * `Result<Vec<ExtensionModule>, Box<dyn Error>>` - 拡張モジュール（設定に書かれた順）
+pub fn extension_modules(
+    backend: BuildBackend,
+    pyproject_doc: &DocumentMut,
+    project_root: &Path,
+) -> Result<Vec<ExtensionModule>, Box<dyn Error>> {
+    match backend {
+        BuildBackend::Maturin => Ok(vec![maturin_extension(pyproject_doc, project_root)]),
+        BuildBackend::SetuptoolsRust => setuptools_rust_extensions(pyproject_doc, project_root),
+        BuildBackend::ScikitBuild => scikit_build_extensions(pyproject_doc, project_root),
+    }
+}
+
+/// `[tool.maturin]`の設定を読み取る
+fn maturin_extension(pyproject_doc: &DocumentMut, project_root: &Path) -> ExtensionModule {
+    let maturin = pyproject_doc.get("tool").and_then(|tool| tool.get("maturin"));
+    let get_str = |key: &str| maturin.and_then(|maturin| maturin.get(key)).and_then(|value| value.as_str());
+    // python-sourceの既定はpyproject.tomlのあるディレクトリ
+    let python_source = project_root.join(get_str("python-source").unwrap_or_default());
+    ExtensionModule {
+        python_module: get_str("module-name").map(str::to_string),
+        manifest_path: project_root.join(get_str("manifest-path").unwrap_or("Cargo.toml")),
+        package_dirs: vec![(String::new(), python_source)],
+        features: get_str_array(maturin.and_then(|maturin| maturin.get("features"))),
+        python_packages: get_str_array(maturin.and_then(|maturin| maturin.get("python-packages"))),
+    }
+}
+
+/// `[tool.setuptools-rust]`の`ext-modules`と`setup.py`の`RustExtension(...)`を読み取る
+fn setuptools_rust_extensions(pyproject_doc: &DocumentMut, project_root: &Path) -> Result<Vec<ExtensionModule>, Box<dyn Error>> {
+    let setup_py = std::fs::read_to_string(project_root.join("setup.py")).unwrap_or_default();
+    let package_dirs = setuptools_package_dirs(pyproject_doc, &setup_py, project_root);
+    let mut extensions = vec![];
+
+    let ext_modules = setuptools_rust_table(pyproject_doc)
+        .and_then(|table| table.get("ext-modules"))
+        .and_then(|ext_modules| ext_modules.as_array_of_tables());
+    for ext_module in ext_modules.into_iter().flatten() {
+        let target = ext_module
+            .get("target")
+            .and_then(|target| target.as_str())
+            .ok_or("[[tool.setuptools-rust.ext-modules]] entry has no `target`")?;
+        let path = ext_module.get("path").and_then(|path| path.as_str()).unwrap_or("Cargo.toml");
+        extensions.push(ExtensionModule {
+            python_module: Some(target.to_string()),
+            manifest_path: project_root.join(path),
+            package_dirs: package_dirs.clone(),
+            features: get_str_array(ext_module.get("features")),
+            python_packages: vec![],
+        });
+    }
+
+    for call in python_calls(&setup_py, "RustExtension") {
+        let args = split_python_args(call);
+        let argument = |position: usize, key: &str| {
+            args.iter()
+                .find_map(|arg| arg.split_once('=').filter(|(name, _)| name.trim() == key).map(|(_, value)| value.trim()))
+                .or_else(|| args.get(position).filter(|arg| !is_keyword_arg(arg)).copied())
+        };
+        // `target`が辞書のもの（バイナリ）は拡張モジュールではない
+        let Some(target) = argument(0, "target").and_then(python_string) else { continue };
+        let path = argument(1, "path").and_then(python_string).unwrap_or_else(|| "Cargo.toml".to_string());
+        extensions.push(ExtensionModule {
+            python_module: Some(target),
+            manifest_path: project_root.join(path),
+            package_dirs: package_dirs.clone(),
+            features: argument(4, "features").map(python_string_list).unwrap_or_default(),
+            python_packages: vec![],
+        });
+    }
+    Ok(extensions)
+}
+
+/// setuptoolsのパッケージディレクトリの設定を読み取る
+/// 
+/// `[tool.setuptools] package-dir`、`[tool.setuptools.packages.find] where`、`setup(package_dir=...)`の順に探し、
+/// どれもなければ`src`レイアウトかフラットレイアウトかを判定する。
+fn setuptools_package_dirs(pyproject_doc: &DocumentMut, setup_py: &str, project_root: &Path) -> Vec<(String, PathBuf)> {
+    let setuptools = pyproject_doc.get("tool").and_then(|tool| tool.get("setuptools"));
+    let mut package_dirs: Vec<(String, PathBuf)> = vec![];
+
+    if let Some(package_dir) = setuptools.and_then(|setuptools| setuptools.get("package-dir")).and_then(|dir| dir.as_table_like()) {
+        package_dirs.extend(
+            package_dir
+                .iter()
+                .filter_map(|(name, dir)| Some((name.to_string(), project_root.join(dir.as_str()?)))),
+        );
+    }
+    let find_where = setuptools
+        .and_then(|setuptools| setuptools.get("packages"))
+        .and_then(|packages| packages.get("find"))
+        .and_then(|find| find.get("where"));
+    if let Some(dir) = get_str_array(find_where).first() {
+        package_dirs.push((String::new(), project_root.join(dir)));
+    }
+    for call in python_calls(setup_py, "setup") {
+        let package_dir = split_python_args(call)
+            .into_iter()
+            .find_map(|arg| arg.split_once('=').filter(|(name, _)| name.trim() == "package_dir").map(|(_, value)| value.trim()));
+        if let Some(dict) = package_dir.and_then(|dict| dict.strip_prefix('{')).and_then(|dict| dict.strip_suffix('}')) {
+            for entry in split_python_args(dict) {
+                let Some((name, dir)) = entry.split_once(':') else { continue };
+                if let (Some(name), Some(dir)) = (python_string(name.trim()), python_string(dir.trim())) {
+                    package_dirs.push((name, project_root.join(dir)));
+                }
+            }
+        }
+    }
+
+    if !package_dirs.iter().any(|(name, _)| name.is_empty()) {
+        let src = project_root.join("src");
+        package_dirs.push((String::new(), if src.is_dir() { src } else { project_root.to_path_buf() }));
+    }
+    package_dirs
+}
+
+/// pyproject.tomlの`[tool.setuptools-rust]`テーブル
+fn setuptools_rust_table(pyproject_doc: &DocumentMut) -> Option<&toml_edit::Item> {
+    pyproject_doc.get("tool").and_then(|tool| tool.get("setuptools-rust"))
+}
+
+/// CMakeLists.txtの`corrosion_import_crate(...)`と`install(TARGETS ... DESTINATION ...)`を読み取る
+/// 
+/// 拡張モジュールの名前はクレートのライブラリ名で、インストール先のディレクトリがパッケージになる。
+fn scikit_build_extensions(pyproject_doc: &DocumentMut, project_root: &Path) -> Result<Vec<ExtensionModule>, Box<dyn Error>> {
+    let source_dir = cmake_source_dir(pyproject_doc, project_root);
+    let cmake_lists_path = source_dir.join("CMakeLists.txt");
+    let cmake_lists = std::fs::read_to_string(&cmake_lists_path)
+        .map_err(|e| format!("failed to read {}: {}", cmake_lists_path.display(), e))?;
+    let project_name = pyproject_doc
+        .get("project")
+        .and_then(|project| project.get("name"))
+        .and_then(|name| name.as_str())
+        .unwrap_or_default();
+    let package_dirs = scikit_build_package_dirs(pyproject_doc, project_root, project_name);
+
+    // ターゲット名からインストール先への対応
+    let mut destinations = vec![];
+    for args in cmake_commands(&cmake_lists, "install") {
+        let targets = cmake_keyword_values(&args, "TARGETS");
+        if let Some(destination) = cmake_keyword_values(&args, "DESTINATION").first() {
+            let destination = destination.replace("${SKBUILD_PROJECT_NAME}", &project_name.replace('-', "_"));
+            destinations.extend(targets.into_iter().map(|target| (target, destination.clone())));
+        }
+    }
+
+    let mut extensions = vec![];
+    for args in cmake_commands(&cmake_lists, "corrosion_import_crate") {
+        let Some(manifest) = cmake_keyword_values(&args, "MANIFEST_PATH").into_iter().next() else {
+            return Err(format!("{}: corrosion_import_crate has no MANIFEST_PATH", cmake_lists_path.display()).into());
+        };
+        let manifest = ["${CMAKE_CURRENT_SOURCE_DIR}/", "${CMAKE_SOURCE_DIR}/", "${PROJECT_SOURCE_DIR}/"]
+            .iter()
+            .fold(manifest, |manifest, prefix| manifest.trim_start_matches(prefix).to_string());
+        let manifest_path = source_dir.join(manifest);
+
+        // Corrosionはクレートのライブラリ名のターゲットを作る
+        let cargo_doc = std::fs::read_to_string(&manifest_path)
+            .ok()
+            .and_then(|content| content.parse::<DocumentMut>().ok())
+            .filter(|doc| doc.get("package").is_some());
+        let python_module = cargo_doc.map(|doc| {
+            let module_name = get_module_name(&doc);
+            match destinations.iter().find(|(target, _)| *target == module_name) {
+                Some((_, destination)) if !destination.trim_matches(['.', '/']).is_empty() => {
+                    format!("{}.{}", destination.trim_matches(['.', '/']).replace('/', "."), module_name)
+                }
+                _ => module_name,
+            }
+        });
+        extensions.push(ExtensionModule {
+            python_module,
+            manifest_path,
+            package_dirs: package_dirs.clone(),
+            features: cmake_keyword_values(&args, "FEATURES"),
+            python_packages: vec![],
+        });
+    }
+    Ok(extensions)
+}
+
+/// scikit-build-coreのパッケージディレクトリを読み取る
+/// 
+/// `wheel.packages`がなければ`src/<name>`、`python/<name>`、`<name>`の順に探す。
+fn scikit_build_package_dirs(pyproject_doc: &DocumentMut, project_root: &Path, project_name: &str) -> Vec<(String, PathBuf)> {
+    let packages = pyproject_doc
+        .get("tool")
+        .and_then(|tool| tool.get("scikit-build"))
+        .and_then(|scikit_build| scikit_build.get("wheel"))
+        .and_then(|wheel| wheel.get("packages"));
+    let mut package_dirs: Vec<(String, PathBuf)> = get_str_array(packages)
+        .iter()
+        .map(|package| project_root.join(package))
+        .filter_map(|dir| Some((dir.file_name()?.to_str()?.to_string(), dir)))
+        .collect();
+    if package_dirs.is_empty() {
+        let name = project_name.replace(['-', '.'], "_").to_lowercase();
+        let found = ["src", "python", ""]
+            .iter()
+            .map(|parent| project_root.join(parent).join(&name))
+            .find(|dir| dir.is_dir());
+        package_dirs.extend(found.map(|dir| (name, dir)));
+    }
+    package_dirs.push((String::new(), project_root.to_path_buf()));
+    package_dirs
+}
+
+/// scikit-build-coreの`cmake.source-dir`（既定はpyproject.tomlのあるディレクトリ）
+fn cmake_source_dir(pyproject_doc: &DocumentMut, project_root: &Path) -> PathBuf {
+    let source_dir = pyproject_doc
+        .get("tool")
+        .and_then(|tool| tool.get("scikit-build"))
+        .and_then(|scikit_build| scikit_build.get("cmake"))
+        .and_then(|cmake| cmake.get("source-dir"))
+        .and_then(|source_dir| source_dir.as_str())
+        .unwrap_or(".");
+    project_root.join(source_dir)
+}
+
+/// 文字列の配列を読み取る
+fn get_str_array(item: Option<&toml_edit::Item>) -> Vec<String> {
+    item.and_then(|item| item.as_array())
+        .map(|array| array.iter().filter_map(|value| value.as_str()).map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// CMakeのコマンド呼び出しの引数を集める（コメントは取り除き、コマンド名の大文字小文字は区別しない）
+/// 
+/// 小文字化でバイト位置がずれないよう、ASCIIの範囲だけを小文字にする。
+fn cmake_commands(content: &str, command: &str) -> Vec<Vec<String>> {
+    let content: String = content
+        .lines()
+        .map(|line| line.split_once('#').map_or(line, |(code, _)| code))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let lower = content.to_ascii_lowercase();
+    let mut commands = vec![];
+    let mut rest = 0;
+    while let Some(found) = lower[rest..].find(command) {
+        let start = rest + found;
+        rest = start + command.len();
+        let preceded = lower[..start].chars().next_back().is_some_and(|c| c.is_alphanumeric() || c == '_');
+        let after = lower[rest..].trim_start();
+        if preceded || !after.starts_with('(') {
+            continue;
+        }
+        let open = rest + (lower[rest..].len() - after.len()) + 1;
+        let Some(close) = content[open..].find(')') else { break };
+        commands.push(
+            content[open..open + close]
+                .split_whitespace()
+                .map(|arg| arg.trim_matches('"').to_string())
+                .collect(),
+        );
+        rest = open + close;
+    }
+    commands
+}
+
+/// CMakeの引数から、キーワードの後に続く値を取り出す（次の大文字のキーワードまで）
+fn cmake_keyword_values(args: &[String], keyword: &str) -> Vec<String> {
+    let is_keyword = |arg: &str| !arg.is_empty() && arg.chars().all(|c| c.is_ascii_uppercase() || c == '_');
+    args.iter()
+        .skip_while(|arg| *arg != keyword)
+        .skip(1)
+        .take_while(|arg| !is_keyword(arg))
+        .cloned()
+        .collect()
+}
+
+/// Pythonソースから関数呼び出しの引数部分（括弧の中）を集める
+fn python_calls<'a>(source: &'a str, name: &str) -> Vec<&'a str> {
+    let mut calls = vec![];
+    let mut rest = 0;
+    while let Some(found) = source[rest..].find(name) {
+        let start = rest + found;
+        rest = start + name.len();
+        let preceded = source[..start].chars().next_back().is_some_and(|c| c.is_alphanumeric() || c == '_');
+        if preceded || !source[rest..].starts_with('(') {
+            continue;
+        }
+        let open = rest + 1;
+        let Some(length) = closing_bracket(&source[open..]) else { break };
+        calls.push(&source[open..open + length]);
+        rest = open + length;
+    }
+    calls
+}
+
+/// 開き括弧の直後から、対応する閉じ括弧までの長さを求める（文字列リテラルの中は数えない）
+fn closing_bracket(source: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut quote = None;
+    for (index, c) in source.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '(' | '[' | '{') => depth += 1,
+            (None, ')' | ']' | '}') if depth == 0 => return Some(index),
+            (None, ')' | ']' | '}') => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// 関数呼び出しの引数をトップレベルのカンマで分ける
+fn split_python_args(args: &str) -> Vec<&str> {
+    let mut result = vec![];
+    let mut depth = 0;
+    let mut quote = None;
+    let mut start = 0;
+    for (index, c) in args.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '(' | '[' | '{') => depth += 1,
+            (None, ')' | ']' | '}') => depth -= 1,
+            (None, ',') if depth == 0 => {
+                result.push(args[start..index].trim());
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    result.push(args[start..].trim());
+    result.retain(|arg| !arg.is_empty());
+    result
+}
+
+/// `name=value`形式の引数かどうか
+fn is_keyword_arg(arg: &str) -> bool {
+    arg.split_once('=').is_some_and(|(name, _)| {
+        let name = name.trim();
+        !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+    })
+}
+
+/// Pythonの文字列リテラルの値を取り出す
+fn python_string(literal: &str) -> Option<String> {
+    let literal = literal.trim().trim_start_matches(['r', 'R']);
+    ['"', '\'']
+        .iter()
+        .find_map(|quote| literal.strip_prefix(*quote)?.strip_suffix(*quote))
+        .map(str::to_string)
+}
+
+/// Pythonの文字列のリスト（`["a", "b"]`）の値を取り出す
+fn python_string_list(literal: &str) -> Vec<String> {
+    let literal = literal.trim();
+    let inner = literal
+        .strip_prefix(['[', '('])
+        .and_then(|inner| inner.strip_suffix([']', ')']))
+        .unwrap_or_default();
+    split_python_args(inner).into_iter().filter_map(python_string).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_project(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("pystubgen-backend-{}-{}", name, std::process::id()));
+        for (path, content) in files {
+            let path = dir.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn test_detect_backend() {
+        let dir = temp_project("detect", &[
+            ("setup.py", "from setuptools_rust import RustExtension\nsetup(rust_extensions=[RustExtension(\"pkg._core\")])\n"),
+            ("cmake/CMakeLists.txt", "corrosion_import_crate(MANIFEST_PATH Cargo.toml)\n"),
+        ]);
+        let detect = |toml: &str| detect_backend(&toml.parse::<DocumentMut>().unwrap(), &dir);
+        let maturin = detect("[build-system]\nbuild-backend = \"maturin\"");
+        let setuptools = detect("[build-system]\nbuild-backend = \"setuptools.build_meta\"");
+        let legacy = detect("");
+        let scikit = detect("[build-system]\nbuild-backend = \"scikit_build_core.build\"\n[tool.scikit-build]\ncmake.source-dir = \"cmake\"");
+        let scikit_without_rust = detect("[build-system]\nbuild-backend = \"scikit_build_core.build\"");
+        let hatch = detect("[build-system]\nbuild-backend = \"hatchling.build\"");
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(maturin, Some(BuildBackend::Maturin));
+        assert_eq!(setuptools, Some(BuildBackend::SetuptoolsRust));
+        assert_eq!(legacy, Some(BuildBackend::SetuptoolsRust));
+        assert_eq!(scikit, Some(BuildBackend::ScikitBuild));
+        assert_eq!(scikit_without_rust, None);
+        assert_eq!(hatch, None);
+    }
+
+    #[test]
+    fn test_setuptools_rust_extensions() {
+        let dir = temp_project("setuptools", &[(
+            "setup.py",
+            "setup(\n    package_dir={\"\": \"python\"},\n    rust_extensions=[\n        RustExtension(\"pkg.native._speedups\", path=\"rust/Cargo.toml\", features=[\"simd\"]),\n        RustExtension({\"cli\": \"pkg.cli\"}, binding=Binding.Exec),\n    ],\n)\n",
+        )]);
+        let pyproject = r#"
+        [build-system]
+        build-backend = "setuptools.build_meta"
+
+        [[tool.setuptools-rust.ext-modules]]
+        target = "pkg._core"
+        features = ["python"]
+        "#.parse::<DocumentMut>().unwrap();
+        let extensions = extension_modules(BuildBackend::SetuptoolsRust, &pyproject, &dir).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(extensions.len(), 2);
+        assert_eq!(extensions[0].python_module.as_deref(), Some("pkg._core"));
+        assert_eq!(extensions[0].manifest_path, dir.join("Cargo.toml"));
+        assert_eq!(extensions[0].features, vec!["python".to_string()]);
+        assert_eq!(extensions[1].python_module.as_deref(), Some("pkg.native._speedups"));
+        assert_eq!(extensions[1].manifest_path, dir.join("rust/Cargo.toml"));
+        assert_eq!(extensions[1].features, vec!["simd".to_string()]);
+        assert_eq!(extensions[1].package_dir("pkg.native"), Some(dir.join("python").join("pkg").join("native")));
+    }
+
+    #[test]
+    fn test_scikit_build_extensions() {
+        let dir = temp_project("scikit", &[
+            ("CMakeLists.txt", "# corrosion_import_crate(MANIFEST_PATH old/Cargo.toml)\ncorrosion_import_crate(\n  MANIFEST_PATH \"${CMAKE_CURRENT_SOURCE_DIR}/rust/Cargo.toml\"\n  FEATURES python\n)\nINSTALL(TARGETS _native DESTINATION ${SKBUILD_PROJECT_NAME})\n"),
+            ("rust/Cargo.toml", "[package]\nname = \"native\"\n[lib]\nname = \"_native\"\n"),
+            ("src/fast_pkg/__init__.py", ""),
+        ]);
+        let pyproject = "[project]\nname = \"fast-pkg\"\n[build-system]\nbuild-backend = \"scikit_build_core.build\"\n"
+            .parse::<DocumentMut>()
+            .unwrap();
+        let extensions = extension_modules(BuildBackend::ScikitBuild, &pyproject, &dir).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(extensions.len(), 1);
+        assert_eq!(extensions[0].python_module.as_deref(), Some("fast_pkg._native"));
+        assert_eq!(extensions[0].manifest_path, dir.join("rust/Cargo.toml"));
+        assert_eq!(extensions[0].features, vec!["python".to_string()]);
+        assert_eq!(extensions[0].package_dir("fast_pkg"), Some(dir.join("src").join("fast_pkg")));
+    }
+
+    #[test]
+    fn test_cmake_commands_non_ascii() {
+        // "İ"は小文字にするとバイト数が変わるため、その後ろの位置がずれないことを確かめる
+        let content = "message(\"İİ\")\nINSTALL(TARGETS _native DESTINATION pkg)\n";
+        assert_eq!(
+            cmake_commands(content, "install"),
+            vec![vec!["TARGETS".to_string(), "_native".to_string(), "DESTINATION".to_string(), "pkg".to_string()]]
+        );
+    }
+
+    #[test]
+    fn test_package_dir() {
+        let extension = ExtensionModule {
+            package_dirs: vec![(String::new(), PathBuf::from("src")), ("pkg.native".to_string(), PathBuf::from("rust/py"))],
+            ..Default::default()
+        };
+        assert_eq!(extension.package_dir("pkg"), Some(PathBuf::from("src/pkg")));
+        assert_eq!(extension.package_dir("pkg.native.sub"), Some(PathBuf::from("rust/py/sub")));
+        assert_eq!(extension.package_dir(""), Some(PathBuf::from("src")));
+        assert_eq!(ExtensionModule::default().package_dir("pkg"), None);
+    }
+}
@@ -0,0 +1,308 @@
+//! ライブラリ用のビルダーモジュール
+//! 
+//! `build.rs`・xtaskのランナー・結合テストなど、コマンドを起動せずにスタブを生成するためのAPIです。
+//! 何も表示せず、組み立てたファイルの内容と診断を返します。書き込むかどうかは呼び出し側が決めます。
+//! 
+//! ```no_run
+//! use cargo_pystubgen::StubGenerator;
+//! 
+//! let stubs = StubGenerator::new("path/to/project").features(["numpy"]).generate()?;
+//! for diagnostic in stubs.diagnostics() {
+//!     eprintln!("{}", diagnostic);
+//! }
+//! stubs.write()?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::error::Error;
+use std::path::PathBuf;
+use crate::stubgen::config::{DocsFormat, DocstringStyle, PythonVersion};
+use crate::stubgen::diagnostic::Diagnostic;
+use crate::stubgen::generator::StubFile;
+use crate::stubgen::{deny_warnings, locate_projects, render_projects, GenerateOptions};
+
+/// スタブ生成のビルダー
+#[derive(Debug, Default)]
+pub struct StubGenerator {
+    /// スタブ生成のオプション
+    options: GenerateOptions,
+    /// 対象にするクレートのCargo.toml（未指定なら見つかったプロジェクトすべて）
+    manifest_path: Option<PathBuf>,
+}
+
+impl StubGenerator {
+    /// プロジェクトのディレクトリを指定してビルダーを作る
+    /// 
+    /// # Arguments
+    /// 
+    /// * `project_dir` - プロジェクト（またはワークスペース）のディレクトリ。相対パスはカレントディレクトリから
+    pub fn new(project_dir: impl Into<PathBuf>) -> Self {
+        StubGenerator {
+            options: GenerateOptions { project_dir: Some(project_dir.into()), ..Default::default() },
+            manifest_path: None,
+        }
+    }
+
+    /// プロジェクトのディレクトリを変更する
+    pub fn project_dir(mut self, project_dir: impl Into<PathBuf>) -> Self {
+        self.options.project_dir = Some(project_dir.into());
+        self
+    }
+
+    /// 有効にするフィーチャーを追加する
+    pub fn features<I, S>(mut self, features: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.options.cfg.features.extend(features.into_iter().map(Into::into));
+        self
+    }
+
+    /// すべてのフィーチャーを有効にする
+    pub fn all_features(mut self, all_features: bool) -> Self {
+        self.options.cfg.all_features = all_features;
+        self
+    }
+
+    /// defaultフィーチャーを無効にする
+    pub fn no_default_features(mut self, no_default_features: bool) -> Self {
+        self.options.cfg.no_default_features = no_default_features;
+        self
+    }
+
+    /// `#[cfg]`の評価に使うターゲットトリプルを指定する
+    pub fn target(mut self, target: impl Into<String>) -> Self {
+        self.options.cfg.target = Some(target.into());
+        self
+    }
+
+    /// マクロ展開後のソースを解析する
+    pub fn expand(mut self, expand: bool) -> Self {
+        self.options.expand = expand;
+        self
+    }
+
+    /// 解析結果のキャッシュを使わない
+    pub fn no_cache(mut self, no_cache: bool) -> Self {
+        self.options.no_cache = no_cache;
+        self
+    }
+
+    /// 解析結果のキャッシュを置くディレクトリを指定する（既定は`target/pystubgen`）
+    pub fn cache_dir(mut self, cache_dir: impl Into<PathBuf>) -> Self {
+        self.options.cache_dir = Some(cache_dir.into());
+        self
+    }
+
+    /// 指定したCargo.tomlのクレートを拡張モジュールにするプロジェクトだけを対象にする
+    pub fn manifest_path(mut self, manifest_path: impl Into<PathBuf>) -> Self {
+        self.manifest_path = Some(manifest_path.into());
+        self
+    }
+
+    /// 並列に処理するスレッド数を指定する
+    pub fn jobs(mut self, jobs: usize) -> Self {
+        self.options.jobs = Some(jobs);
+        self
+    }
+
+    /// 拡張モジュールを含むパッケージの`py.typed`も組み立てる
+    pub fn py_typed(mut self, py_typed: bool) -> Self {
+        self.options.package.py_typed = py_typed;
+        self
+    }
+
+    /// 拡張モジュールを含むパッケージの`__init__.pyi`も組み立てる（手で管理されている場合は検証する）
+    pub fn init_stub(mut self, init_stub: bool) -> Self {
+        self.options.package.init_stub = init_stub;
+        self
+    }
+
+    /// ワークスペースの定義を使わず、配下のPyO3拡張モジュールを持つプロジェクトをすべて探す
+    pub fn scan(mut self, scan: bool) -> Self {
+        self.options.scan = scan;
+        self
+    }
+
+    /// 警告があれば`generate`をエラーにする
+    pub fn deny_warnings(mut self, deny_warnings: bool) -> Self {
+        self.options.deny_warnings = deny_warnings;
+        self
+    }
+
+    /// 出力ディレクトリを指定する（[tool.pystubgen] output-dirより優先。対象のプロジェクトが1つの場合だけ使える）
+    pub fn output_dir(mut self, output_dir: impl Into<PathBuf>) -> Self {
+        self.options.overrides.output_dir = Some(output_dir.into());
+        self
+    }
+
+    /// ドキュメントコメントの出力形式を指定する（[tool.pystubgen] docstring-styleより優先）
+    pub fn docstring_style(mut self, docstring_style: DocstringStyle) -> Self {
+        self.options.overrides.docstring_style = Some(docstring_style);
+        self
+    }
+
+    /// 対象とするPythonのバージョンを指定する（[tool.pystubgen] python-versionより優先）
+    pub fn python_version(mut self, python_version: PythonVersion) -> Self {
+        self.options.overrides.python_version = Some(python_version);
+        self
+    }
+
+    /// スタブの先頭に書くテキストを指定する（[tool.pystubgen] headerより優先）
+    pub fn header(mut self, header: impl Into<String>) -> Self {
+        self.options.overrides.header = Some(header.into());
+        self
+    }
+
+    /// APIリファレンスも組み立てる（[tool.pystubgen] docsより優先）
+    pub fn docs(mut self, docs: DocsFormat) -> Self {
+        self.options.overrides.docs = Some(docs);
+        self
+    }
+
+    /// スタブ生成のオプション
+    pub fn options(&self) -> &GenerateOptions {
+        &self.options
+    }
+
+    /// プロジェクトを探して解析し、ファイルを組み立てる（書き込みはしない）
+    /// 
+    /// # Returns
+    /// 
+    /// * `Result<StubSet, Box<dyn Error>>` - 組み立てたファイルと診断（失敗したプロジェクトがあれば、`deny_warnings`なら警告があってもエラー）
+    pub fn generate(&self) -> Result<StubSet, Box<dyn Error>> {
+        let mut project_infos = locate_projects(&self.options)?;
+        if let Some(manifest_path) = &self.manifest_path {
+            let manifest_path = manifest_path.canonicalize()
+                .map_err(|e| format!("failed to read {}: {}", manifest_path.display(), e))?;
+            project_infos.retain(|project_info| project_info.manifest_path.canonicalize().is_ok_and(|path| path == manifest_path));
+        }
+        let mut projects = vec![];
+        for (result, project_info) in render_projects(&project_infos, &self.options)?.into_iter().zip(&project_infos) {
+            let rendered = result
+                .map_err(|e| format!("failed to generate stubs for {}: {}", project_info.project_name, e))?;
+            projects.push(ProjectStubs {
+                project_name: rendered.project_info.project_name,
+                python_module: rendered.project_info.python_module,
+                files: rendered.files,
+                diagnostics: rendered.analysis.warnings,
+                source_files: rendered.analysis.source_files,
+            });
+        }
+        let stubs = StubSet { projects };
+        deny_warnings(&self.options, stubs.diagnostics().count())?;
+        Ok(stubs)
+    }
+}
+
+/// 1つのプロジェクトについて組み立てたファイル
+#[derive(Debug, Clone)]
+pub struct ProjectStubs {
+    /// プロジェクト名
+    pub project_name: String,
+    /// 拡張モジュールの完全な名前（`my_pkg._core`）
+    pub python_module: String,
+    /// スタブ・パッケージファイル・APIリファレンス（この順）
+    pub files: Vec<StubFile>,
+    /// 診断
+    pub diagnostics: Vec<Diagnostic>,
+    /// 生成に使ったファイル（ビルドバックエンドの設定・Cargo.toml・Rustのソース）
+    pub source_files: Vec<PathBuf>,
+}
+
+/// 組み立てたファイルと診断
+#[derive(Debug, Clone, Default)]
+pub struct StubSet {
+    /// プロジェクトごとの結果（見つかった順）
+    pub projects: Vec<ProjectStubs>,
+}
+
+impl StubSet {
+    /// すべてのプロジェクトのファイル
+    pub fn files(&self) -> impl Iterator<Item = &StubFile> {
+        self.projects.iter().flat_map(|project| &project.files)
+    }
+
+    /// すべてのプロジェクトの診断
+    pub fn diagnostics(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.projects.iter().flat_map(|project| &project.diagnostics)
+    }
+
+    /// ファイルを書き込む（内容が同じファイルは書き込まない）
+    /// 
+    /// # Returns
+    /// 
+    /// * `Result<Vec<PathBuf>, Box<dyn Error>>` - 書き込んだファイルのパス
+    pub fn write(&self) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        let mut written = vec![];
+        for file in self.files() {
+            if file.write()? {
+                written.push(file.path.clone());
+            }
+        }
+        Ok(written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stubgen::generator::FileKind;
+
+    #[test]
+    fn test_builder_options() {
+        let generator = StubGenerator::new("project")
+            .features(["a", "b"])
+            .features(vec!["c".to_string()])
+            .no_default_features(true)
+            .target("x86_64-unknown-linux-gnu")
+            .jobs(2)
+            .py_typed(true)
+            .header("# header");
+        let options = generator.options();
+        assert_eq!(options.project_dir, Some(PathBuf::from("project")));
+        assert_eq!(options.cfg.features, vec!["a", "b", "c"]);
+        assert!(options.cfg.no_default_features && !options.cfg.all_features);
+        assert_eq!(options.cfg.target.as_deref(), Some("x86_64-unknown-linux-gnu"));
+        assert_eq!(options.jobs, Some(2));
+        assert!(options.package.py_typed && !options.package.init_stub);
+        assert_eq!(options.overrides.header.as_deref(), Some("# header"));
+    }
+
+    #[test]
+    fn test_output_dir_with_multiple_projects() {
+        // lib_cとsingle_projectはどちらも_coreという拡張モジュールを持つため、同じ出力先には書けない
+        let generator = StubGenerator::new("tests/test-project").no_cache(true).output_dir("stubs");
+        assert!(generator.generate().is_err());
+
+        let stubs = generator.manifest_path("tests/test-project/single_project/Cargo.toml").generate().unwrap();
+        let cwd = std::env::current_dir().unwrap();
+        assert_eq!(stubs.projects[0].files[0].path, cwd.join("stubs").join("_core.pyi"));
+    }
+
+    #[test]
+    fn test_stub_set_write() {
+        let dir = std::env::temp_dir().join(format!("pystubgen-builder-{}", std::process::id()));
+        let file = |name: &str, content: &str| StubFile {
+            path: dir.join("pkg").join(name),
+            kind: FileKind::Stub,
+            content: content.to_string(),
+        };
+        let stubs = StubSet {
+            projects: vec![ProjectStubs {
+                project_name: "pkg".to_string(),
+                python_module: "pkg._core".to_string(),
+                files: vec![file("_core.pyi", "def f() -> int: ...\n"), file("py.typed", "")],
+                diagnostics: vec![Diagnostic::warning("x")],
+                source_files: vec![],
+            }],
+        };
+        assert_eq!(stubs.files().count(), 2);
+        assert_eq!(stubs.diagnostics().count(), 1);
+        assert_eq!(stubs.write().unwrap().len(), 2);
+        // 内容が同じなら書き込まない
+        assert!(stubs.write().unwrap().is_empty());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,154 @@
+//! build.rs連携モジュール
+//! 
+//! 拡張モジュールのクレートの`build.rs`から呼び出し、`cargo build`（`maturin build`）のたびにスタブを更新します。
+//! Cargoが渡す環境変数からクレート・有効なフィーチャー・ターゲットを決め、
+//! 解析に使ったファイルを`cargo:rerun-if-changed`として出力します。
+//! 
+//! ```no_run
+//! // build.rsのmain関数から呼び出す
+//! cargo_pystubgen::generate_in_build_script().expect("failed to generate Python stubs");
+//! ```
+
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use toml_edit::DocumentMut;
+use crate::stubgen::builder::{StubGenerator, StubSet};
+use crate::stubgen::{cfg, deny_warnings, GenerateOptions};
+
+/// ビルドスクリプトから実行中のクレートのスタブを生成し、設定された出力先に書き込む
+/// 
+/// `CARGO_MANIFEST_DIR`から上のディレクトリへ、このクレートを拡張モジュールにするPythonプロジェクトを探す。
+/// フィーチャーは`CARGO_FEATURE_*`、ターゲットは`TARGET`から決め、解析結果のキャッシュは`OUT_DIR`に置く。
+/// 警告は`cargo:warning`として出力する。
+/// 
+/// # Returns
+/// 
+/// * `Result<StubSet, Box<dyn Error>>` - 生成したファイルと診断
+pub fn generate_in_build_script() -> Result<StubSet, Box<dyn Error>> {
+    generate_in_build_script_with(|generator| generator)
+}
+
+/// ビルダーの設定を変えて`generate_in_build_script`を実行する
+/// 
+/// `deny_warnings`を指定した場合も、警告を`cargo:warning`として出力してからエラーにする。
+/// 
+/// # Arguments
+/// 
+/// * `configure` - 環境変数から設定したビルダーを受け取り、設定を加えたビルダーを返す関数
+/// 
+/// # Returns
+/// 
+/// * `Result<StubSet, Box<dyn Error>>` - 生成したファイルと診断
+pub fn generate_in_build_script_with(
+    configure: impl FnOnce(StubGenerator) -> StubGenerator,
+) -> Result<StubSet, Box<dyn Error>> {
+    let manifest_dir = PathBuf::from(required_env("CARGO_MANIFEST_DIR")?);
+    let manifest_path = manifest_dir.join("Cargo.toml");
+    let cargo_doc = std::fs::read_to_string(&manifest_path)?
+        .parse::<DocumentMut>()
+        .map_err(|e| format!("failed to parse {}: {}", manifest_path.display(), e))?;
+
+    // Cargoが解決したフィーチャーをそのまま使う（有効ならdefaultも含まれる）
+    let mut generator = StubGenerator::new(&manifest_dir)
+        .manifest_path(&manifest_path)
+        .features(cfg::features_from_env(&cargo_doc, std::env::vars()))
+        .no_default_features(true)
+        .cache_dir(PathBuf::from(required_env("OUT_DIR")?).join("pystubgen"));
+    if let Ok(target) = std::env::var("TARGET") {
+        generator = generator.target(target);
+    }
+    let generator = configure(generator);
+    // 警告を出力してから判定するため、生成中はエラーにしない
+    let deny = generator.options().deny_warnings;
+    let generator = generator.deny_warnings(false);
+
+    let stubs = generate_for_crate(generator, &manifest_dir, &manifest_path)?;
+    for path in stubs.projects.iter().flat_map(|project| &project.source_files) {
+        println!("cargo:rerun-if-changed={}", path.display());
+    }
+    for diagnostic in stubs.diagnostics() {
+        // cargo:warningは1行ずつ表示される
+        for line in diagnostic.to_string().lines() {
+            println!("cargo:warning={}", line);
+        }
+    }
+    deny_warnings(&GenerateOptions { deny_warnings: deny, ..Default::default() }, stubs.diagnostics().count())?;
+    stubs.write()?;
+    Ok(stubs)
+}
+
+/// クレートのディレクトリから上へたどり、クレートを拡張モジュールにするプロジェクトが見つかったところで生成する
+/// 
+/// 途中のプロジェクトで生成に失敗しても（このクレートと関係のない設定の誤りなど）、さらに上へたどる。
+fn generate_for_crate(
+    mut generator: StubGenerator,
+    manifest_dir: &Path,
+    manifest_path: &Path,
+) -> Result<StubSet, Box<dyn Error>> {
+    let mut errors = vec![];
+    for dir in manifest_dir.ancestors().filter(|dir| is_python_project(dir)) {
+        generator = generator.project_dir(dir);
+        match generator.generate() {
+            Ok(stubs) if !stubs.projects.is_empty() => return Ok(stubs),
+            Ok(_) => {}
+            Err(e) => errors.push(format!("\n  {}: {}", dir.display(), e)),
+        }
+    }
+    Err(format!("no Python project builds {} as an extension module{}", manifest_path.display(), errors.concat()).into())
+}
+
+/// pyproject.toml（または`setup.py`）のあるディレクトリかどうか
+fn is_python_project(dir: &Path) -> bool {
+    dir.join("pyproject.toml").is_file() || dir.join("setup.py").is_file()
+}
+
+/// ビルドスクリプトに必ず渡される環境変数を読む
+fn required_env(name: &str) -> Result<String, Box<dyn Error>> {
+    std::env::var(name).map_err(|_| format!("{} is not set; call this from a build script", name).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_for_crate() {
+        let project_root = std::env::current_dir().unwrap().join("tests/test-project");
+        let manifest_dir = project_root.join("single_project");
+        let manifest_path = manifest_dir.join("Cargo.toml");
+        let generator = StubGenerator::new(".").manifest_path(&manifest_path).no_cache(true);
+        let stubs = generate_for_crate(generator, &manifest_dir, &manifest_path).unwrap();
+        let names: Vec<&str> = stubs.projects.iter().map(|project| project.project_name.as_str()).collect();
+        assert_eq!(names, vec!["single_project"]);
+        assert!(stubs.projects[0].source_files.contains(&manifest_dir.join("src/lib.rs")));
+        assert!(stubs.projects[0].source_files.contains(&manifest_path));
+
+        // 上のディレクトリにプロジェクトがなければエラー
+        let other = project_root.join("libs/lib_c/Cargo.toml");
+        let generator = StubGenerator::new(".").manifest_path(&other).no_cache(true);
+        assert!(generate_for_crate(generator, &std::env::temp_dir(), &other).is_err());
+    }
+
+    #[test]
+    fn test_generate_for_crate_skips_failing_project() {
+        // 拡張モジュールのクレートの中に、壊れたpyproject.tomlを持つディレクトリがある
+        let project_root = std::env::temp_dir().join(format!("pystubgen-buildscript-{}", std::process::id()));
+        let manifest_dir = project_root.join("native");
+        let broken = manifest_dir.join("examples");
+        std::fs::create_dir_all(manifest_dir.join("src")).unwrap();
+        std::fs::create_dir_all(&broken).unwrap();
+        std::fs::write(project_root.join("pyproject.toml"), "[project]\nname = \"outer\"\n[build-system]\nbuild-backend = \"maturin\"\n[tool.maturin]\nmanifest-path = \"native/Cargo.toml\"\n").unwrap();
+        std::fs::write(manifest_dir.join("Cargo.toml"), "[package]\nname = \"native\"\nversion = \"0.1.0\"\n[lib]\nname = \"native\"\n").unwrap();
+        std::fs::write(manifest_dir.join("src/lib.rs"), "use pyo3::prelude::*;\n\n#[pyfunction]\nfn answer() -> i32 { 42 }\n").unwrap();
+        std::fs::write(broken.join("pyproject.toml"), "[project\n").unwrap();
+
+        let manifest_path = manifest_dir.join("Cargo.toml");
+        let generator = StubGenerator::new(".").manifest_path(&manifest_path).no_cache(true);
+        let result = generate_for_crate(generator, &broken, &manifest_path);
+        std::fs::remove_dir_all(&project_root).unwrap();
+
+        let stubs = result.unwrap();
+        let names: Vec<&str> = stubs.projects.iter().map(|project| project.project_name.as_str()).collect();
+        assert_eq!(names, vec!["native"]);
+    }
+}
@@ -0,0 +1,237 @@
+//! 解析結果キャッシュモジュール
+//! 
+//! ファイルごとのパース結果を`target/pystubgen/`に保存し、次回の実行で内容が変わっていない
+//! ファイルのパースを省略します。キャッシュはファイル内容のハッシュ、有効なフィーチャーと
+//! ターゲット、ツールのバージョンが一致する場合にだけ使われます。
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use crate::stubgen::modgraph::ModuleFile;
+use crate::stubgen::parser::RustSrcData;
+
+/// キャッシュを保存するディレクトリ名（ターゲットディレクトリの下に作る）
+const CACHE_DIR_NAME: &str = "pystubgen";
+
+/// パース結果の形式のバージョン（`RustSrcData`にフィールドを加えたとき、同じソースからのパース結果が変わったときに上げる。
+/// `Option`のフィールドは古いキャッシュでも読めてしまい、`None`として扱われるため）
+const CACHE_FORMAT: u32 = 8;
+
+/// ファイルから見つかったモジュールの解析結果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum CachedEntry {
+    /// ファイル内に書かれたモジュールのパース結果
+    Module(RustSrcData),
+    /// 別ファイルに書かれたモジュール
+    File(ModuleFile),
+}
+
+/// 1つのファイルのキャッシュ
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FileCache {
+    /// ファイル内容のハッシュ
+    hash: String,
+    /// ファイルを読んだ位置の情報
+    module_file: ModuleFile,
+    /// ファイルから見つかったモジュール（宣言順）
+    entries: Vec<CachedEntry>,
+    /// ファイル内で無効として扱った未知の`cfg`の名前
+    unknown_cfgs: Vec<String>,
+}
+
+/// キャッシュファイルの内容
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheData {
+    /// キャッシュを書き出したツールとパース結果の形式のバージョン
+    version: String,
+    /// 有効なフィーチャーとターゲット
+    cfg_key: String,
+    /// ファイルのパスからキャッシュへの対応
+    files: BTreeMap<PathBuf, FileCache>,
+}
+
+/// プロジェクトごとの解析結果キャッシュ
+#[derive(Debug)]
+pub struct StubCache {
+    /// キャッシュファイルのパス
+    path: PathBuf,
+    /// 前回の実行で保存されたキャッシュ
+    previous: CacheData,
+    /// 今回の実行で使ったファイルのキャッシュ（保存時はこれだけを書き出す）
+    current: CacheData,
+    /// キャッシュを使えたファイルの数
+    pub hits: usize,
+    /// パースし直したファイルの数
+    pub misses: usize,
+}
+
+impl StubCache {
+    /// キャッシュを読み込む
+    /// 
+    /// キャッシュファイルがない、壊れている、またはバージョンやフィーチャーが異なる場合は空のキャッシュになる。
+    /// 
+    /// # Arguments
+    /// 
+    /// * `cache_dir` - キャッシュを保存するディレクトリ
+    /// * `project_name` - プロジェクト名（キャッシュファイル名になる）
+    /// * `cfg_key` - 有効なフィーチャーとターゲットを表す文字列
+    /// 
+    /// # Returns
+    /// 
+    /// * `StubCache` - 読み込んだキャッシュ
+    pub fn load(cache_dir: &Path, project_name: &str, cfg_key: &str) -> StubCache {
+        let path = cache_dir.join(format!("{}.json", project_name));
+        let current = CacheData {
+            version: format!("{}+{}", env!("CARGO_PKG_VERSION"), CACHE_FORMAT),
+            cfg_key: cfg_key.to_string(),
+            files: BTreeMap::new(),
+        };
+        let previous = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<CacheData>(&content).ok())
+            .filter(|data| data.version == current.version && data.cfg_key == current.cfg_key)
+            .unwrap_or_default();
+        StubCache { path, previous, current, hits: 0, misses: 0 }
+    }
+
+    /// 内容が変わっていないファイルの解析結果を取得する
+    /// 
+    /// # Arguments
+    /// 
+    /// * `module_file` - ファイルを読む位置の情報
+    /// * `hash` - 現在のファイル内容のハッシュ
+    /// 
+    /// # Returns
+    /// 
+    /// * `Option<(Vec<CachedEntry>, Vec<String>)>` - キャッシュされた解析結果と未知の`cfg`の名前（使えない場合は`None`）
+    pub fn get(&mut self, module_file: &ModuleFile, hash: &str) -> Option<(Vec<CachedEntry>, Vec<String>)> {
+        let cached = self
+            .previous
+            .files
+            .get(&module_file.file)
+            .filter(|cached| cached.hash == hash && cached.module_file == *module_file)
+            // mod foo; の参照先が foo.rs から foo/mod.rs に移った場合などはパースし直す
+            .filter(|cached| {
+                cached.entries.iter().all(|entry| match entry {
+                    CachedEntry::File(child) => child.file.is_file(),
+                    CachedEntry::Module(_) => true,
+                })
+            })?
+            .clone();
+        self.hits += 1;
+        let entries = (cached.entries.clone(), cached.unknown_cfgs.clone());
+        self.current.files.insert(module_file.file.clone(), cached);
+        Some(entries)
+    }
+
+    /// パースし直したファイルの解析結果を登録する
+    /// 
+    /// # Arguments
+    /// 
+    /// * `module_file` - ファイルを読んだ位置の情報
+    /// * `hash` - ファイル内容のハッシュ
+    /// * `entries` - ファイルから見つかったモジュール
+    /// * `unknown_cfgs` - ファイル内で無効として扱った未知の`cfg`の名前
+    pub fn insert(&mut self, module_file: &ModuleFile, hash: String, entries: Vec<CachedEntry>, unknown_cfgs: Vec<String>) {
+        self.misses += 1;
+        self.current.files.insert(
+            module_file.file.clone(),
+            FileCache { hash, module_file: module_file.clone(), entries, unknown_cfgs },
+        );
+    }
+
+    /// 今回の実行で使ったファイルのキャッシュを保存する
+    /// 
+    /// # Returns
+    /// 
+    /// * `Result<(), Box<dyn Error>>` - 処理結果
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&self.path, serde_json::to_string(&self.current)?)?;
+        Ok(())
+    }
+}
+
+/// ファイル内容のハッシュを計算する
+pub fn content_hash(content: &str) -> String {
+    Sha256::digest(content.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// キャッシュを保存するディレクトリを決める
+/// 
+/// `CARGO_TARGET_DIR`が設定されていればそれを使い、なければCargo.tomlのあるディレクトリから
+/// 親をたどって最初に見つかった`target`ディレクトリを使う。
+/// 
+/// # Arguments
+/// 
+/// * `manifest_path` - Cargo.tomlのパス
+/// 
+/// # Returns
+/// 
+/// * `PathBuf` - キャッシュディレクトリ（`<target>/pystubgen`）
+pub fn cache_dir(manifest_path: &Path) -> PathBuf {
+    if let Some(target_dir) = std::env::var_os("CARGO_TARGET_DIR") {
+        return PathBuf::from(target_dir).join(CACHE_DIR_NAME);
+    }
+    let manifest_dir = manifest_path.parent().unwrap_or(Path::new("."));
+    manifest_dir
+        .ancestors()
+        .filter(|dir| dir.join("Cargo.toml").is_file())
+        .map(|dir| dir.join("target"))
+        .find(|target_dir| target_dir.is_dir())
+        .unwrap_or_else(|| manifest_dir.join("target"))
+        .join(CACHE_DIR_NAME)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn src_data(file: &Path) -> RustSrcData {
+        RustSrcData {
+            file: file.to_path_buf(),
+            module_path: vec!["crate".to_string()],
+            functions: vec![],
+            constants: vec![],
+            types: vec![],
+            imports: vec![],
+        }
+    }
+
+    #[test]
+    fn test_cache_round_trip() {
+        let dir = std::env::temp_dir().join(format!("pystubgen-cache-{}", std::process::id()));
+        let lib_rs = dir.join("lib.rs");
+        let module_file = ModuleFile::root(&lib_rs);
+        let hash = content_hash("fn main() {}");
+
+        let mut cache = StubCache::load(&dir, "project", "features=");
+        assert!(cache.get(&module_file, &hash).is_none());
+        cache.insert(&module_file, hash.clone(), vec![CachedEntry::Module(src_data(&lib_rs))], vec!["Py_3_8".to_string()]);
+        cache.save().unwrap();
+
+        let mut cache = StubCache::load(&dir, "project", "features=");
+        let (entries, unknown_cfgs) = cache.get(&module_file, &hash).unwrap();
+        assert!(matches!(&entries[..], [CachedEntry::Module(data)] if data.file == lib_rs));
+        assert_eq!(unknown_cfgs, vec!["Py_3_8"]);
+        assert!(cache.get(&module_file, &content_hash("fn main() { }")).is_none());
+        // フィーチャーが変わったらキャッシュは使わない
+        let mut cache = StubCache::load(&dir, "project", "features=gpu");
+        assert!(cache.get(&module_file, &hash).is_none());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_content_hash() {
+        assert_eq!(content_hash(""), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+        assert_ne!(content_hash("a"), content_hash("b"));
+    }
+}
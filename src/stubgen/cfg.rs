@@ -0,0 +1,700 @@
+//! 条件付きコンパイル評価モジュール
+//! 
+//! `#[cfg(...)]`と`#[cfg_attr(...)]`を、選択されたCargoのフィーチャーとターゲットに対して評価します。
+//! 無効なアイテムを取り除き、`cfg_attr`を展開してからアトリビュートの解析に渡します。
+
+use std::collections::{BTreeSet, HashSet};
+use std::sync::Mutex;
+use syn::punctuated::Punctuated;
+use syn::{Attribute, Item, Meta, Token};
+use toml_edit::DocumentMut;
+
+/// フィーチャーとターゲットの指定（コマンドライン引数に対応する）
+#[derive(Debug, Default, Clone)]
+pub struct CfgOptions {
+    /// 有効にするフィーチャー
+    pub features: Vec<String>,
+    /// すべてのフィーチャーを有効にする
+    pub all_features: bool,
+    /// defaultフィーチャーを無効にする
+    pub no_default_features: bool,
+    /// ターゲットトリプル（未指定ならホスト）
+    pub target: Option<String>,
+}
+
+/// ターゲットに関する`cfg`の値
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TargetInfo {
+    pub arch: String,
+    pub vendor: String,
+    pub os: String,
+    pub env: String,
+    pub family: Option<String>,
+    pub pointer_width: String,
+    pub endian: String,
+    /// パニック時の動作（`unwind`か`abort`）
+    pub panic: String,
+    /// アトミック操作ができる最大のビット幅（0ならアトミック型がない）
+    pub max_atomic_width: u32,
+}
+
+impl TargetInfo {
+    /// 実行中のホストのターゲット情報
+    pub fn host() -> TargetInfo {
+        let os = std::env::consts::OS.to_string();
+        let arch = std::env::consts::ARCH.to_string();
+        let family = Some(std::env::consts::FAMILY.to_string()).filter(|family| !family.is_empty());
+        let env = if cfg!(target_env = "gnu") {
+            "gnu"
+        } else if cfg!(target_env = "musl") {
+            "musl"
+        } else if cfg!(target_env = "msvc") {
+            "msvc"
+        } else {
+            ""
+        };
+        let vendor = match os.as_str() {
+            "macos" | "ios" => "apple",
+            "windows" => "pc",
+            _ => "unknown",
+        };
+        TargetInfo {
+            pointer_width: pointer_width(&arch).to_string(),
+            endian: if cfg!(target_endian = "big") { "big" } else { "little" }.to_string(),
+            panic: panic_strategy(&arch, &os).to_string(),
+            max_atomic_width: max_atomic_width(&arch),
+            vendor: vendor.to_string(),
+            env: env.to_string(),
+            arch,
+            os,
+            family,
+        }
+    }
+
+    /// ターゲットトリプル（`x86_64-unknown-linux-gnu`など）からターゲット情報を作る
+    pub fn from_triple(triple: &str) -> TargetInfo {
+        let parts: Vec<&str> = triple.split('-').collect();
+        let arch = parts.first().copied().unwrap_or_default();
+        let (vendor, rest) = match parts.len() {
+            0 | 1 => ("unknown", &[][..]),
+            // wasm32-wasi のようにベンダーが省略されている
+            2 => ("unknown", &parts[1..]),
+            // thumbv6m-none-eabi のような組み込み向けもベンダーが省略されている
+            _ if parts[1] == "none" => ("unknown", &parts[1..]),
+            _ => (parts[1], &parts[2..]),
+        };
+        let os = match rest.first().copied().unwrap_or("none") {
+            "darwin" => "macos",
+            "unknown" => "none",
+            os => os,
+        };
+        let env = rest.get(1).copied().unwrap_or_default();
+        // thumbv6mはarmとして扱うが、アトミック型を持たない
+        let atomic_width = if arch.starts_with("thumbv6m") { Some(0) } else { None };
+        let arch = match arch {
+            "i386" | "i586" | "i686" => "x86",
+            "arm64" => "aarch64",
+            arch if arch.starts_with("armv7") || arch.starts_with("thumbv") => "arm",
+            arch => arch,
+        };
+        let family = match os {
+            "windows" => Some("windows"),
+            "linux" | "macos" | "ios" | "android" | "freebsd" | "netbsd" | "openbsd" | "dragonfly"
+            | "solaris" | "illumos" | "haiku" | "emscripten" => Some("unix"),
+            _ if arch.starts_with("wasm") => Some("wasm"),
+            _ => None,
+        };
+        let big_endian = matches!(arch, "s390x" | "powerpc" | "powerpc64" | "mips" | "mips64" | "sparc" | "sparc64");
+        TargetInfo {
+            arch: arch.to_string(),
+            vendor: vendor.to_string(),
+            os: os.to_string(),
+            env: env.to_string(),
+            family: family.map(str::to_string),
+            pointer_width: pointer_width(arch).to_string(),
+            endian: if big_endian { "big" } else { "little" }.to_string(),
+            panic: panic_strategy(arch, os).to_string(),
+            max_atomic_width: atomic_width.unwrap_or_else(|| max_atomic_width(arch)),
+        }
+    }
+
+    /// `target_has_atomic = "..."`が満たされるかを確認する
+    /// 
+    /// # Arguments
+    /// 
+    /// * `width` - `8`, `16`, `32`, `64`, `128`, `ptr`のいずれか
+    /// 
+    /// # Returns
+    /// 
+    /// * `bool` - その幅のアトミック型があるか
+    pub fn has_atomic(&self, width: &str) -> bool {
+        let width = if width == "ptr" { self.pointer_width.as_str() } else { width };
+        width.parse::<u32>().is_ok_and(|width| width <= self.max_atomic_width)
+    }
+}
+
+/// アーキテクチャからポインタ幅を求める
+fn pointer_width(arch: &str) -> &'static str {
+    match arch {
+        "x86_64" | "aarch64" | "powerpc64" | "powerpc64le" | "riscv64gc" | "riscv64" | "s390x"
+        | "mips64" | "mips64el" | "sparc64" | "loongarch64" | "wasm64" => "64",
+        "avr" | "msp430" => "16",
+        _ => "32",
+    }
+}
+
+/// アーキテクチャとOSから、既定のパニック時の動作を求める（wasmと組み込み向けは`abort`）
+fn panic_strategy(arch: &str, os: &str) -> &'static str {
+    if (arch.starts_with("wasm") && os != "emscripten") || os == "none" {
+        "abort"
+    } else {
+        "unwind"
+    }
+}
+
+/// アーキテクチャから、アトミック操作ができる最大のビット幅を求める
+fn max_atomic_width(arch: &str) -> u32 {
+    match arch {
+        "aarch64" => 128,
+        "avr" => 8,
+        "msp430" => 16,
+        "riscv32" | "riscv32i" | "riscv32imc" | "mips" | "mipsel" | "powerpc" | "sparc" => 32,
+        _ => 64,
+    }
+}
+
+/// リリースビルドの拡張モジュールでは無効な`cfg`の名前
+const DISABLED_CFGS: &[&str] = &["test", "debug_assertions", "doc", "doctest", "miri", "proc_macro", "overflow_checks"];
+
+/// `cfg`の評価に使うコンテキスト
+#[derive(Debug)]
+pub struct CfgContext {
+    /// 有効なフィーチャー
+    features: HashSet<String>,
+    /// ターゲット情報
+    target: TargetInfo,
+    /// 評価方法を知らず、無効として扱った`cfg`の名前
+    unknown: Mutex<BTreeSet<String>>,
+}
+
+impl Default for CfgContext {
+    fn default() -> Self {
+        CfgContext { features: HashSet::new(), target: TargetInfo::host(), unknown: Mutex::default() }
+    }
+}
+
+impl CfgContext {
+    /// Cargo.tomlの[features]とコマンドライン引数から、有効なフィーチャーを決める
+    /// 
+    /// # Arguments
+    /// 
+    /// * `cargo_doc` - Cargo.tomlのDocumentMut
+    /// * `options` - フィーチャーとターゲットの指定
+    /// 
+    /// # Returns
+    /// 
+    /// * `CfgContext` - 評価用のコンテキスト
+    pub fn new(cargo_doc: &DocumentMut, options: &CfgOptions) -> CfgContext {
+        let features_table = cargo_doc.get("features").and_then(|features| features.as_table_like());
+        let optional_deps = get_optional_dependencies(cargo_doc);
+
+        let mut pending: Vec<String> = options
+            .features
+            .iter()
+            .flat_map(|feature| feature.split([',', ' ']))
+            .filter(|feature| !feature.is_empty() && !feature.contains('/'))
+            .map(str::to_string)
+            .collect();
+        if !options.no_default_features {
+            pending.push("default".to_string());
+        }
+        if options.all_features {
+            if let Some(table) = features_table {
+                pending.extend(table.iter().map(|(name, _)| name.to_string()));
+            }
+            pending.extend(optional_deps.iter().cloned());
+        }
+
+        // フィーチャーが有効にする別のフィーチャーを再帰的にたどる
+        let mut features = HashSet::new();
+        while let Some(feature) = pending.pop() {
+            if !features.insert(feature.clone()) {
+                continue;
+            }
+            let Some(enables) = features_table
+                .and_then(|table| table.get(&feature))
+                .and_then(|item| item.as_array())
+            else {
+                continue;
+            };
+            for enabled in enables.iter().filter_map(|value| value.as_str()) {
+                if enabled.starts_with("dep:") {
+                    continue;
+                }
+                match enabled.split_once('/') {
+                    // "serde/std" は任意依存のserdeも有効にする（"serde?/std"は有効にしない）
+                    Some((dep, _)) if optional_deps.iter().any(|name| name == dep) => pending.push(dep.to_string()),
+                    Some(_) => {}
+                    None => pending.push(enabled.to_string()),
+                }
+            }
+        }
+        let target = options.target.as_deref().map_or_else(TargetInfo::host, TargetInfo::from_triple);
+        CfgContext { features, target, unknown: Mutex::default() }
+    }
+
+    /// 同じフィーチャーとターゲットで、未知の`cfg`の記録だけを空にしたコンテキストを作る
+    /// 
+    /// ファイルごとに未知の`cfg`を集めてキャッシュに保存するために使う。
+    pub fn scoped(&self) -> CfgContext {
+        CfgContext { features: self.features.clone(), target: self.target.clone(), unknown: Mutex::default() }
+    }
+
+    /// これまでの評価で見つかった未知の`cfg`の名前を取り出す
+    /// 
+    /// # Returns
+    /// 
+    /// * `Vec<String>` - 無効として扱った`cfg`の名前（名前順、重複なし）
+    pub fn take_unknown(&self) -> Vec<String> {
+        std::mem::take(&mut *self.unknown.lock().unwrap()).into_iter().collect()
+    }
+
+    /// 評価方法を知らない`cfg`の名前を記録し、無効として扱う
+    fn unknown_cfg(&self, path: &syn::Path) -> bool {
+        let name = path.segments.iter().map(|segment| segment.ident.to_string()).collect::<Vec<_>>().join("::");
+        self.unknown.lock().unwrap().insert(name);
+        false
+    }
+
+    /// キャッシュのキーに使う、有効なフィーチャーとターゲットを表す文字列
+    pub fn cache_key(&self) -> String {
+        let mut features: Vec<&str> = self.features.iter().map(String::as_str).collect();
+        features.sort_unstable();
+        format!("features={};target={:?}", features.join(","), self.target)
+    }
+
+    /// フィーチャーが有効かどうかを確認する
+    pub fn has_feature(&self, feature: &str) -> bool {
+        self.features.contains(feature)
+    }
+
+    /// アトリビュートの`#[cfg(...)]`がすべて満たされているかを確認する
+    pub fn is_enabled(&self, attrs: &[Attribute]) -> bool {
+        attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("cfg"))
+            .all(|attr| attr.parse_args::<Meta>().is_ok_and(|predicate| self.eval(&predicate)))
+    }
+
+    /// cfgの述語を評価する
+    /// 
+    /// # Arguments
+    /// 
+    /// * `predicate` - `all(...)`, `any(...)`, `not(...)`, `key = "value"`, `key`のいずれか
+    /// 
+    /// # Returns
+    /// 
+    /// * `bool` - 述語が満たされているか
+    pub fn eval(&self, predicate: &Meta) -> bool {
+        match predicate {
+            Meta::List(list) => {
+                let Ok(args) = list.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated) else {
+                    return false;
+                };
+                if list.path.is_ident("all") {
+                    args.iter().all(|arg| self.eval(arg))
+                } else if list.path.is_ident("any") {
+                    args.iter().any(|arg| self.eval(arg))
+                } else if list.path.is_ident("not") {
+                    args.len() == 1 && !self.eval(&args[0])
+                } else {
+                    self.unknown_cfg(&list.path)
+                }
+            }
+            Meta::NameValue(name_value) => {
+                let syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(value), .. }) = &name_value.value else {
+                    return false;
+                };
+                let value = value.value();
+                let Some(key) = name_value.path.get_ident() else { return self.unknown_cfg(&name_value.path) };
+                let target = &self.target;
+                match key.to_string().as_str() {
+                    "feature" => self.has_feature(&value),
+                    "target_os" => target.os == value,
+                    "target_arch" => target.arch == value,
+                    "target_vendor" => target.vendor == value,
+                    "target_env" => target.env == value,
+                    "target_family" => target.family.as_deref() == Some(value.as_str()),
+                    "target_pointer_width" => target.pointer_width == value,
+                    "target_endian" => target.endian == value,
+                    "panic" => target.panic == value,
+                    "target_has_atomic" => target.has_atomic(&value),
+                    _ => self.unknown_cfg(&name_value.path),
+                }
+            }
+            Meta::Path(path) => match path.get_ident().map(|ident| ident.to_string()).as_deref() {
+                Some("unix") => self.target.family.as_deref() == Some("unix"),
+                Some("windows") => self.target.family.as_deref() == Some("windows"),
+                Some(name) if DISABLED_CFGS.contains(&name) => false,
+                _ => self.unknown_cfg(path),
+            },
+        }
+    }
+
+    /// `#[cfg_attr(predicate, attr, ...)]`を展開する
+    /// 
+    /// 述語が満たされていれば中のアトリビュートに置き換え、満たされていなければ取り除く。
+    pub fn expand_cfg_attr(&self, attrs: &mut Vec<Attribute>) {
+        while attrs.iter().any(|attr| attr.path().is_ident("cfg_attr")) {
+            let mut expanded = Vec::with_capacity(attrs.len());
+            for attr in attrs.drain(..) {
+                if !attr.path().is_ident("cfg_attr") {
+                    expanded.push(attr);
+                    continue;
+                }
+                let Ok(args) = attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated) else {
+                    continue;
+                };
+                let mut args = args.into_iter();
+                if args.next().is_some_and(|predicate| self.eval(&predicate)) {
+                    expanded.extend(args.map(|meta| Attribute { meta, ..attr.clone() }));
+                }
+            }
+            *attrs = expanded;
+        }
+    }
+
+    /// アイテムのリストから無効なものを取り除き、残ったアイテムの`cfg_attr`を展開する
+    /// 
+    /// 関数の引数と本体の文（`#[pymodule]`の`m.add(...)`など）、implのメソッドなど、アイテムの中身も同様に処理する。
+    pub fn configure_items(&self, items: &mut Vec<Item>) {
+        for item in items.iter_mut() {
+            if let Some(attrs) = item_attrs_mut(item) {
+                self.expand_cfg_attr(attrs);
+            }
+        }
+        items.retain(|item| self.is_enabled(item_attrs(item)));
+        for item in items.iter_mut() {
+            match item {
+                Item::Fn(item_fn) => {
+                    self.configure_fn_args(&mut item_fn.sig);
+                    self.configure_stmts(&mut item_fn.block.stmts);
+                }
+                Item::Impl(item_impl) => {
+                    for impl_item in item_impl.items.iter_mut() {
+                        if let syn::ImplItem::Fn(method) = impl_item {
+                            self.expand_cfg_attr(&mut method.attrs);
+                            self.configure_fn_args(&mut method.sig);
+                        }
+                    }
+                    item_impl.items.retain(|impl_item| match impl_item {
+                        syn::ImplItem::Fn(method) => self.is_enabled(&method.attrs),
+                        syn::ImplItem::Const(constant) => self.is_enabled(&constant.attrs),
+                        _ => true,
+                    });
+                }
+                Item::Struct(item_struct) => {
+                    for field in item_struct.fields.iter_mut() {
+                        self.expand_cfg_attr(&mut field.attrs);
+                    }
+                    if let syn::Fields::Named(fields) = &mut item_struct.fields {
+                        fields.named = std::mem::take(&mut fields.named)
+                            .into_iter()
+                            .filter(|field| self.is_enabled(&field.attrs))
+                            .collect();
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// 関数本体の文に付いた`cfg`を評価する（入れ子のブロックの中までは見ない）
+    fn configure_stmts(&self, stmts: &mut Vec<syn::Stmt>) {
+        stmts.retain_mut(|stmt| match stmt_attrs_mut(stmt) {
+            Some(attrs) => {
+                self.expand_cfg_attr(attrs);
+                self.is_enabled(attrs)
+            }
+            None => true,
+        });
+    }
+
+    /// 関数の引数に付いた`cfg`を評価する
+    fn configure_fn_args(&self, sig: &mut syn::Signature) {
+        for arg in sig.inputs.iter_mut() {
+            if let syn::FnArg::Typed(pat_type) = arg {
+                self.expand_cfg_attr(&mut pat_type.attrs);
+            }
+        }
+        sig.inputs = std::mem::take(&mut sig.inputs)
+            .into_iter()
+            .filter(|arg| match arg {
+                syn::FnArg::Typed(pat_type) => self.is_enabled(&pat_type.attrs),
+                syn::FnArg::Receiver(receiver) => self.is_enabled(&receiver.attrs),
+            })
+            .collect();
+    }
+}
+
+/// ビルドスクリプトの環境変数（`CARGO_FEATURE_<NAME>`）から、有効なフィーチャーの名前を求める
+/// 
+/// 環境変数の名前は大文字で`-`が`_`になっているため、Cargo.tomlの[features]と任意依存の名前と照らし合わせる。
+/// 
+/// # Arguments
+/// 
+/// * `cargo_doc` - Cargo.tomlのDocumentMut
+/// * `vars` - 環境変数
+/// 
+/// # Returns
+/// 
+/// * `Vec<String>` - 有効なフィーチャー（`default`を含む）
+pub fn features_from_env(cargo_doc: &DocumentMut, vars: impl IntoIterator<Item = (String, String)>) -> Vec<String> {
+    let enabled: Vec<String> = vars
+        .into_iter()
+        .filter_map(|(name, _)| name.strip_prefix("CARGO_FEATURE_").map(str::to_string))
+        .collect();
+    let mut declared: Vec<String> = cargo_doc
+        .get("features")
+        .and_then(|features| features.as_table_like())
+        .map(|table| table.iter().map(|(name, _)| name.to_string()).collect())
+        .unwrap_or_default();
+    declared.extend(get_optional_dependencies(cargo_doc));
+    declared
+        .into_iter()
+        .filter(|name| enabled.contains(&name.to_uppercase().replace('-', "_")))
+        .collect()
+}
+
+/// Cargo.tomlの任意依存（optional = true）の名前を取得する
+fn get_optional_dependencies(cargo_doc: &DocumentMut) -> Vec<String> {
+    cargo_doc
+        .get("dependencies")
+        .and_then(|deps| deps.as_table_like())
+        .map(|deps| {
+            deps.iter()
+                .filter(|(_, dep)| dep.get("optional").and_then(|optional| optional.as_bool()) == Some(true))
+                .map(|(name, _)| name.to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// アイテムのアトリビュートを取得する
+fn item_attrs(item: &Item) -> &[Attribute] {
+    match item {
+        Item::Const(item) => &item.attrs,
+        Item::Enum(item) => &item.attrs,
+        Item::ExternCrate(item) => &item.attrs,
+        Item::Fn(item) => &item.attrs,
+        Item::ForeignMod(item) => &item.attrs,
+        Item::Impl(item) => &item.attrs,
+        Item::Macro(item) => &item.attrs,
+        Item::Mod(item) => &item.attrs,
+        Item::Static(item) => &item.attrs,
+        Item::Struct(item) => &item.attrs,
+        Item::Trait(item) => &item.attrs,
+        Item::TraitAlias(item) => &item.attrs,
+        Item::Type(item) => &item.attrs,
+        Item::Union(item) => &item.attrs,
+        Item::Use(item) => &item.attrs,
+        _ => &[],
+    }
+}
+
+/// アイテムのアトリビュートを可変で取得する
+fn item_attrs_mut(item: &mut Item) -> Option<&mut Vec<Attribute>> {
+    match item {
+        Item::Const(item) => Some(&mut item.attrs),
+        Item::Enum(item) => Some(&mut item.attrs),
+        Item::ExternCrate(item) => Some(&mut item.attrs),
+        Item::Fn(item) => Some(&mut item.attrs),
+        Item::ForeignMod(item) => Some(&mut item.attrs),
+        Item::Impl(item) => Some(&mut item.attrs),
+        Item::Macro(item) => Some(&mut item.attrs),
+        Item::Mod(item) => Some(&mut item.attrs),
+        Item::Static(item) => Some(&mut item.attrs),
+        Item::Struct(item) => Some(&mut item.attrs),
+        Item::Trait(item) => Some(&mut item.attrs),
+        Item::TraitAlias(item) => Some(&mut item.attrs),
+        Item::Type(item) => Some(&mut item.attrs),
+        Item::Union(item) => Some(&mut item.attrs),
+        Item::Use(item) => Some(&mut item.attrs),
+        _ => None,
+    }
+}
+
+/// 文のアトリビュートを可変で取得する（式文のアトリビュートは一番外側の式に付く）
+fn stmt_attrs_mut(stmt: &mut syn::Stmt) -> Option<&mut Vec<Attribute>> {
+    match stmt {
+        syn::Stmt::Local(local) => Some(&mut local.attrs),
+        syn::Stmt::Item(item) => item_attrs_mut(item),
+        syn::Stmt::Macro(stmt_macro) => Some(&mut stmt_macro.attrs),
+        syn::Stmt::Expr(expr, _) => match expr {
+            syn::Expr::Try(expr) => Some(&mut expr.attrs),
+            syn::Expr::MethodCall(expr) => Some(&mut expr.attrs),
+            syn::Expr::Call(expr) => Some(&mut expr.attrs),
+            syn::Expr::Macro(expr) => Some(&mut expr.attrs),
+            syn::Expr::Assign(expr) => Some(&mut expr.attrs),
+            syn::Expr::Block(expr) => Some(&mut expr.attrs),
+            syn::Expr::If(expr) => Some(&mut expr.attrs),
+            syn::Expr::Paren(expr) => Some(&mut expr.attrs),
+            _ => None,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CARGO_TOML: &str = r#"
+    [package]
+    name = "bindings"
+
+    [dependencies]
+    numpy = { version = "0.22", optional = true }
+
+    [features]
+    default = ["python"]
+    python = []
+    gpu = ["cuda"]
+    cuda = []
+    array = ["numpy/half"]
+    "#;
+
+    fn context(options: &CfgOptions) -> CfgContext {
+        CfgContext::new(&CARGO_TOML.parse::<DocumentMut>().unwrap(), options)
+    }
+
+    #[test]
+    fn test_features_from_env() {
+        let vars = ["CARGO_FEATURE_DEFAULT", "CARGO_FEATURE_GPU", "CARGO_FEATURE_NUMPY", "CARGO_MANIFEST_DIR"]
+            .map(|name| (name.to_string(), "1".to_string()));
+        let features = features_from_env(&CARGO_TOML.parse::<DocumentMut>().unwrap(), vars);
+        assert_eq!(features, vec!["default", "gpu", "numpy"]);
+
+        let doc = "[features]\nfast-math = []\n".parse::<DocumentMut>().unwrap();
+        let vars = [("CARGO_FEATURE_FAST_MATH".to_string(), "1".to_string())];
+        assert_eq!(features_from_env(&doc, vars), vec!["fast-math"]);
+    }
+
+    #[test]
+    fn test_resolve_features() {
+        let ctx = context(&CfgOptions::default());
+        assert!(ctx.has_feature("python"));
+        assert!(!ctx.has_feature("gpu"));
+
+        let ctx = context(&CfgOptions {
+            features: vec!["gpu,array".to_string()],
+            no_default_features: true,
+            ..Default::default()
+        });
+        assert!(!ctx.has_feature("python"));
+        assert!(ctx.has_feature("gpu"));
+        assert!(ctx.has_feature("cuda"));
+        assert!(ctx.has_feature("numpy"));
+
+        let ctx = context(&CfgOptions { all_features: true, ..Default::default() });
+        assert!(ctx.has_feature("gpu") && ctx.has_feature("array") && ctx.has_feature("numpy"));
+    }
+
+    #[test]
+    fn test_eval_predicates() {
+        let ctx = context(&CfgOptions {
+            target: Some("x86_64-pc-windows-msvc".to_string()),
+            ..Default::default()
+        });
+        let eval = |code: &str| ctx.eval(&syn::parse_str::<Meta>(code).unwrap());
+
+        assert!(eval(r#"feature = "python""#));
+        assert!(!eval(r#"feature = "gpu""#));
+        assert!(eval("windows"));
+        assert!(!eval("unix"));
+        assert!(eval(r#"all(target_os = "windows", target_env = "msvc", target_pointer_width = "64")"#));
+        assert!(eval(r#"any(feature = "gpu", not(target_arch = "aarch64"))"#));
+        assert!(eval(r#"all(panic = "unwind", target_has_atomic = "64", target_has_atomic = "ptr")"#));
+        assert!(!eval(r#"target_has_atomic = "128""#));
+        assert!(!eval("test"));
+        assert!(ctx.take_unknown().is_empty());
+
+        // 未知のcfgは無効として扱い、名前を一度だけ記録する
+        assert!(!eval("Py_LIMITED_API"));
+        assert!(!eval(r#"any(Py_LIMITED_API, target_feature = "avx2")"#));
+        assert!(eval(r#"not(tokio_unstable)"#));
+        assert_eq!(ctx.take_unknown(), vec!["Py_LIMITED_API", "target_feature", "tokio_unstable"]);
+        assert!(ctx.take_unknown().is_empty());
+    }
+
+    #[test]
+    fn test_target_from_triple() {
+        let target = TargetInfo::from_triple("aarch64-apple-darwin");
+        assert_eq!(target.os, "macos");
+        assert_eq!(target.vendor, "apple");
+        assert_eq!(target.family.as_deref(), Some("unix"));
+
+        let target = TargetInfo::from_triple("wasm32-wasi");
+        assert_eq!(target.os, "wasi");
+        assert_eq!(target.family.as_deref(), Some("wasm"));
+        assert_eq!(target.pointer_width, "32");
+        assert_eq!(target.panic, "abort");
+        assert!(target.has_atomic("ptr") && target.has_atomic("64"));
+
+        let target = TargetInfo::from_triple("thumbv6m-none-eabi");
+        assert_eq!(target.panic, "abort");
+        assert!(!target.has_atomic("8"));
+
+        let target = TargetInfo::from_triple("riscv32imc-unknown-none-elf");
+        assert!(target.has_atomic("32") && !target.has_atomic("64"));
+        assert_eq!(TargetInfo::from_triple("aarch64-apple-darwin").panic, "unwind");
+    }
+
+    #[test]
+    fn test_configure_items() {
+        let ctx = context(&CfgOptions::default());
+        let mut file: syn::File = syn::parse_str(r#"
+            #[cfg(feature = "gpu")]
+            #[pyfunction]
+            fn gpu_only() {}
+
+            #[cfg_attr(feature = "python", pyfunction)]
+            #[cfg_attr(feature = "gpu", pyo3(name = "gpu"))]
+            fn maybe_exported(#[cfg(feature = "gpu")] device: u32, value: i32) {}
+
+            #[cfg(test)]
+            mod tests;
+        "#).unwrap();
+        ctx.configure_items(&mut file.items);
+
+        assert_eq!(file.items.len(), 1);
+        let Item::Fn(item_fn) = &file.items[0] else { panic!("関数であるべき") };
+        assert_eq!(item_fn.sig.ident, "maybe_exported");
+        assert_eq!(item_fn.attrs.len(), 1);
+        assert!(item_fn.attrs[0].path().is_ident("pyfunction"));
+        assert_eq!(item_fn.sig.inputs.len(), 1);
+    }
+
+    #[test]
+    fn test_configure_module_body() {
+        let ctx = context(&CfgOptions::default());
+        let mut file: syn::File = syn::parse_str(r#"
+            #[pymodule]
+            fn _core(m: &Bound<'_, PyModule>) -> PyResult<()> {
+                m.add("VERSION", 1)?;
+                #[cfg(feature = "gpu")]
+                m.add("GPU", true)?;
+                #[cfg(feature = "python")]
+                m.add("PYTHON", true)?;
+                #[cfg_attr(feature = "python", cfg(feature = "gpu"))]
+                m.add("BOTH", true)?;
+                Ok(())
+            }
+        "#).unwrap();
+        ctx.configure_items(&mut file.items);
+
+        let mut constants = vec![];
+        crate::stubgen::parser::parse_module_constants(&file.items, &mut constants);
+        let names: Vec<&str> = constants.iter().map(|constant| constant.name.as_str()).collect();
+        assert_eq!(names, vec!["VERSION", "PYTHON"]);
+    }
+}
@@ -0,0 +1,512 @@
+//! スタブ検査モジュール
+//! 
+//! 生成したスタブを、ローカルにインストールされている`stubtest`（mypy）と`pyright --verifytypes`で
+//! ビルドした拡張モジュールと照らし合わせて検査します。
+//! 検査ツールの出力は共通の診断に変換し、問題のある関数・定数が定義されているRustのソースの場所を添えて表示します。
+
+use std::error::Error;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str::FromStr;
+use crate::stubgen::analyzer::ItemSource;
+use crate::stubgen::diagnostic::{Diagnostic as StubDiagnostic, Severity};
+use crate::stubgen::locator::ProjectInfo;
+use crate::stubgen::logging::{Logger, Verbosity};
+use crate::stubgen::{deny_warnings, generate_projects, locate_projects, print_report, verify, GenerateOptions};
+
+/// スタブの検査ツール
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Checker {
+    /// mypyの`stubtest`（スタブと実行時のモジュールの食い違い）
+    Stubtest,
+    /// `pyright --verifytypes`（公開名の型の完全性）
+    Pyright,
+}
+
+impl FromStr for Checker {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "stubtest" => Ok(Checker::Stubtest),
+            "pyright" => Ok(Checker::Pyright),
+            _ => Err(format!("unknown checker `{}` (expected stubtest or pyright)", s)),
+        }
+    }
+}
+
+impl fmt::Display for Checker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Checker::Stubtest => write!(f, "stubtest"),
+            Checker::Pyright => write!(f, "pyright"),
+        }
+    }
+}
+
+/// 検査のオプション
+#[derive(Debug, Default, Clone)]
+pub struct CheckOptions {
+    /// 使う検査ツール（空ならインストールされているものすべて）
+    pub checkers: Vec<Checker>,
+    /// 使うPythonインタプリタ（未指定なら仮想環境を探す）
+    pub python: Option<PathBuf>,
+    /// ビルドせず、インストール済みのモジュールを検査する
+    pub no_build: bool,
+}
+
+/// 検査ツールが報告した問題
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// 報告した検査ツール
+    pub checker: Checker,
+    /// `error`・`warning`・`note`
+    pub severity: String,
+    /// 問題のある名前（`pkg._core.add`など）
+    pub symbol: Option<String>,
+    /// メッセージ
+    pub message: String,
+    /// スタブの中の場所（ファイルと1始まりの行）
+    pub stub_location: Option<(PathBuf, usize)>,
+}
+
+impl Diagnostic {
+    /// エラーかどうか
+    pub fn is_error(&self) -> bool {
+        self.severity == "error"
+    }
+}
+
+/// スタブを生成し、検査ツールで検査する
+/// 
+/// # Arguments
+/// 
+/// * `options` - スタブ生成のオプション
+/// * `check_options` - 検査のオプション
+/// 
+/// # Returns
+/// 
+/// * `Result<(), Box<dyn Error>>` - 処理結果（検査ツールがエラーを報告すればエラー）
+pub fn check_stubs(options: &GenerateOptions, check_options: &CheckOptions) -> Result<(), Box<dyn Error>> {
+    let logger = options.logger();
+    let project_infos = locate_projects(options)?;
+
+    let mut errors = 0;
+    let mut failed = 0;
+    let mut warnings = 0;
+    for (result, project_info) in generate_projects(&project_infos, options)?.into_iter().zip(&project_infos) {
+        let report = match result {
+            Ok(report) => report,
+            Err(e) => {
+                logger.error(format!("failed to generate stubs for {}: {}", project_info.project_name, e));
+                failed += 1;
+                continue;
+            }
+        };
+        print_report(&report, &logger);
+        warnings += report.warnings.len();
+
+        let project_info = options.overrides.apply(project_info);
+        logger.log(Verbosity::Normal, &format!("Checking project: {} ({})", project_info.project_name, project_info.python_module));
+        match check_project(&project_info, check_options, &logger) {
+            Ok(diagnostics) => {
+                for diagnostic in &diagnostics {
+                    logger.diagnostic(&to_stub_diagnostic(diagnostic, &project_info, &report.output_path, &report.sources));
+                }
+                errors += diagnostics.iter().filter(|diagnostic| diagnostic.is_error()).count();
+            }
+            Err(e) => {
+                logger.error(format!("failed to check {}: {}", project_info.project_name, e));
+                failed += 1;
+            }
+        }
+    }
+    if failed > 0 {
+        return Err(format!("{} project(s) could not be checked", failed).into());
+    }
+    if errors > 0 {
+        return Err(format!("{} error(s) reported by type checkers", errors).into());
+    }
+    deny_warnings(options, warnings)
+}
+
+/// 1つのプロジェクトを検査ツールで検査する
+fn check_project(
+    project_info: &ProjectInfo,
+    check_options: &CheckOptions,
+    logger: &Logger,
+) -> Result<Vec<Diagnostic>, Box<dyn Error>> {
+    let python = check_options.python.clone().unwrap_or_else(|| verify::find_python(&project_info.project_root));
+    if !check_options.no_build {
+        verify::build_module(project_info, &python)?;
+    }
+
+    let checkers = if check_options.checkers.is_empty() {
+        vec![Checker::Stubtest, Checker::Pyright]
+    } else {
+        check_options.checkers.clone()
+    };
+    let mut diagnostics = vec![];
+    for checker in checkers {
+        let found = match checker {
+            Checker::Stubtest => run_stubtest(project_info, &python)?,
+            Checker::Pyright => run_pyright(project_info, &python)?,
+        };
+        match found {
+            Some(found) => diagnostics.extend(found),
+            None => logger.warn(format!("{} is not installed; skipping", checker)),
+        }
+    }
+    Ok(diagnostics)
+}
+
+/// `python -m mypy.stubtest`を実行する（mypyがなければ`None`）
+fn run_stubtest(project_info: &ProjectInfo, python: &Path) -> Result<Option<Vec<Diagnostic>>, Box<dyn Error>> {
+    let output = Command::new(python)
+        .args(["-m", "mypy.stubtest", &project_info.python_module])
+        .env("MYPYPATH", stub_root(project_info))
+        .current_dir(&project_info.project_root)
+        .output()
+        .map_err(|e| format!("failed to run {}: {}", python.display(), e))?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if stderr.contains("No module named 'mypy") || stderr.contains("No module named mypy") {
+        return Ok(None);
+    }
+    let diagnostics = parse_stubtest_output(&String::from_utf8_lossy(&output.stdout));
+    if !output.status.success() && diagnostics.is_empty() {
+        let message = stderr.lines().rev().find(|line| !line.trim().is_empty()).unwrap_or("failed");
+        return Err(format!("stubtest failed: {}", message.trim()).into());
+    }
+    Ok(Some(diagnostics))
+}
+
+/// `pyright --verifytypes`を実行する（pyrightがなければ`None`）
+fn run_pyright(project_info: &ProjectInfo, python: &Path) -> Result<Option<Vec<Diagnostic>>, Box<dyn Error>> {
+    // verifytypesはパッケージ単位で検査する
+    let package = project_info.python_module.split('.').next().unwrap_or_default();
+    let output = match Command::new("pyright")
+        .args(["--verifytypes", package, "--ignoreexternal", "--outputjson", "--pythonpath"])
+        .arg(python)
+        .current_dir(&project_info.project_root)
+        .output()
+    {
+        Ok(output) => output,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("failed to run pyright: {}", e).into()),
+    };
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    parse_pyright_output(&stdout)
+        .map(Some)
+        .map_err(|e| format!("failed to read pyright output: {}", e).into())
+}
+
+/// スタブの検索パス（最上位のパッケージを含むディレクトリ）
+fn stub_root(project_info: &ProjectInfo) -> PathBuf {
+    let depth = project_info.python_module.matches('.').count();
+    project_info.output_dir.ancestors().nth(depth).unwrap_or(&project_info.output_dir).to_path_buf()
+}
+
+/// stubtestの出力を診断に変換する
+/// 
+/// `error: pkg._core.add is inconsistent, ...`で始まるブロックと、スタブ自体のエラー
+/// （`path.pyi:3: error: ...`）を読み取る。
+/// 
+/// # Arguments
+/// 
+/// * `output` - stubtestの標準出力
+/// 
+/// # Returns
+/// 
+/// * `Vec<Diagnostic>` - 診断（出力の順）
+fn parse_stubtest_output(output: &str) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = vec![];
+    for line in output.lines() {
+        if let Some(message) = line.strip_prefix("error: ") {
+            if message.starts_with("not checking stubs") {
+                continue;
+            }
+            let symbol = message.split_whitespace().next().map(str::to_string);
+            diagnostics.push(Diagnostic {
+                checker: Checker::Stubtest,
+                severity: "error".to_string(),
+                symbol,
+                message: message.to_string(),
+                stub_location: None,
+            });
+        } else if let Some(location) = line.strip_prefix("Stub: in file ") {
+            // 直前のエラーのスタブの場所
+            if let Some(diagnostic) = diagnostics.last_mut().filter(|d| d.stub_location.is_none()) {
+                diagnostic.stub_location = parse_location(location);
+            }
+        } else if let Some((location, message)) = line.split_once(": error: ") {
+            // スタブを読み込めなかった場合のmypyのエラー
+            if let Some(stub_location) = parse_location(location).filter(|(path, _)| path.extension().is_some_and(|ext| ext == "pyi")) {
+                diagnostics.push(Diagnostic {
+                    checker: Checker::Stubtest,
+                    severity: "error".to_string(),
+                    symbol: None,
+                    message: message.to_string(),
+                    stub_location: Some(stub_location),
+                });
+            }
+        }
+    }
+    diagnostics
+}
+
+/// `path:line`を読み取る
+fn parse_location(location: &str) -> Option<(PathBuf, usize)> {
+    let (path, line) = location.trim().rsplit_once(':')?;
+    Some((PathBuf::from(path), line.parse().ok()?))
+}
+
+/// `pyright --verifytypes --outputjson`の出力を診断に変換する
+/// 
+/// # Arguments
+/// 
+/// * `output` - pyrightの標準出力（JSON）
+/// 
+/// # Returns
+/// 
+/// * `Result<Vec<Diagnostic>, serde_json::Error>` - 名前ごとの診断（出力の順）
+fn parse_pyright_output(output: &str) -> Result<Vec<Diagnostic>, serde_json::Error> {
+    let json: serde_json::Value = serde_json::from_str(output)?;
+    let mut diagnostics = vec![];
+    let symbols = json["typeCompleteness"]["symbols"].as_array().cloned().unwrap_or_default();
+    let general = json["generalDiagnostics"].as_array().cloned().unwrap_or_default();
+    let entries = symbols
+        .iter()
+        .flat_map(|symbol| {
+            let name = symbol["name"].as_str().map(str::to_string);
+            let found = symbol["diagnostics"].as_array().cloned().unwrap_or_default();
+            found.into_iter().map(move |diagnostic| (name.clone(), diagnostic))
+        })
+        .chain(general.into_iter().map(|diagnostic| (None, diagnostic)));
+    for (symbol, diagnostic) in entries {
+        let severity = match diagnostic["severity"].as_str().unwrap_or("error") {
+            "information" => "note",
+            severity => severity,
+        };
+        // pyrightの行は0始まり
+        let stub_location = diagnostic["file"].as_str().zip(diagnostic["range"]["start"]["line"].as_u64())
+            .map(|(file, line)| (PathBuf::from(file), line as usize + 1));
+        diagnostics.push(Diagnostic {
+            checker: Checker::Pyright,
+            severity: severity.to_string(),
+            symbol,
+            message: diagnostic["message"].as_str().unwrap_or_default().to_string(),
+            stub_location,
+        });
+    }
+    Ok(diagnostics)
+}
+
+/// 検査ツールの診断を、対応するRustのソースとスタブの場所を持つ共通の診断に変換する
+/// 
+/// エラー以外（`warning`・`note`）は警告として扱い、`note`はメッセージに残す。
+/// 
+/// # Arguments
+/// 
+/// * `diagnostic` - 検査ツールの診断
+/// * `project_info` - プロジェクト情報
+/// * `stub_path` - 生成したスタブのパス
+/// * `sources` - 関数・定数が定義されている場所
+/// 
+/// # Returns
+/// 
+/// * `StubDiagnostic` - 表示する診断
+fn to_stub_diagnostic(
+    diagnostic: &Diagnostic,
+    project_info: &ProjectInfo,
+    stub_path: &Path,
+    sources: &[ItemSource],
+) -> StubDiagnostic {
+    let (severity, message) = match diagnostic.severity.as_str() {
+        "error" => (Severity::Error, diagnostic.message.clone()),
+        "warning" => (Severity::Warning, diagnostic.message.clone()),
+        severity => (Severity::Warning, format!("{}: {}", severity, diagnostic.message)),
+    };
+    let mut stub_diagnostic = StubDiagnostic {
+        severity,
+        message: format!("[{}] {}", diagnostic.checker, message),
+        locations: vec![],
+    };
+    if let Some(source) = rust_source(diagnostic, &project_info.python_module, stub_path, sources) {
+        stub_diagnostic = stub_diagnostic.with_location(&source.file, source.line, &source.name);
+    }
+    if let Some((path, line)) = &diagnostic.stub_location {
+        let item = diagnostic.symbol.clone().unwrap_or_else(|| project_info.python_module.clone());
+        stub_diagnostic = stub_diagnostic.with_location(path, *line, item);
+    }
+    stub_diagnostic
+}
+
+/// 診断の対象の関数・定数が定義されているRustのソースの場所を探す
+/// 
+/// 名前が分かればそれを使い、分からなければスタブの行を含むトップレベルの定義の名前を使う。
+/// 
+/// # Arguments
+/// 
+/// * `diagnostic` - 診断
+/// * `python_module` - 拡張モジュールのPythonでの名前
+/// * `stub_path` - 生成したスタブのパス
+/// * `sources` - 関数・定数が定義されている場所
+/// 
+/// # Returns
+/// 
+/// * `Option<&ItemSource>` - 定義されている場所（見つからなければ`None`）
+fn rust_source<'a>(
+    diagnostic: &Diagnostic,
+    python_module: &str,
+    stub_path: &Path,
+    sources: &'a [ItemSource],
+) -> Option<&'a ItemSource> {
+    let name = match &diagnostic.symbol {
+        Some(symbol) => symbol
+            .strip_prefix(python_module)
+            .and_then(|rest| rest.strip_prefix('.'))
+            .and_then(|rest| rest.split('.').next())
+            .map(str::to_string),
+        None => diagnostic.stub_location.as_ref()
+            .filter(|(path, _)| same_file(path, stub_path))
+            .and_then(|(_, line)| {
+                let stub = std::fs::read_to_string(stub_path).ok()?;
+                stub_item_at(&stub, *line)
+            }),
+    }?;
+    sources.iter().find(|source| source.name == name)
+}
+
+/// 同じファイルかどうか（相対パスと絶対パスの違いは無視する）
+fn same_file(a: &Path, b: &Path) -> bool {
+    a == b || a.canonicalize().ok().zip(b.canonicalize().ok()).is_some_and(|(a, b)| a == b)
+}
+
+/// スタブの指定した行（1始まり）を含むトップレベルの定義の名前
+fn stub_item_at(stub: &str, line: usize) -> Option<String> {
+    stub.lines()
+        .take(line)
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .find(|line| !line.starts_with([' ', '\t', '#', '@', ')']) && !line.trim().is_empty())
+        .map(|line| line.strip_prefix("def ").or_else(|| line.strip_prefix("async def ")).unwrap_or(line))
+        .map(|line| line.chars().take_while(|c| c.is_alphanumeric() || *c == '_').collect::<String>())
+        .filter(|name| !name.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_stubtest_output() {
+        let output = "error: lib_c._core.add is inconsistent, stub argument \"b\" differs from runtime argument \"c\"\nStub: in file /ws/python/lib_c/_core.pyi:5\ndef (a: builtins.int, b: builtins.int) -> builtins.int\nRuntime:\ndef (a, c)\n\nerror: not checking stubs due to mypy build errors:\n/ws/python/lib_c/_core.pyi:9: error: Name \"Point\" is not defined  [name-defined]\nFound 2 errors (checked 1 module)\n";
+        assert_eq!(parse_stubtest_output(output), vec![
+            Diagnostic {
+                checker: Checker::Stubtest,
+                severity: "error".to_string(),
+                symbol: Some("lib_c._core.add".to_string()),
+                message: "lib_c._core.add is inconsistent, stub argument \"b\" differs from runtime argument \"c\"".to_string(),
+                stub_location: Some((PathBuf::from("/ws/python/lib_c/_core.pyi"), 5)),
+            },
+            Diagnostic {
+                checker: Checker::Stubtest,
+                severity: "error".to_string(),
+                symbol: None,
+                message: "Name \"Point\" is not defined  [name-defined]".to_string(),
+                stub_location: Some((PathBuf::from("/ws/python/lib_c/_core.pyi"), 9)),
+            },
+        ]);
+    }
+
+    #[test]
+    fn test_parse_pyright_output() {
+        let output = r#"{
+            "typeCompleteness": {
+                "packageName": "lib_c",
+                "symbols": [
+                    {"name": "lib_c._core.add", "diagnostics": []},
+                    {"name": "lib_c._core.scale", "diagnostics": [{
+                        "file": "/ws/python/lib_c/_core.pyi",
+                        "severity": "error",
+                        "message": "Type is partially unknown",
+                        "range": {"start": {"line": 11, "character": 4}, "end": {"line": 11, "character": 9}}
+                    }]}
+                ]
+            },
+            "generalDiagnostics": [{"severity": "information", "message": "No py.typed file found"}]
+        }"#;
+        let diagnostics = parse_pyright_output(output).unwrap();
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].symbol.as_deref(), Some("lib_c._core.scale"));
+        assert_eq!(diagnostics[0].stub_location, Some((PathBuf::from("/ws/python/lib_c/_core.pyi"), 12)));
+        assert!(diagnostics[0].is_error());
+        assert_eq!(diagnostics[1].severity, "note");
+        assert_eq!(diagnostics[1].stub_location, None);
+    }
+
+    #[test]
+    fn test_rust_source() {
+        let sources = vec![
+            ItemSource { name: "add".to_string(), file: PathBuf::from("src/lib.rs"), line: 12 },
+            ItemSource { name: "scale".to_string(), file: PathBuf::from("src/api.rs"), line: 3 },
+        ];
+        let diagnostic = |symbol: &str| Diagnostic {
+            checker: Checker::Stubtest,
+            severity: "error".to_string(),
+            symbol: Some(symbol.to_string()),
+            message: String::new(),
+            stub_location: None,
+        };
+        let stub_path = Path::new("_core.pyi");
+        assert_eq!(rust_source(&diagnostic("lib_c._core.scale"), "lib_c._core", stub_path, &sources), Some(&sources[1]));
+        assert_eq!(rust_source(&diagnostic("lib_c._core.add.__doc__"), "lib_c._core", stub_path, &sources), Some(&sources[0]));
+        assert_eq!(rust_source(&diagnostic("lib_c.other.add"), "lib_c._core", stub_path, &sources), None);
+    }
+
+    #[test]
+    fn test_to_stub_diagnostic() {
+        let project_info = ProjectInfo { python_module: "lib_c._core".to_string(), ..Default::default() };
+        let sources = vec![ItemSource { name: "scale".to_string(), file: PathBuf::from("src/api.rs"), line: 3 }];
+        let stub_path = Path::new("/ws/python/lib_c/_core.pyi");
+        let diagnostic = Diagnostic {
+            checker: Checker::Pyright,
+            severity: "error".to_string(),
+            symbol: Some("lib_c._core.scale".to_string()),
+            message: "Type is partially unknown".to_string(),
+            stub_location: Some((stub_path.to_path_buf(), 12)),
+        };
+        let converted = to_stub_diagnostic(&diagnostic, &project_info, stub_path, &sources);
+        assert_eq!(converted.severity, Severity::Error);
+        assert_eq!(converted.message, "[pyright] Type is partially unknown");
+        let locations: Vec<(&Path, usize)> = converted.locations.iter().map(|location| (location.file.as_path(), location.line)).collect();
+        assert_eq!(locations, vec![(Path::new("src/api.rs"), 3), (stub_path, 12)]);
+
+        // noteは警告として、メッセージに残す
+        let note = Diagnostic { severity: "note".to_string(), symbol: None, stub_location: None, ..diagnostic };
+        let converted = to_stub_diagnostic(&note, &project_info, stub_path, &sources);
+        assert_eq!(converted.severity, Severity::Warning);
+        assert_eq!(converted.message, "[pyright] note: Type is partially unknown");
+        assert!(converted.locations.is_empty());
+    }
+
+    #[test]
+    fn test_stub_item_at() {
+        let stub = "from typing import Any\n\n# 足し算\ndef add(\n    a: int,\n    b: int,\n) -> int:\n    ...\n\nVERSION: str\n";
+        assert_eq!(stub_item_at(stub, 5), Some("add".to_string()));
+        assert_eq!(stub_item_at(stub, 8), Some("add".to_string()));
+        assert_eq!(stub_item_at(stub, 10), Some("VERSION".to_string()));
+    }
+
+    #[test]
+    fn test_stub_root() {
+        let project_info = ProjectInfo {
+            python_module: "lib_c._core".to_string(),
+            output_dir: PathBuf::from("/ws/python/lib_c"),
+            ..Default::default()
+        };
+        assert_eq!(stub_root(&project_info), PathBuf::from("/ws/python"));
+    }
+}
@@ -0,0 +1,463 @@
+//! プロジェクト設定モジュール
+//! 
+//! pyproject.tomlの`[tool.pystubgen]`テーブルを読み込み、検証します。
+//! 誤った値は問題のあるキーを添えたエラーとして報告し、コマンドラインの指定は設定より優先します。
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use toml_edit::DocumentMut;
+use crate::stubgen::locator::ProjectInfo;
+
+/// `[tool.pystubgen]`で使えるキー
+const CONFIG_KEYS: &[&str] = &[
+    "output-dir",
+    "include",
+    "exclude",
+    "include-items",
+    "exclude-items",
+    "docstring-style",
+    "python-version",
+    "type-map",
+    "header",
+    "features",
+    "all-features",
+    "no-default-features",
+    "docs",
+];
+
+/// ドキュメントコメントの出力形式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DocstringStyle {
+    /// 定義の前に`#`コメントとして書く
+    #[default]
+    Comment,
+    /// 定義の中に`"""docstring"""`として書く
+    Docstring,
+    /// 書かない
+    None,
+}
+
+impl FromStr for DocstringStyle {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "comment" => Ok(DocstringStyle::Comment),
+            "docstring" => Ok(DocstringStyle::Docstring),
+            "none" => Ok(DocstringStyle::None),
+            _ => Err(format!("expected one of `comment`, `docstring`, `none`, found `{}`", value)),
+        }
+    }
+}
+
+/// APIリファレンスの出力形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocsFormat {
+    /// Markdown（MkDocs向け）
+    Markdown,
+    /// reStructuredText（Sphinxの`py`ドメイン）
+    Rst,
+}
+
+impl DocsFormat {
+    /// 出力するファイルの拡張子
+    pub fn extension(self) -> &'static str {
+        match self {
+            DocsFormat::Markdown => "md",
+            DocsFormat::Rst => "rst",
+        }
+    }
+}
+
+impl FromStr for DocsFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "markdown" | "md" => Ok(DocsFormat::Markdown),
+            "rst" => Ok(DocsFormat::Rst),
+            _ => Err(format!("expected one of `markdown`, `rst`, found `{}`", value)),
+        }
+    }
+}
+
+/// 対象とするPythonのバージョン（`3.9`など）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PythonVersion {
+    pub major: u32,
+    pub minor: u32,
+}
+
+impl FromStr for PythonVersion {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("expected a version like `3.9`, found `{}`", value);
+        let (major, minor) = value.trim().split_once('.').ok_or_else(invalid)?;
+        Ok(PythonVersion {
+            major: major.parse().map_err(|_| invalid())?,
+            minor: minor.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+impl PythonVersion {
+    /// `requires-python`の指定から、対応する最も古いバージョンを求める
+    /// 
+    /// # Arguments
+    /// 
+    /// * `requires_python` - バージョン指定（`>=3.9`、`>=3.8,<4`、`~=3.10`など）
+    /// 
+    /// # Returns
+    /// 
+    /// * `Option<PythonVersion>` - 下限のバージョン（下限がなければ`None`）
+    pub fn from_requires_python(requires_python: &str) -> Option<PythonVersion> {
+        requires_python
+            .split(',')
+            .filter_map(|specifier| {
+                let specifier = specifier.trim();
+                // >3.8 は 3.8.1 などを含むため、下限はどの演算子でも書かれたバージョンになる
+                let version = ["~=", "==", ">=", ">"]
+                    .iter()
+                    .find_map(|operator| specifier.strip_prefix(operator))?;
+                let mut parts = version.trim().split('.');
+                let major = parts.next()?.parse().ok()?;
+                let minor = parts.next().map_or(Some(0), |minor| minor.parse().ok())?;
+                Some(PythonVersion { major, minor })
+            })
+            .max()
+    }
+
+    /// 組み込みのジェネリック型（`list[int]`、PEP 585）を使えるか
+    pub fn supports_builtin_generics(self) -> bool {
+        self >= PythonVersion { major: 3, minor: 9 }
+    }
+
+    /// `X | Y`形式の共用型（PEP 604）を使えるか
+    pub fn supports_union_operator(self) -> bool {
+        self >= PythonVersion { major: 3, minor: 10 }
+    }
+
+    /// `type`文とジェネリックな型エイリアス（PEP 695）を使えるか
+    pub fn supports_type_statement(self) -> bool {
+        self >= PythonVersion { major: 3, minor: 12 }
+    }
+}
+
+impl fmt::Display for PythonVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// `[tool.pystubgen]`の設定
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StubConfig {
+    /// スタブの出力先（pyproject.tomlからの相対パス）
+    pub output_dir: Option<PathBuf>,
+    /// 対象にするファイル（pyproject.tomlからの相対パスのグロブ、空ならすべて）
+    pub include: Vec<glob::Pattern>,
+    /// 対象から外すファイル
+    pub exclude: Vec<glob::Pattern>,
+    /// 出力する関数・定数の名前（グロブ、空ならすべて）
+    pub include_items: Vec<glob::Pattern>,
+    /// 出力しない関数・定数の名前
+    pub exclude_items: Vec<glob::Pattern>,
+    /// ドキュメントコメントの出力形式
+    pub docstring_style: DocstringStyle,
+    /// 対象とするPythonのバージョン（未指定なら`[project] requires-python`の下限）
+    pub python_version: Option<PythonVersion>,
+    /// Rustの型（パスまたは型名）からPythonの型への上書き
+    pub type_map: BTreeMap<String, String>,
+    /// スタブの先頭にそのまま書くテキスト
+    pub header: Option<String>,
+    /// 有効にするフィーチャー
+    pub features: Vec<String>,
+    /// すべてのフィーチャーを有効にする
+    pub all_features: bool,
+    /// defaultフィーチャーを無効にする
+    pub no_default_features: bool,
+    /// スタブと一緒に出力するAPIリファレンスの形式（未指定なら出力しない）
+    pub docs: Option<DocsFormat>,
+}
+
+impl StubConfig {
+    /// ファイルがスタブの対象かどうか
+    /// 
+    /// # Arguments
+    /// 
+    /// * `project_root` - pyproject.tomlのあるディレクトリ（パターンの基準）
+    /// * `file` - Rustのソースファイル
+    pub fn includes_file(&self, project_root: &Path, file: &Path) -> bool {
+        let relative = file.strip_prefix(project_root).unwrap_or(file);
+        let matches = |patterns: &[glob::Pattern]| patterns.iter().any(|pattern| pattern.matches_path(relative));
+        (self.include.is_empty() || matches(&self.include)) && !matches(&self.exclude)
+    }
+
+    /// 関数・定数がスタブに出力されるかどうか
+    pub fn includes_item(&self, name: &str) -> bool {
+        let matches = |patterns: &[glob::Pattern]| patterns.iter().any(|pattern| pattern.matches(name));
+        (self.include_items.is_empty() || matches(&self.include_items)) && !matches(&self.exclude_items)
+    }
+}
+
+/// コマンドラインで指定された、設定より優先する値
+#[derive(Debug, Clone, Default)]
+pub struct ConfigOverrides {
+    /// スタブの出力先（カレントディレクトリからの相対パス）
+    pub output_dir: Option<PathBuf>,
+    /// ドキュメントコメントの出力形式
+    pub docstring_style: Option<DocstringStyle>,
+    /// 対象とするPythonのバージョン
+    pub python_version: Option<PythonVersion>,
+    /// スタブの先頭に書くテキスト
+    pub header: Option<String>,
+    /// APIリファレンスの出力形式
+    pub docs: Option<DocsFormat>,
+}
+
+impl ConfigOverrides {
+    /// プロジェクトの情報にコマンドラインの指定を反映する
+    /// 
+    /// # Arguments
+    /// 
+    /// * `project_info` - 設定を読み込んだプロジェクトの情報
+    /// 
+    /// # Returns
+    /// 
+    /// * `ProjectInfo` - 指定を反映したプロジェクトの情報
+    pub fn apply(&self, project_info: &ProjectInfo) -> ProjectInfo {
+        let mut project_info = project_info.clone();
+        if let Some(output_dir) = &self.output_dir {
+            project_info.output_dir = std::env::current_dir().unwrap_or_default().join(output_dir);
+        }
+        let config = &mut project_info.config;
+        if let Some(docstring_style) = self.docstring_style {
+            config.docstring_style = docstring_style;
+        }
+        if let Some(python_version) = self.python_version {
+            config.python_version = Some(python_version);
+        }
+        if let Some(header) = &self.header {
+            config.header = Some(header.clone());
+        }
+        if let Some(docs) = self.docs {
+            config.docs = Some(docs);
+        }
+        project_info
+    }
+
+    /// 指定が対象のプロジェクトすべてに使えるか確かめる
+    /// 
+    /// 出力先はプロジェクトごとに分けられないため、複数のプロジェクトに同じ出力先を指定するとスタブが上書きし合う。
+    /// 
+    /// # Arguments
+    /// 
+    /// * `project_infos` - 指定を反映するプロジェクト
+    /// 
+    /// # Returns
+    /// 
+    /// * `Result<(), Box<dyn Error>>` - 出力先が指定されていて、プロジェクトが複数あればエラー
+    pub fn check_projects(&self, project_infos: &[ProjectInfo]) -> Result<(), Box<dyn Error>> {
+        if self.output_dir.is_some() && project_infos.len() > 1 {
+            let names: Vec<&str> = project_infos.iter().map(|project_info| project_info.project_name.as_str()).collect();
+            return Err(format!(
+                "--output-dir can only be used with a single project, but {} were found ({}); set [tool.pystubgen] output-dir in each project instead",
+                names.len(),
+                names.join(", ")
+            ).into());
+        }
+        Ok(())
+    }
+}
+
+/// pyproject.tomlの`[tool.pystubgen]`を読み込む
+/// 
+/// # Arguments
+/// 
+/// * `pyproject_doc` - pyproject.tomlのDocumentMut
+/// * `project_root` - pyproject.tomlのあるディレクトリ
+/// 
+/// # Returns
+/// 
+/// * `Result<StubConfig, Box<dyn Error>>` - 設定（テーブルがなければ既定値、値が誤っていればキーを添えたエラー）
+pub fn read_config(pyproject_doc: &DocumentMut, project_root: &Path) -> Result<StubConfig, Box<dyn Error>> {
+    // python-versionがなければrequires-pythonの下限を対象にする
+    let requires_python = pyproject_doc
+        .get("project")
+        .and_then(|project| project.get("requires-python"))
+        .and_then(|requires_python| requires_python.as_str())
+        .and_then(PythonVersion::from_requires_python);
+    let mut config = StubConfig { python_version: requires_python, ..Default::default() };
+    let Some(table) = pyproject_doc.get("tool").and_then(|tool| tool.get("pystubgen")) else {
+        return Ok(config);
+    };
+    let invalid = |key: &str, message: String| -> Box<dyn Error> {
+        format!("{}: invalid [tool.pystubgen] {}: {}", project_root.join("pyproject.toml").display(), key, message).into()
+    };
+    let table = table.as_table_like().ok_or_else(|| invalid("table", "expected a table".to_string()))?;
+
+    for (key, item) in table.iter() {
+        match key {
+            "output-dir" => config.output_dir = Some(PathBuf::from(get_str(item).map_err(|e| invalid(key, e))?)),
+            "include" => config.include = get_patterns(item).map_err(|e| invalid(key, e))?,
+            "exclude" => config.exclude = get_patterns(item).map_err(|e| invalid(key, e))?,
+            "include-items" => config.include_items = get_patterns(item).map_err(|e| invalid(key, e))?,
+            "exclude-items" => config.exclude_items = get_patterns(item).map_err(|e| invalid(key, e))?,
+            "docstring-style" => {
+                config.docstring_style = get_str(item).and_then(|value| value.parse()).map_err(|e| invalid(key, e))?;
+            }
+            "python-version" => {
+                config.python_version = Some(get_str(item).and_then(|value| value.parse()).map_err(|e| invalid(key, e))?);
+            }
+            "type-map" => {
+                let type_map = item.as_table_like().ok_or_else(|| invalid(key, "expected a table".to_string()))?;
+                for (rust_type, python_type) in type_map.iter() {
+                    let python_type = get_str(python_type).map_err(|e| invalid(&format!("type-map.\"{}\"", rust_type), e))?;
+                    config.type_map.insert(rust_type.to_string(), python_type);
+                }
+            }
+            "header" => config.header = Some(get_str(item).map_err(|e| invalid(key, e))?),
+            "features" => config.features = get_str_array(item).map_err(|e| invalid(key, e))?,
+            "all-features" => config.all_features = get_bool(item).map_err(|e| invalid(key, e))?,
+            "no-default-features" => config.no_default_features = get_bool(item).map_err(|e| invalid(key, e))?,
+            "docs" => config.docs = Some(get_str(item).and_then(|value| value.parse()).map_err(|e| invalid(key, e))?),
+            _ => return Err(invalid(key, format!("unknown key (expected one of {})", CONFIG_KEYS.join(", ")))),
+        }
+    }
+    Ok(config)
+}
+
+/// 文字列の値を読み取る
+fn get_str(item: &toml_edit::Item) -> Result<String, String> {
+    item.as_str().map(str::to_string).ok_or_else(|| "expected a string".to_string())
+}
+
+/// 真偽値を読み取る
+fn get_bool(item: &toml_edit::Item) -> Result<bool, String> {
+    item.as_bool().ok_or_else(|| "expected a boolean".to_string())
+}
+
+/// 文字列の配列を読み取る
+fn get_str_array(item: &toml_edit::Item) -> Result<Vec<String>, String> {
+    let array = item.as_array().ok_or_else(|| "expected an array of strings".to_string())?;
+    array
+        .iter()
+        .map(|value| value.as_str().map(str::to_string).ok_or_else(|| "expected an array of strings".to_string()))
+        .collect()
+}
+
+/// グロブパターンの配列を読み取る
+fn get_patterns(item: &toml_edit::Item) -> Result<Vec<glob::Pattern>, String> {
+    get_str_array(item)?
+        .iter()
+        .map(|pattern| glob::Pattern::new(pattern).map_err(|e| format!("invalid pattern `{}`: {}", pattern, e)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(toml: &str) -> Result<StubConfig, String> {
+        read_config(&toml.parse::<DocumentMut>().unwrap(), Path::new("/project")).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn test_read_config() {
+        let config = read(r##"
+        [tool.pystubgen]
+        output-dir = "stubs"
+        exclude = ["src/internal/**"]
+        exclude-items = ["_*"]
+        docstring-style = "docstring"
+        python-version = "3.9"
+        header = "# generated"
+        features = ["python"]
+        docs = "rst"
+
+        [tool.pystubgen.type-map]
+        "chrono::DateTime" = "datetime.datetime"
+        "##).unwrap();
+
+        assert_eq!(config.output_dir, Some(PathBuf::from("stubs")));
+        assert_eq!(config.docstring_style, DocstringStyle::Docstring);
+        assert_eq!(config.python_version, Some(PythonVersion { major: 3, minor: 9 }));
+        assert_eq!(config.type_map.get("chrono::DateTime").map(String::as_str), Some("datetime.datetime"));
+        assert_eq!(config.features, vec!["python".to_string()]);
+        assert_eq!(config.docs, Some(DocsFormat::Rst));
+        assert!(!config.includes_file(Path::new("/project"), Path::new("/project/src/internal/mod.rs")));
+        assert!(config.includes_file(Path::new("/project"), Path::new("/project/src/lib.rs")));
+        assert!(!config.includes_item("_private"));
+        assert!(config.includes_item("public"));
+        assert_eq!(read("").unwrap(), StubConfig::default());
+        let requires_python = read("[project]\nrequires-python = \">=3.8\"").unwrap();
+        assert_eq!(requires_python.python_version, Some(PythonVersion { major: 3, minor: 8 }));
+    }
+
+    #[test]
+    fn test_read_config_errors() {
+        let cases = [
+            ("[tool.pystubgen]\noutput = \"stubs\"", "invalid [tool.pystubgen] output: unknown key"),
+            ("[tool.pystubgen]\ndocstring-style = \"google\"", "invalid [tool.pystubgen] docstring-style: expected one of"),
+            ("[tool.pystubgen]\npython-version = 3.9", "invalid [tool.pystubgen] python-version: expected a string"),
+            ("[tool.pystubgen]\nexclude = \"src\"", "invalid [tool.pystubgen] exclude: expected an array of strings"),
+            ("[tool.pystubgen]\ninclude = [\"src/[\"]", "invalid [tool.pystubgen] include: invalid pattern `src/[`"),
+            ("[tool.pystubgen]\ndocs = \"html\"", "invalid [tool.pystubgen] docs: expected one of `markdown`, `rst`"),
+            ("[tool.pystubgen.type-map]\nUuid = 1", "invalid [tool.pystubgen] type-map.\"Uuid\": expected a string"),
+        ];
+        for (toml, expected) in cases {
+            let error = read(toml).unwrap_err();
+            assert!(error.contains(expected), "{}: {}", toml, error);
+        }
+    }
+
+    #[test]
+    fn test_python_version_from_requires_python() {
+        let version = |spec: &str| PythonVersion::from_requires_python(spec).map(|version| version.to_string());
+        assert_eq!(version(">=3.12").as_deref(), Some("3.12"));
+        assert_eq!(version(">=3.8, <4").as_deref(), Some("3.8"));
+        assert_eq!(version("~=3.10").as_deref(), Some("3.10"));
+        assert_eq!(version("==3.11.*").as_deref(), Some("3.11"));
+        assert_eq!(version(">3.8").as_deref(), Some("3.8"));
+        assert_eq!(version(">=3.8.1").as_deref(), Some("3.8"));
+        assert_eq!(version("<3.13"), None);
+    }
+
+    #[test]
+    fn test_config_overrides() {
+        let project_info = ProjectInfo {
+            config: StubConfig { docstring_style: DocstringStyle::None, ..Default::default() },
+            ..Default::default()
+        };
+        let overrides = ConfigOverrides {
+            docstring_style: Some(DocstringStyle::Comment),
+            header: Some("# header".to_string()),
+            ..Default::default()
+        };
+        let applied = overrides.apply(&project_info);
+        assert_eq!(applied.config.docstring_style, DocstringStyle::Comment);
+        assert_eq!(applied.config.header.as_deref(), Some("# header"));
+        assert_eq!(applied.output_dir, project_info.output_dir);
+    }
+
+    #[test]
+    fn test_config_overrides_output_dir() {
+        // 拡張モジュールの名前が同じ（_core）2つのプロジェクト
+        let project = |name: &str| ProjectInfo {
+            project_name: name.to_string(),
+            module_name: "_core".to_string(),
+            python_module: format!("{}._core", name),
+            ..Default::default()
+        };
+        let projects = vec![project("lib_c"), project("single_project")];
+        let overrides = ConfigOverrides { output_dir: Some(PathBuf::from("stubs")), ..Default::default() };
+        let error = overrides.check_projects(&projects).unwrap_err().to_string();
+        assert!(error.contains("2 were found (lib_c, single_project)"), "{}", error);
+        assert!(overrides.check_projects(&projects[..1]).is_ok());
+        assert!(ConfigOverrides::default().check_projects(&projects).is_ok());
+    }
+}
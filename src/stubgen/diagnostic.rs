@@ -0,0 +1,90 @@
+//! 診断モジュール
+//! 
+//! 解析・生成中に見つかった問題（解決できなかった型、シグネチャの食い違いなど）を、
+//! 表示する文字列ではなく重大度・メッセージ・Rustのソースの場所を持つデータとして扱います。
+//! コマンドは`Display`で表示し、ライブラリの利用者は内容を調べて扱いを決められます。
+
+use std::fmt;
+use std::path::PathBuf;
+use serde::Serialize;
+
+/// 診断の重大度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// 警告（スタブは生成される）
+    Warning,
+    /// エラー
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// 診断の対象になったRustのソースの場所
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Location {
+    /// ファイルのパス
+    pub file: PathBuf,
+    /// 行番号
+    pub line: usize,
+    /// 関数・定数などの名前
+    pub item: String,
+}
+
+/// 診断
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    /// 重大度
+    pub severity: Severity,
+    /// メッセージ（`warning: `などの接頭辞は含まない）
+    pub message: String,
+    /// 関係するRustのソースの場所
+    pub locations: Vec<Location>,
+}
+
+impl Diagnostic {
+    /// 場所を持たない警告を作る
+    pub fn warning(message: impl Into<String>) -> Self {
+        Diagnostic { severity: Severity::Warning, message: message.into(), locations: vec![] }
+    }
+
+    /// 場所を追加する
+    pub fn with_location(mut self, file: impl Into<PathBuf>, line: usize, item: impl Into<String>) -> Self {
+        self.locations.push(Location { file: file.into(), line, item: item.into() });
+        self
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.severity, self.message)?;
+        for location in &self.locations {
+            write!(f, "\n  --> {}:{} ({})", location.file.display(), location.line, location.item)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let diagnostic = Diagnostic::warning("unresolved type `Foo` was replaced with `Any`")
+            .with_location("src/lib.rs", 3, "make_foo")
+            .with_location("src/lib.rs", 8, "take_foo");
+        assert_eq!(
+            diagnostic.to_string(),
+            "warning: unresolved type `Foo` was replaced with `Any`\n  --> src/lib.rs:3 (make_foo)\n  --> src/lib.rs:8 (take_foo)"
+        );
+        assert_eq!(Diagnostic::warning("x").to_string(), "warning: x");
+    }
+}
@@ -0,0 +1,217 @@
+//! APIリファレンス生成モジュール
+//! 
+//! 解析結果の中間表現から、Pythonから見たAPIリファレンス（MkDocs向けのMarkdown、
+//! Sphinxの`py`ドメインを使うreStructuredText）を生成します。
+//! スタブと同じ解析結果から作るため、手で書いたリファレンスのように実装とずれることがありません。
+
+use crate::stubgen::config::DocsFormat;
+use crate::stubgen::ir::{IrConstant, IrFunction, IrProject, ParamKind};
+
+/// APIリファレンスを組み立てる
+/// 
+/// モジュールごとに、クラス・定数・関数の順に並べる。
+/// 
+/// # Arguments
+/// 
+/// * `project` - プロジェクトの中間表現
+/// * `format` - 出力形式
+/// 
+/// # Returns
+/// 
+/// * `String` - APIリファレンスの内容
+pub fn render_docs(project: &IrProject, format: DocsFormat) -> String {
+    match format {
+        DocsFormat::Markdown => render_markdown(project),
+        DocsFormat::Rst => render_rst(project),
+    }
+}
+
+/// Markdownで組み立てる
+fn render_markdown(project: &IrProject) -> String {
+    let mut out = format!("# `{}`\n", project.python_module);
+    if !project.classes.is_empty() {
+        out.push_str("\n## Classes\n");
+        for class in &project.classes {
+            out.push_str(&format!("\n### `{}`\n", class.name));
+            push_markdown_doc(&mut out, &class.doc);
+        }
+    }
+    if !project.constants.is_empty() {
+        out.push_str("\n## Constants\n");
+        for constant in &project.constants {
+            out.push_str(&format!("\n### `{}`\n\n```python\n{}: {}\n```\n", constant.name, constant.name, constant_type(constant)));
+            push_markdown_doc(&mut out, &constant.doc);
+        }
+    }
+    if !project.functions.is_empty() {
+        out.push_str("\n## Functions\n");
+        for function in &project.functions {
+            out.push_str(&format!("\n### `{}`\n\n```python\ndef {}\n```\n", function.name, function_signature(function)));
+            push_markdown_doc(&mut out, &function.doc);
+        }
+    }
+    out
+}
+
+/// Markdownの見出しの後にドキュメントコメントを書く
+fn push_markdown_doc(out: &mut String, doc: &str) {
+    if !doc.trim().is_empty() {
+        out.push_str(&format!("\n{}\n", doc.trim()));
+    }
+}
+
+/// reStructuredTextで組み立てる
+fn render_rst(project: &IrProject) -> String {
+    let title = &project.python_module;
+    let mut out = format!("{}\n{}\n\n.. py:module:: {}\n", title, "=".repeat(title.chars().count()), title);
+    if !project.classes.is_empty() {
+        out.push_str("\nClasses\n-------\n");
+        for class in &project.classes {
+            out.push_str(&format!("\n.. py:class:: {}\n", class.name));
+            push_rst_doc(&mut out, &class.doc);
+        }
+    }
+    if !project.constants.is_empty() {
+        out.push_str("\nConstants\n---------\n");
+        for constant in &project.constants {
+            out.push_str(&format!("\n.. py:data:: {}\n   :type: {}\n", constant.name, constant_type(constant)));
+            push_rst_doc(&mut out, &constant.doc);
+        }
+    }
+    if !project.functions.is_empty() {
+        out.push_str("\nFunctions\n---------\n");
+        for function in &project.functions {
+            out.push_str(&format!("\n.. py:function:: {}\n", function_signature(function)));
+            push_rst_doc(&mut out, &function.doc);
+        }
+    }
+    out
+}
+
+/// ディレクティブの本文としてドキュメントコメントを書く
+fn push_rst_doc(out: &mut String, doc: &str) {
+    if !doc.trim().is_empty() {
+        out.push_str(&format!("\n   {}\n", doc.trim()));
+    }
+}
+
+/// 定数の型（`Final`を含む）
+fn constant_type(constant: &IrConstant) -> String {
+    if constant.is_final {
+        format!("Final[{}]", constant.ty)
+    } else {
+        constant.ty.clone()
+    }
+}
+
+/// 関数のシグネチャ（`add(a: int, *, b: int = 1) -> int`）
+/// 
+/// 引数の種類から区切りの`/`・`*`を補う。
+fn function_signature(function: &IrFunction) -> String {
+    let mut parts = vec![];
+    let mut previous = None;
+    for param in &function.params {
+        if previous == Some(ParamKind::PositionalOnly) && param.kind != ParamKind::PositionalOnly {
+            parts.push("/".to_string());
+        }
+        if param.kind == ParamKind::KeywordOnly
+            && !matches!(previous, Some(ParamKind::KeywordOnly | ParamKind::VarPositional))
+        {
+            parts.push("*".to_string());
+        }
+        let prefix = match param.kind {
+            ParamKind::VarPositional => "*",
+            ParamKind::VarKeyword => "**",
+            _ => "",
+        };
+        let mut part = format!("{}{}: {}", prefix, param.name, param.ty);
+        if let Some(default) = &param.default {
+            part.push_str(&format!(" = {}", default));
+        }
+        parts.push(part);
+        previous = Some(param.kind);
+    }
+    if previous == Some(ParamKind::PositionalOnly) {
+        parts.push("/".to_string());
+    }
+    format!("{}({}) -> {}", function.name, parts.join(", "), function.return_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use crate::stubgen::ir::{IrClass, IrParam, IrSpan};
+
+    fn param(name: &str, kind: ParamKind, ty: &str, default: Option<&str>) -> IrParam {
+        IrParam { name: name.to_string(), kind, ty: ty.to_string(), default: default.map(str::to_string) }
+    }
+
+    fn project() -> IrProject {
+        IrProject {
+            name: "lib_a".to_string(),
+            python_module: "lib_a._core".to_string(),
+            stub_path: PathBuf::from("python/lib_a/_core.pyi"),
+            python_version: None,
+            modules: vec![],
+            classes: vec![IrClass {
+                name: "Point".to_string(),
+                rust_path: "crate::RustPoint".to_string(),
+                doc: " 2次元の点".to_string(),
+                source: IrSpan { file: PathBuf::from("src/lib.rs"), line: 4 },
+            }],
+            functions: vec![IrFunction {
+                name: "add".to_string(),
+                params: vec![
+                    param("a", ParamKind::PositionalOrKeyword, "int", None),
+                    param("b", ParamKind::KeywordOnly, "int", Some("1")),
+                ],
+                return_type: "int".to_string(),
+                doc: " 足し算".to_string(),
+                source: None,
+            }],
+            constants: vec![IrConstant {
+                name: "VERSION".to_string(),
+                ty: "str".to_string(),
+                is_final: true,
+                doc: String::new(),
+                source: None,
+            }],
+            type_aliases: vec![],
+            unresolved_types: vec![],
+        }
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        assert_eq!(
+            render_docs(&project(), DocsFormat::Markdown),
+            "# `lib_a._core`\n\n## Classes\n\n### `Point`\n\n2次元の点\n\n## Constants\n\n### `VERSION`\n\n```python\nVERSION: Final[str]\n```\n\n## Functions\n\n### `add`\n\n```python\ndef add(a: int, *, b: int = 1) -> int\n```\n\n足し算\n"
+        );
+    }
+
+    #[test]
+    fn test_render_rst() {
+        assert_eq!(
+            render_docs(&project(), DocsFormat::Rst),
+            "lib_a._core\n===========\n\n.. py:module:: lib_a._core\n\nClasses\n-------\n\n.. py:class:: Point\n\n   2次元の点\n\nConstants\n---------\n\n.. py:data:: VERSION\n   :type: Final[str]\n\nFunctions\n---------\n\n.. py:function:: add(a: int, *, b: int = 1) -> int\n\n   足し算\n"
+        );
+    }
+
+    #[test]
+    fn test_function_signature() {
+        let function = IrFunction {
+            name: "join".to_string(),
+            params: vec![
+                param("sep", ParamKind::PositionalOnly, "str", None),
+                param("parts", ParamKind::VarPositional, "Any", None),
+                param("limit", ParamKind::KeywordOnly, "int", Some("None")),
+                param("options", ParamKind::VarKeyword, "Any", None),
+            ],
+            return_type: "str".to_string(),
+            doc: String::new(),
+            source: None,
+        };
+        assert_eq!(function_signature(&function), "join(sep: str, /, *parts: Any, limit: int = None, **options: Any) -> str");
+    }
+}
@@ -0,0 +1,351 @@
+//! マクロ展開モジュール
+//! 
+//! `cargo expand`またはnightlyの`rustc -Zunpretty=expanded`でマクロ展開後のクレートを取得し、
+//! 通常のパーサー・アナライザーで解析できる形にします。
+//! 展開後は`#[pyfunction]`などのアトリビュートが消えるため、PyO3が生成したコードから復元します。
+
+use std::error::Error;
+use std::path::Path;
+use std::process::Command;
+use proc_macro2::{TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::parse::Parser;
+use syn::Item;
+use crate::stubgen::cfg::CfgOptions;
+use crate::stubgen::signature::parse_text_signature;
+
+/// マクロ展開後のクレート
+#[derive(Debug)]
+pub struct ExpandedCrate {
+    /// PyO3のアトリビュートを復元したアイテム
+    pub items: Vec<Item>,
+    /// 展開後のコードからシグネチャを復元できなかった`#[pyfunction]`の名前
+    pub missing_signatures: Vec<String>,
+}
+
+/// マクロ展開後のクレートを取得し、アイテムのリストにする
+/// 
+/// # Arguments
+/// 
+/// * `manifest_path` - Cargo.tomlのパス
+/// * `cfg_options` - フィーチャーとターゲットの指定
+/// 
+/// # Returns
+/// 
+/// * `Result<ExpandedCrate, Box<dyn Error>>` - PyO3のアトリビュートを復元したアイテム
+pub fn expand_crate_items(manifest_path: &Path, cfg_options: &CfgOptions) -> Result<ExpandedCrate, Box<dyn Error>> {
+    let source = expand_crate(manifest_path, cfg_options)?;
+    let mut items = syn::parse_file(&source)
+        .map_err(|e| format!("failed to parse expanded source: {}", e))?
+        .items;
+    let missing_signatures = restore_pyo3_attributes(&mut items);
+    Ok(ExpandedCrate { items, missing_signatures })
+}
+
+/// マクロ展開後のクレートのソースコードを取得する
+/// 
+/// `cargo expand`がインストールされていればそれを使い、なければnightlyの`rustc`を使う。
+/// 
+/// # Arguments
+/// 
+/// * `manifest_path` - Cargo.tomlのパス
+/// * `cfg_options` - フィーチャーとターゲットの指定
+/// 
+/// # Returns
+/// 
+/// * `Result<String, Box<dyn Error>>` - 展開後のソースコード
+pub fn expand_crate(manifest_path: &Path, cfg_options: &CfgOptions) -> Result<String, Box<dyn Error>> {
+    let mut errors = vec![];
+
+    if is_cargo_expand_installed() {
+        let mut command = Command::new("cargo");
+        command.arg("expand").arg("--lib").arg("--manifest-path").arg(manifest_path);
+        command.args(cargo_feature_args(cfg_options));
+        match run_expand(command) {
+            Ok(source) => return Ok(source),
+            Err(e) => errors.push(format!("cargo expand: {}", e)),
+        }
+    }
+
+    let mut command = Command::new("cargo");
+    command
+        .arg("+nightly")
+        .arg("rustc")
+        .arg("--lib")
+        .arg("--profile=check")
+        .arg("--manifest-path")
+        .arg(manifest_path);
+    command.args(cargo_feature_args(cfg_options));
+    command.args(["--", "-Zunpretty=expanded"]);
+    match run_expand(command) {
+        Ok(source) => return Ok(source),
+        Err(e) => errors.push(format!("rustc -Zunpretty=expanded: {}", e)),
+    }
+
+    Err(errors.join("; ").into())
+}
+
+/// `cargo expand`が使えるかどうかを確認する
+fn is_cargo_expand_installed() -> bool {
+    Command::new("cargo")
+        .args(["expand", "--version"])
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+/// フィーチャーとターゲットの指定をcargoの引数にする
+fn cargo_feature_args(cfg_options: &CfgOptions) -> Vec<String> {
+    let mut args = vec![];
+    if !cfg_options.features.is_empty() {
+        args.push("--features".to_string());
+        args.push(cfg_options.features.join(","));
+    }
+    if cfg_options.all_features {
+        args.push("--all-features".to_string());
+    }
+    if cfg_options.no_default_features {
+        args.push("--no-default-features".to_string());
+    }
+    if let Some(target) = &cfg_options.target {
+        args.push("--target".to_string());
+        args.push(target.clone());
+    }
+    args
+}
+
+/// 展開コマンドを実行し、標準出力を返す
+fn run_expand(mut command: Command) -> Result<String, Box<dyn Error>> {
+    let output = command.output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let message = stderr.lines().rev().find(|line| !line.trim().is_empty()).unwrap_or("failed");
+        return Err(message.trim().to_string().into());
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// PyO3のマクロが生成したコードから、元のアトリビュートを復元する
+/// 
+/// * `#[pyfunction] fn foo` は同名の`mod foo { struct MakeDef; ... }`を生成する
+///   （引数に`PyModule`を取るものは`#[pymodule]`とみなす）
+/// * `#[pyclass]` は`impl PyTypeInfo for Foo { const NAME: &str = "..."; }`を生成する
+/// * `#[pyo3(signature)]`・`text_signature`は、`MakeDef`の`PyMethodDef`に渡す`"foo(a, b=1)\n--\n\n..."`の
+///   文字列から`#[pyo3(signature = (...))]`として復元する
+/// 
+/// # Arguments
+/// 
+/// * `items` - 展開後のアイテムのリスト（インラインモジュールの中も再帰的に処理する）
+/// 
+/// # Returns
+/// 
+/// * `Vec<String>` - シグネチャを復元できなかった`#[pyfunction]`の名前
+pub fn restore_pyo3_attributes(items: &mut [Item]) -> Vec<String> {
+    let generated_defs: Vec<String> = items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Mod(item_mod) if contains_make_def(item_mod) => Some(item_mod.ident.to_string()),
+            _ => None,
+        })
+        .collect();
+    let pyclass_names: Vec<(String, String)> = items.iter().filter_map(get_pytypeinfo_name).collect();
+    let signatures: Vec<(String, String)> = generated_defs
+        .iter()
+        .filter_map(|name| Some((name.clone(), find_generated_signature(items, name)?)))
+        .collect();
+
+    let mut missing_signatures = vec![];
+    for item in items.iter_mut() {
+        match item {
+            Item::Fn(item_fn) if generated_defs.contains(&item_fn.sig.ident.to_string()) => {
+                if takes_module(&item_fn.sig) {
+                    item_fn.attrs.push(syn::parse_quote!(#[pymodule]));
+                    continue;
+                }
+                item_fn.attrs.push(syn::parse_quote!(#[pyfunction]));
+                let name = item_fn.sig.ident.to_string();
+                match signatures.iter().find(|(def, _)| *def == name).and_then(|(_, text)| signature_attribute(text)) {
+                    Some(attr) => item_fn.attrs.push(attr),
+                    None => missing_signatures.push(name),
+                }
+            }
+            Item::Struct(syn::ItemStruct { ident, attrs, .. }) | Item::Enum(syn::ItemEnum { ident, attrs, .. }) => {
+                if let Some((_, python_name)) = pyclass_names.iter().find(|(name, _)| ident == name) {
+                    attrs.push(syn::parse_quote!(#[pyclass(name = #python_name)]));
+                }
+            }
+            Item::Mod(item_mod) => {
+                if let Some((_, items)) = &mut item_mod.content {
+                    missing_signatures.extend(restore_pyo3_attributes(items));
+                }
+            }
+            _ => {}
+        }
+    }
+    missing_signatures
+}
+
+/// `#[pyfunction]`のために生成された`mod foo`・`impl foo::MakeDef`から、`__text_signature__`の元になる`(a, b=1)`を探す
+fn find_generated_signature(items: &[Item], name: &str) -> Option<String> {
+    items.iter()
+        .filter(|item| match item {
+            Item::Mod(item_mod) => item_mod.ident == name,
+            Item::Impl(item_impl) => match &*item_impl.self_ty {
+                syn::Type::Path(self_ty) => {
+                    let segments: Vec<String> = self_ty.path.segments.iter().map(|seg| seg.ident.to_string()).collect();
+                    segments.ends_with(&[name.to_string(), "MakeDef".to_string()])
+                }
+                _ => false,
+            },
+            _ => false,
+        })
+        .find_map(|item| find_signature_literal(item.to_token_stream()))
+}
+
+/// トークン列の文字列リテラルから、`foo(a, b=1)\n--\n\n`の形のシグネチャを探す
+fn find_signature_literal(tokens: TokenStream) -> Option<String> {
+    tokens.into_iter().find_map(|token| match token {
+        TokenTree::Group(group) => find_signature_literal(group.stream()),
+        TokenTree::Literal(literal) => {
+            let value = match syn::parse_str::<syn::Lit>(&literal.to_string()).ok()? {
+                syn::Lit::Str(lit) => lit.value(),
+                syn::Lit::CStr(lit) => lit.value().to_string_lossy().into_owned(),
+                _ => return None,
+            };
+            let (head, _) = value.split_once("\n--\n")?;
+            let start = head.find('(')?;
+            head.ends_with(')').then(|| head[start..].to_string())
+        }
+        _ => None,
+    })
+}
+
+/// `(a, b=1, *, c=None)`から`#[pyo3(signature = (a, b = 1, *, c = None))]`を作る
+fn signature_attribute(text_signature: &str) -> Option<syn::Attribute> {
+    let params: Vec<String> = parse_text_signature(text_signature)?
+        .into_iter()
+        .map(|param| match param.default {
+            // Pythonの文字列はRustの文字列リテラルにする
+            Some(default) if default.starts_with('\'') => {
+                format!("{} = {:?}", param.name, default.trim_matches('\''))
+            }
+            Some(default) => format!("{} = {}", param.name, default),
+            None => param.name,
+        })
+        .collect();
+    let attr = format!("#[pyo3(signature = ({}))]", params.join(", "));
+    syn::Attribute::parse_outer.parse_str(&attr).ok()?.pop()
+}
+
+/// PyO3が`#[pyfunction]`/`#[pymodule]`のために生成するモジュールかどうか
+fn contains_make_def(item_mod: &syn::ItemMod) -> bool {
+    item_mod.content.as_ref().is_some_and(|(_, items)| {
+        items.iter().any(|item| matches!(item, Item::Struct(item_struct) if item_struct.ident == "MakeDef"))
+    })
+}
+
+/// 関数が`PyModule`を引数に取るかどうか
+fn takes_module(sig: &syn::Signature) -> bool {
+    sig.inputs.iter().any(|arg| {
+        let syn::FnArg::Typed(pat_type) = arg else { return false };
+        pat_type.ty.to_token_stream().to_string().contains("PyModule")
+    })
+}
+
+/// `impl PyTypeInfo for Foo { const NAME: &str = "..."; }`から型名とPython側の名前を取得する
+fn get_pytypeinfo_name(item: &Item) -> Option<(String, String)> {
+    let Item::Impl(item_impl) = item else { return None };
+    let (_, trait_path, _) = item_impl.trait_.as_ref()?;
+    if trait_path.segments.last()?.ident != "PyTypeInfo" {
+        return None;
+    }
+    let syn::Type::Path(self_ty) = &*item_impl.self_ty else { return None };
+    let name = self_ty.path.segments.last()?.ident.to_string();
+    let python_name = item_impl.items.iter().find_map(|impl_item| match impl_item {
+        syn::ImplItem::Const(constant) if constant.ident == "NAME" => match &constant.expr {
+            syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(value), .. }) => Some(value.value()),
+            _ => None,
+        },
+        _ => None,
+    })?;
+    Some((name, python_name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stubgen::parser::*;
+
+    /// PyO3 0.22の`cargo expand`の出力を簡略化したもの
+    const EXPANDED: &str = r#"
+        use pyo3::prelude::*;
+        #[doc = " 足し算"]
+        fn add(a: i32, b: i32, scale: f64, label: &str) -> i32 { a + b }
+        #[doc(hidden)]
+        mod add {
+            pub(crate) struct MakeDef;
+            pub const _PYO3_DEF: ::pyo3::impl_::pymethods::PyMethodDef = MakeDef::_PYO3_DEF;
+        }
+        impl add::MakeDef {
+            const _PYO3_DEF: ::pyo3::impl_::pymethods::PyMethodDef =
+                ::pyo3::impl_::pymethods::PyMethodDef::fastcall_cfunction_with_keywords(
+                    "add\0",
+                    ::pyo3::impl_::pymethods::PyCFunctionFastWithKeywords({ __pyfunction_add }),
+                    "add(a, b=1, *, scale=1.5, label='x')\n--\n\n\u{8db3}\u{3057}\u{7b97}\0",
+                );
+        }
+        fn legacy(a: i32) -> i32 { a }
+        #[doc(hidden)]
+        mod legacy {
+            pub(crate) struct MakeDef;
+        }
+        fn helper() {}
+        struct RustPoint { x: f64 }
+        unsafe impl ::pyo3::type_object::PyTypeInfo for RustPoint {
+            const NAME: &'static str = "Point";
+            const MODULE: ::std::option::Option<&'static str> = ::core::option::Option::None;
+        }
+        fn _core(m: &Bound<'_, PyModule>) -> PyResult<()> { Ok(()) }
+        #[doc(hidden)]
+        mod _core {
+            pub(crate) struct MakeDef;
+        }
+    "#;
+
+    #[test]
+    fn test_restore_pyo3_attributes() {
+        let mut file: syn::File = syn::parse_str(EXPANDED).unwrap();
+        assert_eq!(restore_pyo3_attributes(&mut file.items), vec!["legacy".to_string()]);
+
+        let functions: Vec<RustFunctionData> = file.items.iter().filter_map(|item| match item {
+            Item::Fn(item_fn) => Some(parse_function_data(item_fn)),
+            _ => None,
+        }).collect();
+        assert_eq!(functions[0].attributes, vec!["pyfunction".to_string()]);
+        assert_eq!(functions[0].doc, " 足し算");
+        assert_eq!(functions[0].signature.as_deref(), Some(r#"(a , b = 1 , *, scale = 1.5 , label = "x")"#));
+        assert_eq!(functions[1].attributes, vec!["pyfunction".to_string()]);
+        assert_eq!(functions[1].signature, None);
+        assert!(functions[2].attributes.is_empty());
+        assert_eq!(functions[3].attributes, vec!["pymodule".to_string()]);
+
+        let types: Vec<RustTypeData> = file.items.iter().filter_map(parse_type_data).collect();
+        assert_eq!(types[0], RustTypeData {
+            name: "RustPoint".to_string(),
+            kind: RustTypeKind::PyClass { python_name: "Point".to_string(), doc: String::new() },
+            line: 24,
+        });
+    }
+
+    #[test]
+    fn test_cargo_feature_args() {
+        let args = cargo_feature_args(&CfgOptions {
+            features: vec!["gpu".to_string(), "python".to_string()],
+            no_default_features: true,
+            target: Some("x86_64-unknown-linux-gnu".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(args, vec![
+            "--features", "gpu,python", "--no-default-features", "--target", "x86_64-unknown-linux-gnu",
+        ]);
+    }
+}
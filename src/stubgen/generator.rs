@@ -2,48 +2,219 @@
 //! 
 //! このモジュールは、解析されたRustコードの情報からPythonの型ヒント付きスタブファイルを生成します。
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::error::Error;
 use crate::stubgen::analyzer::*;
-use std::fs::File;
+use crate::stubgen::config::{DocstringStyle, StubConfig};
 use std::io::Write;
-use std::fs::OpenOptions;
+use serde::Serialize;
 
-/// Pythonスタブファイルを生成する
+/// `typing`モジュールからインポートする可能性のある名前
+const TYPING_NAMES: &[&str] = &["Any", "Dict", "Final", "List", "Optional", "Set", "Tuple", "Union"];
+
+/// 生成したファイルの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FileKind {
+    /// 拡張モジュールのスタブ（`_core.pyi`）
+    Stub,
+    /// `py.typed`マーカー
+    PyTyped,
+    /// パッケージの`__init__.pyi`
+    InitStub,
+    /// APIリファレンス
+    Docs,
+}
+
+/// 生成したファイル（まだ書き込んでいないもの）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StubFile {
+    /// 書き込み先のパス
+    pub path: PathBuf,
+    /// ファイルの種類
+    pub kind: FileKind,
+    /// ファイルの内容
+    pub content: String,
+}
+
+impl StubFile {
+    /// ファイルを書き込む
+    /// 
+    /// # Returns
+    /// 
+    /// * `Result<bool, Box<dyn Error>>` - ファイルを書き込んだかどうか（内容が同じなら書き込まない）
+    pub fn write(&self) -> Result<bool, Box<dyn Error>> {
+        if std::fs::read_to_string(&self.path).is_ok_and(|existing| existing == self.content) {
+            return Ok(false);
+        }
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("failed to create {}: {}", dir.display(), e))?;
+        }
+        std::fs::write(&self.path, &self.content)
+            .map_err(|e| format!("failed to write {}: {}", self.path.display(), e))?;
+        Ok(true)
+    }
+}
+
+/// Pythonスタブファイルの内容を組み立てる（書き込みはしない）
 /// 
 /// # Arguments
 /// 
 /// * `python_src_data` - 解析されたPython関数データ
 /// * `output_dir` - 出力ディレクトリ
 /// * `module_name` - モジュール名
+/// * `config` - [tool.pystubgen]の設定
 /// 
 /// # Returns
 /// 
-/// * `Result<(), Box<dyn Error>>` - 処理結果
-pub fn generate_stub(
+/// * `Result<StubFile, Box<dyn Error>>` - `<output_dir>/<module_name>.pyi`に書くスタブ
+pub fn render_stub(
     python_src_data: &PythonSrcData,
     output_dir: &Path,
     module_name: &str,
+    config: &StubConfig,
+) -> Result<StubFile, Box<dyn Error>> {
+    let mut stub = vec![];
+    write_stub(&mut stub, python_src_data, module_name, config)?;
+    Ok(StubFile {
+        path: output_dir.join(format!("{}.pyi", module_name)),
+        kind: FileKind::Stub,
+        content: String::from_utf8(stub)?,
+    })
+}
+
+/// モジュール全体のスタブを書き出す
+/// 
+/// # Arguments
+/// 
+/// * `out` - 出力先
+/// * `python_src_data` - 解析されたPython関数データ
+/// * `module_name` - モジュール名
+/// * `config` - [tool.pystubgen]の設定（ヘッダーとドキュメントコメントの形式）
+/// 
+/// # Returns
+/// 
+/// * `Result<(), Box<dyn Error>>` - 処理結果
+pub fn write_stub(
+    out: &mut impl Write,
+    python_src_data: &PythonSrcData,
+    module_name: &str,
+    config: &StubConfig,
 ) -> Result<(), Box<dyn Error>> {
-    let output_path = output_dir.join(format!("{}.pyi", module_name));
-    let mut file = OpenOptions::new()
-        .write(true)
-        .append(true)
-        .create(true)
-        .open(&output_path)
-        .map_err(|e| format!("File create failed: {}", e))?;
+    if let Some(header) = &config.header {
+        writeln!(out, "{}", header.trim_end())?;
+        writeln!(out)?;
+    }
+
+    let typing_imports = collect_typing_imports(python_src_data);
+    if !typing_imports.is_empty() {
+        writeln!(out, "from typing import {}", typing_imports.join(", "))?;
+        writeln!(out)?;
+    }
+
+    // Python 3.12以降の型エイリアス（PEP 695）
+    for alias in &python_src_data.type_aliases {
+        if alias.params.is_empty() {
+            writeln!(out, "type {} = {}", alias.name, alias.target)?;
+        } else {
+            writeln!(out, "type {}[{}] = {}", alias.name, alias.params.join(", "), alias.target)?;
+        }
+    }
+    if !python_src_data.type_aliases.is_empty() {
+        writeln!(out)?;
+    }
 
-    println!("Generating stub file: {}", output_path.display());
+    for constant_data in &python_src_data.constants {
+        generate_constant_stub(out, constant_data, config.docstring_style)?;
+    }
+    if !python_src_data.constants.is_empty() {
+        writeln!(out)?;
+    }
+
+    // メソッド・属性は出力せず、シグネチャから参照できるようにクラスだけを宣言する
+    for class in &python_src_data.classes {
+        writeln!(out, "class {}:", class)?;
+        writeln!(out, "    ...")?;
+        writeln!(out)?;
+    }
 
     for function_data in &python_src_data.functions {
         if function_data.name == module_name {
             continue;
         }
-        generate_function_stub(&mut file, function_data)?;
+        generate_function_stub(out, function_data, config.docstring_style)?;
+    }
+
+    Ok(())
+}
+
+/// スタブ内で使われている`typing`の名前を集める
+/// 
+/// # Arguments
+/// 
+/// * `python_src_data` - 解析されたPython関数データ
+/// 
+/// # Returns
+/// 
+/// * `Vec<&'static str>` - インポートが必要な名前（`TYPING_NAMES`の順）
+fn collect_typing_imports(python_src_data: &PythonSrcData) -> Vec<&'static str> {
+    let mut used = vec![];
+    for function_data in &python_src_data.functions {
+        used.extend(function_data.args.iter().map(|(_, ty)| ty.as_str()));
+        used.push(function_data.return_type.as_str());
     }
+    let constant_types = python_src_data
+        .constants
+        .iter()
+        .map(constant_annotation)
+        .collect::<Vec<String>>();
+    used.extend(constant_types.iter().map(String::as_str));
+    used.extend(python_src_data.type_aliases.iter().map(|alias| alias.target.as_str()));
+
+    TYPING_NAMES
+        .iter()
+        .copied()
+        .filter(|name| {
+            used.iter().any(|ty| {
+                ty.split(|c: char| !c.is_alphanumeric() && c != '_')
+                    .any(|ident| ident == *name)
+            })
+        })
+        .collect()
+}
 
-    file.flush()?;
+/// 定数の型注釈を組み立てる（`Final[int]`など）
+fn constant_annotation(constant_data: &PythonConstantData) -> String {
+    if constant_data.is_final {
+        format!("Final[{}]", constant_data.ty)
+    } else {
+        constant_data.ty.clone()
+    }
+}
 
+/// 個々の定数のスタブを生成する
+/// 
+/// # Arguments
+/// 
+/// * `out` - 出力先
+/// * `constant_data` - 定数データ
+/// * `docstring_style` - ドキュメントコメントの出力形式
+/// 
+/// # Returns
+/// 
+/// * `Result<(), Box<dyn Error>>` - 処理結果
+fn generate_constant_stub(
+    out: &mut impl Write,
+    constant_data: &PythonConstantData,
+    docstring_style: DocstringStyle,
+) -> Result<(), Box<dyn Error>> {
+    if !constant_data.doc.is_empty() && docstring_style == DocstringStyle::Comment {
+        writeln!(out, "# {}", constant_data.doc)?;
+    }
+    writeln!(out, "{}: {}", constant_data.name, constant_annotation(constant_data))?;
+    if !constant_data.doc.is_empty() && docstring_style == DocstringStyle::Docstring {
+        writeln!(out, "{}", docstring(&constant_data.doc))?;
+    }
     Ok(())
 }
 
@@ -51,19 +222,21 @@ pub fn generate_stub(
 /// 
 /// # Arguments
 /// 
-/// * `file` - 出力ファイル
+/// * `out` - 出力先
 /// * `function_data` - 関数データ
+/// * `docstring_style` - ドキュメントコメントの出力形式
 /// 
 /// # Returns
 /// 
 /// * `Result<(), Box<dyn Error>>` - 処理結果
 fn generate_function_stub(
-    file: &mut File,
+    out: &mut impl Write,
     function_data: &PythonFunctionData,
+    docstring_style: DocstringStyle,
 ) -> Result<(), Box<dyn Error>> {
 
-    if !function_data.doc.is_empty() {
-        writeln!(file, "# {}", function_data.doc)?;
+    if !function_data.doc.is_empty() && docstring_style == DocstringStyle::Comment {
+        writeln!(out, "# {}", function_data.doc)?;
     }
 
     let args_str = if function_data.args.is_empty() {
@@ -72,20 +245,174 @@ fn generate_function_stub(
         function_data
             .args
             .iter()
-            .map(|(name, ty)| format!("{}: {}", name, ty))
+            .map(|(name, ty)| {
+                // 区切りの`*`・`/`は型を持たない
+                let arg = if ty.is_empty() { name.clone() } else { format!("{}: {}", name, ty) };
+                match function_data.defaults.iter().find(|(arg_name, _)| arg_name == name) {
+                    Some((_, default)) => format!("{} = {}", arg, default),
+                    None => arg,
+                }
+            })
             .collect::<Vec<String>>()
             .join(", ")
     };
 
-    
+
     writeln!(
-        file,
+        out,
         "def {}({}) -> {}:",
         function_data.name, args_str, function_data.return_type
     )?;
-    writeln!(file, "    ...")?;
-    writeln!(file)?;
+    if !function_data.doc.is_empty() && docstring_style == DocstringStyle::Docstring {
+        writeln!(out, "    {}", docstring(&function_data.doc))?;
+    }
+    writeln!(out, "    ...")?;
+    writeln!(out)?;
 
-    file.flush()?;
     Ok(())
 }
+
+/// ドキュメントコメントをPythonのdocstringにする
+fn docstring(doc: &str) -> String {
+    let mut doc = doc.trim().replace('\\', "\\\\").replace("\"\"\"", "\\\"\"\"");
+    // 末尾の`"`は閉じ引用符とつながってしまう
+    if doc.ends_with('"') {
+        doc.insert(doc.len() - 1, '\\');
+    }
+    format!("\"\"\"{}\"\"\"", doc)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_stub_with_constants() {
+        let python_src_data = PythonSrcData {
+            functions: vec![PythonFunctionData {
+                name: "get".to_string(),
+                args: vec![("key".to_string(), "str".to_string())],
+                defaults: vec![],
+                return_type: "Any".to_string(),
+                doc: String::new(),
+            }],
+            constants: vec![
+                PythonConstantData {
+                    name: "__version__".to_string(),
+                    ty: "str".to_string(),
+                    is_final: false,
+                    doc: String::new(),
+                },
+                PythonConstantData {
+                    name: "FACTOR".to_string(),
+                    ty: "int".to_string(),
+                    is_final: true,
+                    doc: String::new(),
+                },
+            ],
+            ..Default::default()
+        };
+
+        let mut out = vec![];
+        write_stub(&mut out, &python_src_data, "_core", &StubConfig::default()).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "from typing import Any, Final\n\
+             \n\
+             __version__: str\n\
+             FACTOR: Final[int]\n\
+             \n\
+             def get(key: str) -> Any:\n    ...\n\n"
+        );
+    }
+
+    #[test]
+    fn test_write_stub_with_config() {
+        let python_src_data = PythonSrcData {
+            functions: vec![PythonFunctionData {
+                name: "add".to_string(),
+                args: vec![("a".to_string(), "int".to_string())],
+                defaults: vec![],
+                return_type: "int".to_string(),
+                doc: " 足し算".to_string(),
+            }],
+            constants: vec![PythonConstantData {
+                name: "FACTOR".to_string(),
+                ty: "int".to_string(),
+                is_final: false,
+                doc: " 係数".to_string(),
+            }],
+            ..Default::default()
+        };
+        let render = |config: &StubConfig| {
+            let mut out = vec![];
+            write_stub(&mut out, &python_src_data, "_core", config).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+
+        let docstring = StubConfig {
+            docstring_style: DocstringStyle::Docstring,
+            header: Some("# Do not edit.\n".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            render(&docstring),
+            "# Do not edit.\n\nFACTOR: int\n\"\"\"係数\"\"\"\n\ndef add(a: int) -> int:\n    \"\"\"足し算\"\"\"\n    ...\n\n"
+        );
+        let none = StubConfig { docstring_style: DocstringStyle::None, ..Default::default() };
+        assert_eq!(render(&none), "FACTOR: int\n\ndef add(a: int) -> int:\n    ...\n\n");
+    }
+
+    #[test]
+    fn test_write_stub_with_type_aliases() {
+        let python_src_data = PythonSrcData {
+            functions: vec![PythonFunctionData {
+                name: "solve".to_string(),
+                args: vec![("m".to_string(), "Matrix".to_string())],
+                defaults: vec![],
+                return_type: "Pair[Any]".to_string(),
+                doc: String::new(),
+            }],
+            type_aliases: vec![
+                PythonTypeAlias { name: "Matrix".to_string(), params: vec![], target: "list[list[float]]".to_string() },
+                PythonTypeAlias { name: "Pair".to_string(), params: vec!["T".to_string()], target: "tuple[T, T]".to_string() },
+            ],
+            ..Default::default()
+        };
+        let mut out = vec![];
+        write_stub(&mut out, &python_src_data, "_core", &StubConfig::default()).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "from typing import Any\n\
+             \n\
+             type Matrix = list[list[float]]\n\
+             type Pair[T] = tuple[T, T]\n\
+             \n\
+             def solve(m: Matrix) -> Pair[Any]:\n    ...\n\n"
+        );
+    }
+
+    #[test]
+    fn test_write_stub_with_signature() {
+        let python_src_data = PythonSrcData {
+            functions: vec![PythonFunctionData {
+                name: "join".to_string(),
+                args: vec![
+                    ("sep".to_string(), "str".to_string()),
+                    ("*parts".to_string(), "Any".to_string()),
+                    ("limit".to_string(), "int | None".to_string()),
+                ],
+                defaults: vec![("limit".to_string(), "None".to_string())],
+                return_type: "str".to_string(),
+                doc: String::new(),
+            }],
+            ..Default::default()
+        };
+        let mut out = vec![];
+        write_stub(&mut out, &python_src_data, "_core", &StubConfig::default()).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "from typing import Any\n\ndef join(sep: str, *parts: Any, limit: int | None = None) -> str:\n    ...\n\n"
+        );
+    }
+}
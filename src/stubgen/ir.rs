@@ -0,0 +1,427 @@
+//! 中間表現出力モジュール
+//! 
+//! 解析結果（関数・定数・クラス・型エイリアスと、それらが定義されているRustのソースの場所）を、
+//! バージョン付きのJSONとして出力します。ドキュメント生成などの外部ツールがRustを解析し直さずに
+//! バインディングのAPIを読めるようにするためのもので、内部の構造体とは切り離した形式にしています。
+
+use std::error::Error;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use serde::Serialize;
+use crate::stubgen::analyzer::{ItemSource, PythonFunctionData};
+use crate::stubgen::locator::ProjectInfo;
+use crate::stubgen::logging::{Logger, MessageFormat};
+use crate::stubgen::parser::RustTypeKind;
+use crate::stubgen::{analyze_project, locate_projects, GenerateOptions, ProjectAnalysis};
+
+/// 中間表現の形式のバージョン（互換性のない変更をしたら上げる）
+pub const IR_VERSION: u32 = 1;
+
+/// 中間表現のドキュメント
+#[derive(Debug, Serialize)]
+pub struct IrDocument {
+    /// 形式のバージョン
+    pub version: u32,
+    /// 出力したツールとそのバージョン
+    pub generator: String,
+    /// プロジェクトごとの解析結果
+    pub projects: Vec<IrProject>,
+}
+
+/// プロジェクトの解析結果
+#[derive(Debug, Serialize)]
+pub struct IrProject {
+    /// プロジェクト名
+    pub name: String,
+    /// 拡張モジュールのPythonでの名前（`pkg._core`）
+    pub python_module: String,
+    /// スタブファイルのパス（プロジェクトルートからの相対パス）
+    pub stub_path: PathBuf,
+    /// 対象とするPythonのバージョン
+    pub python_version: Option<String>,
+    /// 解析したRustのモジュール（宣言順）
+    pub modules: Vec<IrModule>,
+    /// `#[pyclass]`のクラス
+    pub classes: Vec<IrClass>,
+    /// 関数
+    pub functions: Vec<IrFunction>,
+    /// モジュール変数・定数
+    pub constants: Vec<IrConstant>,
+    /// 型エイリアス（Python 3.12以降の`type`文）
+    pub type_aliases: Vec<IrTypeAlias>,
+    /// 解決できずに`Any`とした型
+    pub unresolved_types: Vec<IrUnresolvedType>,
+}
+
+/// Rustのソースの場所
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct IrSpan {
+    /// ファイル（プロジェクトルートからの相対パス）
+    pub file: PathBuf,
+    /// 行（1始まり）
+    pub line: usize,
+}
+
+/// Rustのモジュール
+#[derive(Debug, Serialize)]
+pub struct IrModule {
+    /// モジュールパス（`crate::api`）
+    pub path: String,
+    /// ファイル
+    pub file: PathBuf,
+}
+
+/// `#[pyclass]`のクラス
+#[derive(Debug, Serialize)]
+pub struct IrClass {
+    /// Python側の名前
+    pub name: String,
+    /// Rustの型のパス（`crate::api::RustPoint`）
+    pub rust_path: String,
+    /// ドキュメントコメント
+    pub doc: String,
+    /// 定義されている場所
+    pub source: IrSpan,
+}
+
+/// 関数
+#[derive(Debug, Serialize)]
+pub struct IrFunction {
+    /// 関数名
+    pub name: String,
+    /// 引数
+    pub params: Vec<IrParam>,
+    /// 戻り値の型
+    pub return_type: String,
+    /// ドキュメントコメント
+    pub doc: String,
+    /// 定義されている場所
+    pub source: Option<IrSpan>,
+}
+
+/// 関数の引数
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct IrParam {
+    /// 引数名（`*`は含まない）
+    pub name: String,
+    /// 引数の種類（`inspect.Parameter.kind`に対応する）
+    pub kind: ParamKind,
+    /// 型
+    #[serde(rename = "type")]
+    pub ty: String,
+    /// デフォルト値（Pythonの式。単純なリテラル以外は`...`）
+    pub default: Option<String>,
+}
+
+/// 引数の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParamKind {
+    PositionalOnly,
+    PositionalOrKeyword,
+    VarPositional,
+    KeywordOnly,
+    VarKeyword,
+}
+
+/// モジュール変数・定数
+#[derive(Debug, Serialize)]
+pub struct IrConstant {
+    /// 変数名
+    pub name: String,
+    /// 型
+    #[serde(rename = "type")]
+    pub ty: String,
+    /// `Final`かどうか
+    #[serde(rename = "final")]
+    pub is_final: bool,
+    /// ドキュメントコメント
+    pub doc: String,
+    /// 定義されている場所
+    pub source: Option<IrSpan>,
+}
+
+/// 型エイリアス
+#[derive(Debug, Serialize)]
+pub struct IrTypeAlias {
+    /// エイリアス名
+    pub name: String,
+    /// 型パラメータ
+    pub params: Vec<String>,
+    /// 展開先の型
+    pub target: String,
+}
+
+/// 解決できなかった型
+#[derive(Debug, Serialize)]
+pub struct IrUnresolvedType {
+    /// Rustの型名
+    pub name: String,
+    /// 使われていたアイテム名
+    pub item: String,
+    /// 使われていた場所
+    pub source: IrSpan,
+}
+
+/// プロジェクトを解析し、中間表現をJSONで出力する
+/// 
+/// 中間表現を標準出力に書く場合は、標準出力を中間表現だけにするため、警告を人が読む形式で標準エラー出力に表示する。
+/// 
+/// # Arguments
+/// 
+/// * `options` - スタブ生成のオプション
+/// * `output` - 出力先のファイル（未指定なら標準出力）
+/// 
+/// # Returns
+/// 
+/// * `Result<(), Box<dyn Error>>` - 処理結果（解析に失敗したプロジェクトがあればエラー）
+pub fn dump_ir(options: &GenerateOptions, output: Option<&Path>) -> Result<(), Box<dyn Error>> {
+    let mut logger = options.logger();
+    if output.is_none() {
+        logger = Logger { format: MessageFormat::Human, ..logger };
+    }
+    let project_infos = locate_projects(options)?;
+
+    let mut document = IrDocument {
+        version: IR_VERSION,
+        generator: format!("cargo-pystubgen {}", env!("CARGO_PKG_VERSION")),
+        projects: vec![],
+    };
+    let mut failed = 0;
+    for project_info in &project_infos {
+        let project_info = options.overrides.apply(project_info);
+        match analyze_project(&project_info, options) {
+            Ok(analysis) => {
+                for warning in &analysis.warnings {
+                    logger.diagnostic(warning);
+                }
+                document.projects.push(build_project_ir(&project_info, &analysis));
+            }
+            Err(e) => {
+                logger.error(format!("failed to analyze {}: {}", project_info.project_name, e));
+                failed += 1;
+            }
+        }
+    }
+
+    let json = serde_json::to_string_pretty(&document)?;
+    match output {
+        Some(path) => std::fs::write(path, json + "\n")?,
+        None => writeln!(std::io::stdout(), "{}", json)?,
+    }
+    if failed > 0 {
+        return Err(format!("{} project(s) failed", failed).into());
+    }
+    Ok(())
+}
+
+/// 解析結果からプロジェクトの中間表現を組み立てる
+/// 
+/// # Arguments
+/// 
+/// * `project_info` - プロジェクト情報
+/// * `analysis` - 解析結果
+/// 
+/// # Returns
+/// 
+/// * `IrProject` - プロジェクトの中間表現
+pub fn build_project_ir(project_info: &ProjectInfo, analysis: &ProjectAnalysis) -> IrProject {
+    let root = &project_info.project_root;
+    let python_src_data = &analysis.python_src_data;
+    let span = |source: &ItemSource| IrSpan { file: relative_path(root, &source.file), line: source.line };
+    let source_of = |name: &str| python_src_data.sources.iter().find(|source| source.name == name).map(span);
+
+    let modules = analysis.rust_src_data.iter()
+        .map(|rust_src_data| IrModule {
+            path: rust_src_data.module_path.join("::"),
+            file: relative_path(root, &rust_src_data.file),
+        })
+        .collect();
+    let classes = analysis.rust_src_data.iter()
+        .flat_map(|rust_src_data| rust_src_data.types.iter().map(move |ty| (rust_src_data, ty)))
+        .filter_map(|(rust_src_data, ty)| match &ty.kind {
+            RustTypeKind::PyClass { python_name, doc } if project_info.config.includes_item(python_name) => Some(IrClass {
+                name: python_name.clone(),
+                doc: doc.clone(),
+                rust_path: format!("{}::{}", rust_src_data.module_path.join("::"), ty.name),
+                source: IrSpan { file: relative_path(root, &rust_src_data.file), line: ty.line },
+            }),
+            _ => None,
+        })
+        .collect();
+    let functions = python_src_data.functions.iter()
+        .map(|function_data| IrFunction {
+            name: function_data.name.clone(),
+            params: function_params(function_data),
+            return_type: function_data.return_type.clone(),
+            doc: function_data.doc.clone(),
+            source: source_of(&function_data.name),
+        })
+        .collect();
+    let constants = python_src_data.constants.iter()
+        .map(|constant_data| IrConstant {
+            name: constant_data.name.clone(),
+            ty: constant_data.ty.clone(),
+            is_final: constant_data.is_final,
+            doc: constant_data.doc.clone(),
+            source: source_of(&constant_data.name),
+        })
+        .collect();
+    let type_aliases = python_src_data.type_aliases.iter()
+        .map(|alias| IrTypeAlias { name: alias.name.clone(), params: alias.params.clone(), target: alias.target.clone() })
+        .collect();
+    let unresolved_types = python_src_data.unresolved_types.iter()
+        .map(|unresolved| IrUnresolvedType {
+            name: unresolved.name.clone(),
+            item: unresolved.item.clone(),
+            source: IrSpan { file: relative_path(root, &unresolved.file), line: unresolved.line },
+        })
+        .collect();
+
+    IrProject {
+        name: project_info.project_name.clone(),
+        python_module: project_info.python_module.clone(),
+        stub_path: relative_path(root, &project_info.output_dir.join(format!("{}.pyi", project_info.module_name))),
+        python_version: project_info.config.python_version.map(|version| version.to_string()),
+        modules,
+        classes,
+        functions,
+        constants,
+        type_aliases,
+        unresolved_types,
+    }
+}
+
+/// 関数の引数に種類を付ける（区切りの`*`・`/`は種類に反映して取り除く）
+fn function_params(function_data: &PythonFunctionData) -> Vec<IrParam> {
+    let positional_only = function_data.args.iter().any(|(name, _)| name == "/");
+    let mut kind = if positional_only { ParamKind::PositionalOnly } else { ParamKind::PositionalOrKeyword };
+    let mut params = vec![];
+    for (name, ty) in &function_data.args {
+        let (name, param_kind) = match name.as_str() {
+            "/" => {
+                kind = ParamKind::PositionalOrKeyword;
+                continue;
+            }
+            "*" => {
+                kind = ParamKind::KeywordOnly;
+                continue;
+            }
+            _ => match (name.strip_prefix("**"), name.strip_prefix('*')) {
+                (Some(name), _) => (name, ParamKind::VarKeyword),
+                (None, Some(name)) => {
+                    kind = ParamKind::KeywordOnly;
+                    (name, ParamKind::VarPositional)
+                }
+                (None, None) => (name.as_str(), kind),
+            },
+        };
+        let default = function_data.defaults.iter()
+            .find(|(arg, _)| arg == name)
+            .map(|(_, default)| default.clone());
+        params.push(IrParam { name: name.to_string(), kind: param_kind, ty: ty.clone(), default });
+    }
+    params
+}
+
+/// プロジェクトルートからの相対パスにする（外側のパスはそのまま）
+fn relative_path(root: &Path, path: &Path) -> PathBuf {
+    path.strip_prefix(root).unwrap_or(path).to_path_buf()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stubgen::analyzer::{PythonConstantData, PythonSrcData};
+    use crate::stubgen::parser::{RustSrcData, RustTypeData};
+
+    #[test]
+    fn test_function_params() {
+        let function_data = PythonFunctionData {
+            name: "join".to_string(),
+            args: vec![
+                ("sep".to_string(), "str".to_string()),
+                ("/".to_string(), String::new()),
+                ("*parts".to_string(), "Any".to_string()),
+                ("limit".to_string(), "int".to_string()),
+                ("**options".to_string(), "Any".to_string()),
+            ],
+            defaults: vec![("limit".to_string(), "10".to_string())],
+            return_type: "str".to_string(),
+            doc: String::new(),
+        };
+        let params = function_params(&function_data);
+        let kinds: Vec<(&str, ParamKind, Option<&str>)> = params
+            .iter()
+            .map(|param| (param.name.as_str(), param.kind, param.default.as_deref()))
+            .collect();
+        assert_eq!(kinds, vec![
+            ("sep", ParamKind::PositionalOnly, None),
+            ("parts", ParamKind::VarPositional, None),
+            ("limit", ParamKind::KeywordOnly, Some("10")),
+            ("options", ParamKind::VarKeyword, None),
+        ]);
+    }
+
+    #[test]
+    fn test_build_project_ir() {
+        let project_info = ProjectInfo {
+            project_name: "lib_a".to_string(),
+            module_name: "_core".to_string(),
+            python_module: "lib_a._core".to_string(),
+            output_dir: PathBuf::from("/ws/lib_a/python/lib_a"),
+            project_root: PathBuf::from("/ws/lib_a"),
+            ..Default::default()
+        };
+        let analysis = ProjectAnalysis {
+            rust_src_data: vec![RustSrcData {
+                file: PathBuf::from("/ws/lib_a/src/lib.rs"),
+                module_path: vec!["crate".to_string()],
+                types: vec![RustTypeData {
+                    name: "RustPoint".to_string(),
+                    kind: RustTypeKind::PyClass { python_name: "Point".to_string(), doc: " 2次元の点".to_string() },
+                    line: 4,
+                }],
+                ..Default::default()
+            }],
+            python_src_data: PythonSrcData {
+                functions: vec![PythonFunctionData {
+                    name: "add".to_string(),
+                    args: vec![("a".to_string(), "int".to_string())],
+                    defaults: vec![],
+                    return_type: "int".to_string(),
+                    doc: " 足し算".to_string(),
+                }],
+                constants: vec![PythonConstantData {
+                    name: "VERSION".to_string(),
+                    ty: "str".to_string(),
+                    is_final: true,
+                    doc: String::new(),
+                }],
+                sources: vec![ItemSource { name: "add".to_string(), file: PathBuf::from("/ws/lib_a/src/lib.rs"), line: 9 }],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let json = serde_json::to_value(build_project_ir(&project_info, &analysis)).unwrap();
+        assert_eq!(json, serde_json::json!({
+            "name": "lib_a",
+            "python_module": "lib_a._core",
+            "stub_path": "python/lib_a/_core.pyi",
+            "python_version": null,
+            "modules": [{ "path": "crate", "file": "src/lib.rs" }],
+            "classes": [{ "name": "Point", "rust_path": "crate::RustPoint", "doc": " 2次元の点", "source": { "file": "src/lib.rs", "line": 4 } }],
+            "functions": [{
+                "name": "add",
+                "params": [{ "name": "a", "kind": "positional_or_keyword", "type": "int", "default": null }],
+                "return_type": "int",
+                "doc": " 足し算",
+                "source": { "file": "src/lib.rs", "line": 9 },
+            }],
+            "constants": [{ "name": "VERSION", "type": "str", "final": true, "doc": "", "source": null }],
+            "type_aliases": [],
+            "unresolved_types": [],
+        }));
+    }
+}
@@ -2,27 +2,113 @@
 
 use std::env;
 use std::error::Error;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use toml_edit::DocumentMut;
+use crate::stubgen::backend::{self, BuildBackend, ExtensionModule};
+use crate::stubgen::config::{self, StubConfig};
 
 
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Default)]
 pub struct ProjectInfo{
     pub project_name:String,
+    /// 拡張モジュールの名前（`module-name`の最後の要素、スタブのファイル名になる）
     pub module_name:String,
+    /// 拡張モジュールの完全な名前（`my_pkg._core`など）
+    pub python_module:String,
     pub output_dir:PathBuf,
-    pub rust_src_file:Vec<PathBuf>,
+    /// Pythonソースとの構成
+    pub layout:ProjectLayout,
+    /// pyproject.tomlのあるディレクトリ
+    pub project_root:PathBuf,
+    /// クレートルートのファイル（Cargo.tomlの[lib] path、既定はsrc/lib.rs）
+    pub crate_root:PathBuf,
+    /// Cargo.tomlのパス
+    pub manifest_path:PathBuf,
+    /// ビルドバックエンドの設定で有効にするフィーチャー（[tool.maturin] featuresなど）
+    pub features:Vec<String>,
+    /// [tool.maturin] python-packagesで同梱されるPythonパッケージ
+    pub python_packages:Vec<String>,
+    /// [tool.pystubgen]の設定
+    pub config:StubConfig,
+    /// 拡張モジュールをビルドするバックエンド
+    pub build_backend:BuildBackend,
 }
 
-pub fn locate_python_project()->Result<Vec<ProjectInfo>, Box<dyn Error>>{
+/// 拡張モジュールとPythonソースの構成
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ProjectLayout{
+    /// Pythonパッケージの中に拡張モジュールを置く（`python/my_pkg/_core.pyi`）
+    #[default]
+    Mixed,
+    /// Pythonソースがなく、拡張モジュールがトップレベルのモジュールになる（`<root>/<module>.pyi`）
+    PureRust,
+}
+
+/// 再帰的な探索で中に入らないディレクトリ
+const SKIPPED_DIRS: &[&str] = &["target", ".venv", "venv", "node_modules", ".git"];
+
+/// カレントディレクトリからPythonプロジェクトを探す
+/// 
+/// # Arguments
+/// 
+/// * `scan` - ワークスペースの定義を使わず、配下のpyproject.tomlをすべて探す
+/// 
+/// # Returns
+/// 
+/// * `Result<Vec<ProjectInfo>, Box<dyn Error>>` - 見つかったプロジェクト
+pub fn locate_python_project(scan: bool)->Result<Vec<ProjectInfo>, Box<dyn Error>>{
     let current_dir = env::current_dir().expect("現在のディレクトリを取得できませんでした");
-    // pyproject.tomlを探し解析してワークスペースか、単一プロジェクトかを判断する
+    locate_python_project_in(&current_dir, scan)
+}
+
+/// 指定したディレクトリからPythonプロジェクトを探す
+/// 
+/// # Arguments
+/// 
+/// * `dir` - 探索を始めるディレクトリ（絶対パス）
+/// * `scan` - ワークスペースの定義を使わず、配下のpyproject.tomlをすべて探す
+/// 
+/// # Returns
+/// 
+/// * `Result<Vec<ProjectInfo>, Box<dyn Error>>` - 見つかったプロジェクト
+pub fn locate_python_project_in(dir: &Path, scan: bool)->Result<Vec<ProjectInfo>, Box<dyn Error>>{
     let mut project_infos = vec![];
-    get_maturin_project_info(current_dir.to_path_buf(),&mut project_infos)?;
+    if scan {
+        scan_python_projects(dir, &mut project_infos)?;
+    } else {
+        // pyproject.tomlを探し解析してワークスペースか、単一プロジェクトかを判断する
+        get_workspace_project_info(dir.to_path_buf(),&mut project_infos)?;
+    }
     Ok(project_infos)
 }
 
+/// ディレクトリ以下を再帰的に探し、PyO3の拡張モジュールを持つプロジェクトをすべて集める
+/// 
+/// `target`・`.venv`・`node_modules`などには入らない。
+/// 
+/// # Arguments
+/// 
+/// * `dir` - 探索するディレクトリ
+/// * `project_infos` - プロジェクトの情報を格納するベクター
+fn scan_python_projects(dir:&Path, project_infos:&mut Vec<ProjectInfo>)->Result<(), Box<dyn Error>>{
+    if let Some(doc) = read_pyproject(dir)? {
+        project_infos.extend(get_project_infos(&doc, dir)?);
+    }
+
+    let mut sub_dirs: Vec<PathBuf> = dir.read_dir()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .filter(|path| !path.file_name().and_then(|name| name.to_str()).is_some_and(|name| SKIPPED_DIRS.contains(&name)))
+        .collect();
+    sub_dirs.sort();
+    for sub_dir in sub_dirs {
+        scan_python_projects(&sub_dir, project_infos)?;
+    }
+    Ok(())
+}
+
 /// プロジェクトの情報を集める
 /// 
 /// # Arguments
@@ -33,78 +119,279 @@ pub fn locate_python_project()->Result<Vec<ProjectInfo>, Box<dyn Error>>{
 /// # Returns
 /// 
 /// 
-fn get_maturin_project_info(project_root:PathBuf,project_infos:&mut Vec<ProjectInfo>)->Result<(), Box<dyn Error>>{
-    if project_root.join("pyproject.toml").exists(){
-        // ワークスペース
-        let pyproject_toml = std::fs::read_to_string(project_root.join("pyproject.toml")).unwrap();
-        let doc = pyproject_toml.parse::<DocumentMut>().expect("TOMLパース失敗");
-
-        if let Some(uv_workspace) = doc.get("tool")
-                                   .and_then(|tool| tool.get("uv"))
-                                   .and_then(|uv| uv.get("workspace")){
-            let members = get_workspace_members_path(uv_workspace,project_root);
-            for member in members {
-                get_maturin_project_info(member, project_infos)?;
-            }
+fn get_workspace_project_info(project_root:PathBuf,project_infos:&mut Vec<ProjectInfo>)->Result<(), Box<dyn Error>>{
+    let Some(doc) = read_pyproject(&project_root)? else {
+        //pythonのプロジェクトではないのでエラー
+        return Err(format!("{}はpyproject.tomlを発見できなかったため解析できませんでした", project_root.display()).into());
+    };
+
+    // ワークスペース
+    let members = get_workspace_members_path(&doc, &project_root)?;
+    project_infos.extend(get_project_infos(&doc, &project_root)?);
+    for member in members {
+        get_workspace_project_info(member, project_infos)?;
+    }
+    Ok(())
+}
+
+/// ディレクトリのpyproject.tomlを読み込む
+/// 
+/// pyproject.tomlがなく`setup.py`だけがあるプロジェクトは、空のpyproject.tomlとして扱う。
+/// 
+/// # Arguments
+/// 
+/// * `dir` - プロジェクトのディレクトリ
+/// 
+/// # Returns
+/// 
+/// * `Result<Option<DocumentMut>, Box<dyn Error>>` - pyproject.tomlの内容（Pythonのプロジェクトでなければ`None`）
+fn read_pyproject(dir:&Path)->Result<Option<DocumentMut>, Box<dyn Error>>{
+    let pyproject_path = dir.join("pyproject.toml");
+    if pyproject_path.is_file() {
+        let doc = std::fs::read_to_string(&pyproject_path)?
+            .parse::<DocumentMut>()
+            .map_err(|e| format!("failed to parse {}: {}", pyproject_path.display(), e))?;
+        return Ok(Some(doc));
+    }
+    Ok(dir.join("setup.py").is_file().then(DocumentMut::new))
+}
+
+/// pyproject.tomlからPyO3の拡張モジュールを持つプロジェクトの情報を組み立てる
+/// 
+/// # Arguments
+/// 
+/// * `pyproject_doc` - pyproject.tomlのDocumentMut
+/// * `project_root` - pyproject.tomlのあるディレクトリ
+/// 
+/// # Returns
+/// 
+/// * `Result<Vec<ProjectInfo>, Box<dyn Error>>` - 拡張モジュールごとのプロジェクトの情報（拡張モジュールがなければ空）
+fn get_project_infos(pyproject_doc:&DocumentMut, project_root:&Path)->Result<Vec<ProjectInfo>, Box<dyn Error>>{
+    let Some(build_backend) = backend::detect_backend(pyproject_doc, project_root) else {
+        return Ok(vec![]);
+    };
+    let config = config::read_config(pyproject_doc, project_root)?;
+    backend::extension_modules(build_backend, pyproject_doc, project_root)?
+        .into_iter()
+        .map(|extension| get_project_info(extension, build_backend, &config, project_root))
+        .collect()
+}
+
+/// 拡張モジュールの設定からプロジェクトの情報を組み立てる
+/// 
+/// # Arguments
+/// 
+/// * `extension` - ビルドバックエンドの設定から読み取った拡張モジュール
+/// * `build_backend` - 拡張モジュールをビルドするバックエンド
+/// * `config` - [tool.pystubgen]の設定
+/// * `project_root` - pyproject.tomlのあるディレクトリ
+/// 
+/// # Returns
+/// 
+/// * `Result<ProjectInfo, Box<dyn Error>>` - プロジェクトの情報
+fn get_project_info(extension:ExtensionModule, build_backend:BuildBackend, config:&StubConfig, project_root:&Path)->Result<ProjectInfo, Box<dyn Error>>{
+    // Cargoワークスペースのサブディレクトリにあるクレートも manifest-path で指定できる
+    let manifest_path = extension.manifest_path.clone();
+    let cargo_toml = std::fs::read_to_string(&manifest_path)
+        .map_err(|e| format!("failed to read {}: {}", manifest_path.display(), e))?;
+    let cargo_doc = cargo_toml.parse::<DocumentMut>()
+        .map_err(|e| format!("failed to parse {}: {}", manifest_path.display(), e))?;
+    if cargo_doc.get("package").is_none() {
+        return Err(format!(
+            "{} has no [package] section; point the build backend's manifest path (e.g. [tool.maturin] manifest-path) at the extension crate",
+            manifest_path.display()
+        ).into());
+    }
+    let manifest_dir = manifest_path.parent().unwrap_or(project_root);
+    let crate_root = manifest_dir.join(get_crate_root(&cargo_doc));
+
+    let python_module = extension.python_module.clone().unwrap_or_else(|| get_module_name(&cargo_doc));
+    let package_dir = |package: &str| extension.package_dir(package).unwrap_or_else(|| project_root.to_path_buf());
+    let (layout, output_dir, module_name) = match python_module.rsplit_once('.') {
+        // my_pkg._core は my_pkg パッケージの中に置かれる
+        Some((package, module)) => (ProjectLayout::Mixed, package_dir(package), module.to_string()),
+        // パッケージと同名のフォルダがあれば my_pkg/my_pkg.pyi に置かれる
+        None if package_dir(&python_module).is_dir() => (
+            ProjectLayout::Mixed,
+            package_dir(&python_module),
+            python_module.clone(),
+        ),
+        // Pythonソースがなければトップレベルのモジュールになる
+        None => (ProjectLayout::PureRust, project_root.to_path_buf(), python_module.clone()),
+    };
+    // [tool.pystubgen] output-dirはバックエンドの構成より優先する
+    let output_dir = config.output_dir.as_ref().map_or(output_dir, |dir| project_root.join(dir));
+
+    Ok(ProjectInfo {
+        project_name: get_project_name(&cargo_doc),
+        module_name,
+        python_module,
+        output_dir,
+        layout,
+        project_root: project_root.to_path_buf(),
+        crate_root,
+        manifest_path,
+        features: extension.features,
+        python_packages: extension.python_packages,
+        config: config.clone(),
+        build_backend,
+    })
+}
+
+/// pyproject.tomlのワークスペース定義からメンバーを取得する
+/// 
+/// 次の定義に対応する。
+/// 
+/// * uv・Rye・Hatch: `[tool.<manager>.workspace]`の`members`（グロブ）と`exclude`
+/// * Poetry: `path`で指定されたローカル依存（`[tool.poetry.dependencies]`とグループ）
+/// * PDM: `-e file:///${PROJECT_ROOT}/...`形式の編集可能な依存
+/// 
+/// # Arguments
+/// 
+/// * `pyproject_doc` - pyproject.tomlのDocumentMut
+/// * `workspace_root` - ワークスペースのルートディレクトリ
+///
+/// # Returns
+/// 
+/// ワークスペースのメンバーのパス（重複なし）
+fn get_workspace_members_path(pyproject_doc:&DocumentMut, workspace_root:&Path)->Result<Vec<PathBuf>, Box<dyn Error>>{
+    let tool = pyproject_doc.get("tool");
+    let mut members = vec![];
+
+    for manager in ["uv", "rye", "hatch"] {
+        let Some(workspace) = tool.and_then(|tool| tool.get(manager)).and_then(|manager| manager.get("workspace")) else {
+            continue;
+        };
+        let patterns = get_member_patterns(workspace.get("members"));
+        // Ryeはmembersが省略されると配下のすべてのプロジェクトをメンバーにする
+        let patterns = if patterns.is_empty() && manager == "rye" { vec!["*".to_string()] } else { patterns };
+        let excludes = get_member_patterns(workspace.get("exclude"));
+        members.extend(expand_member_patterns(workspace_root, &patterns, &excludes)?);
+    }
+
+    if let Some(poetry) = tool.and_then(|tool| tool.get("poetry")) {
+        let mut dependency_tables = vec![poetry.get("dependencies"), poetry.get("dev-dependencies")];
+        if let Some(groups) = poetry.get("group").and_then(|groups| groups.as_table_like()) {
+            dependency_tables.extend(groups.iter().map(|(_, group)| group.get("dependencies")));
         }
-        else{
-            if is_maturin_project(&doc){
-                let cargo_toml = std::fs::read_to_string(project_root.join("Cargo.toml")).unwrap();
-                let cargo_doc = cargo_toml.parse::<DocumentMut>().unwrap();
-                let mut rust_src_files = vec![];
-                get_rust_src_files(project_root.join("src"),&mut rust_src_files)?;
-                let output_dir = project_root.join("src").join(get_project_name(&cargo_doc));
-                project_infos.push(ProjectInfo {
-                    project_name: get_project_name(&cargo_doc),
-                    module_name: get_module_name(&cargo_doc),
-                    rust_src_file: rust_src_files,
-                    output_dir: output_dir
-                });
+        for dependencies in dependency_tables.into_iter().flatten().filter_map(|table| table.as_table_like()) {
+            for (_, dependency) in dependencies.iter() {
+                if let Some(path) = dependency.get("path").and_then(|path| path.as_str()) {
+                    members.push(workspace_root.join(path));
+                }
             }
         }
     }
-    else{
-        //pythonのプロジェクトではないのでエラー
-        return Err(format!("{}はpyproject.tomlを発見できなかったため解析できませんでした", project_root.display()).into());
+
+    if let Some(pdm) = tool.and_then(|tool| tool.get("pdm")) {
+        let mut requirements = vec![];
+        for key in ["dev-dependencies", "optional-dependencies"] {
+            if let Some(groups) = pdm.get(key).and_then(|groups| groups.as_table_like()) {
+                requirements.extend(groups.iter().filter_map(|(_, group)| group.as_array()).flat_map(|array| array.iter()));
+            }
+        }
+        if let Some(dependencies) = pyproject_doc.get("project").and_then(|project| project.get("dependencies")).and_then(|d| d.as_array()) {
+            requirements.extend(dependencies.iter());
+        }
+        members.extend(
+            requirements
+                .into_iter()
+                .filter_map(|requirement| requirement.as_str())
+                .filter_map(get_pdm_local_path)
+                .map(|path| workspace_root.join(path)),
+        );
     }
 
-    Ok(())
+    let mut unique = vec![];
+    for member in members {
+        let member = normalize_path(&member);
+        if member != workspace_root && !unique.contains(&member) {
+            unique.push(member);
+        }
+    }
+    Ok(unique)
 }
 
-/// pyproject.tomlの[build-system]セクションのbuild-backendがmaturinであるかどうかを確認する
-fn is_maturin_project(pyproject_toml:&toml_edit::DocumentMut)->bool{
-    let build_system = pyproject_toml.get("build-system").unwrap();
-    let build_backend = build_system.get("build-backend").unwrap();
-    build_backend.as_str().unwrap() == "maturin"
+/// `members`・`exclude`の値を文字列のリストにする（Hatchの`{ path = "..." }`にも対応する）
+fn get_member_patterns(item:Option<&toml_edit::Item>)->Vec<String>{
+    let Some(array) = item.and_then(|item| item.as_array()) else {
+        return vec![];
+    };
+    array.iter()
+        .filter_map(|value| match value {
+            toml_edit::Value::String(pattern) => Some(pattern.value().to_string()),
+            toml_edit::Value::InlineTable(table) => table.get("path").and_then(|path| path.as_str()).map(str::to_string),
+            _ => None,
+        })
+        .collect()
 }
 
-/// pyproject.tomlの[tool.uv.workspace]セクションからワークスペースのメンバーを取得する
+/// グロブパターンをpyproject.tomlのあるディレクトリに展開する
 /// 
 /// # Arguments
 /// 
-/// * `workspace_section` - pyproject.tomlの[tool.uv.workspace]セクション
 /// * `workspace_root` - ワークスペースのルートディレクトリ
-///
+/// * `patterns` - メンバーのパターン（`libs/*`、`packages/**/py`など）
+/// * `excludes` - 除外するパターン
+/// 
 /// # Returns
 /// 
-/// ワークスペースのメンバーのパス
-fn get_workspace_members_path(workspace_section:&toml_edit::Item,workspace_root:PathBuf)->Vec<PathBuf>{
-    let members = workspace_section.get("members").unwrap();
-    members.as_array().unwrap().iter()
-    .map(|v|{
-        //memberが/*で終わっているかどうかを確認する
-        if v.as_str().unwrap().ends_with("/*"){
-            // [foo/*]で終わっている場合は、ディレクトリを再帰的に探索する
-            let path = v.as_str().unwrap().trim_end_matches("/*");
-            let dir = workspace_root.join(path);
-            dir.read_dir().unwrap().map(|entry| entry.unwrap().path()).collect()
+/// * `Result<Vec<PathBuf>, Box<dyn Error>>` - メンバーのディレクトリ（パターンごとに名前順）
+fn expand_member_patterns(workspace_root:&Path, patterns:&[String], excludes:&[String])->Result<Vec<PathBuf>, Box<dyn Error>>{
+    let excludes = excludes
+        .iter()
+        .map(|exclude| glob::Pattern::new(&workspace_root.join(exclude).to_string_lossy()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("invalid workspace exclude pattern: {}", e))?;
+
+    let mut members = vec![];
+    for pattern in patterns {
+        let is_glob = pattern.contains(['*', '?', '[']);
+        let full_pattern = workspace_root.join(pattern);
+        let mut paths: Vec<PathBuf> = glob::glob(&full_pattern.to_string_lossy())
+            .map_err(|e| format!("invalid workspace member pattern `{}`: {}", pattern, e))?
+            .filter_map(|path| path.ok())
+            // グロブにはワークスペースのメンバーではないディレクトリやファイルも一致する
+            .filter(|path| !is_glob || path.join("pyproject.toml").is_file())
+            .filter(|path| !excludes.iter().any(|exclude| exclude.matches_path(path)))
+            .collect();
+        if !is_glob && paths.is_empty() && !excludes.iter().any(|exclude| exclude.matches_path(&full_pattern)) {
+            // 存在しないメンバーはget_workspace_project_infoでエラーにする
+            paths.push(full_pattern);
         }
-        else{
-            // [foo/*]で終わっていない場合は、そのままパスを返す
-            vec![workspace_root.join(v.as_str().unwrap())]
+        paths.sort();
+        members.extend(paths);
+    }
+    Ok(members)
+}
+
+/// PDMの依存指定からローカルパスを取り出す（`-e file:///${PROJECT_ROOT}/libs/foo`、`foo @ file:///${PROJECT_ROOT}/libs/foo`）
+fn get_pdm_local_path(requirement:&str)->Option<String>{
+    let requirement = requirement.trim();
+    let location = match requirement.strip_prefix("-e") {
+        Some(editable) => editable.trim(),
+        None => requirement.split_once(" @ ")?.1.trim(),
+    };
+    let path = location
+        .strip_prefix("file:///${PROJECT_ROOT}/")
+        .or_else(|| location.strip_prefix("file://${PROJECT_ROOT}/"))
+        .or_else(|| location.strip_prefix("./"))?;
+    Some(path.trim_end_matches('/').to_string())
+}
+
+/// `.`や`..`を取り除いたパスにする（ファイルシステムにはアクセスしない）
+fn normalize_path(path:&Path)->PathBuf{
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            std::path::Component::CurDir => {}
+            std::path::Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
         }
-    })
-    .flatten().collect()
+    }
+    normalized
 }
 
 
@@ -125,6 +412,7 @@ fn get_project_name(toml_doc:&toml_edit::DocumentMut)->String{
 
 /// Cargo.tomlの[lib]セクションからモジュール名を取得する
 /// 
+/// ビルドバックエンドの設定に拡張モジュールの名前がない場合に使う。
 /// 
 /// # Arguments
 /// 
@@ -133,89 +421,199 @@ fn get_project_name(toml_doc:&toml_edit::DocumentMut)->String{
 /// # Returns
 /// 
 /// モジュール名
-fn get_module_name(toml_doc:&toml_edit::DocumentMut)->String{
-    let lib_section = toml_doc.get("lib").unwrap();
-    let name = lib_section.get("name").unwrap();
-    name.as_str().unwrap().to_string()
+pub fn get_module_name(toml_doc:&toml_edit::DocumentMut)->String{
+    // [lib] nameがなければパッケージ名（`-`は`_`になる）
+    toml_doc.get("lib")
+        .and_then(|lib| lib.get("name"))
+        .and_then(|name| name.as_str())
+        .map_or_else(|| get_project_name(toml_doc).replace('-', "_"), str::to_string)
 }
 
-/// srcディレクトリ以下のRustソースファイルを再帰的に探索する
-fn get_rust_src_files(src_root:PathBuf,rust_src_files:&mut Vec<PathBuf>)->Result<(), Box<dyn Error>>{
-    let entries = std::fs::read_dir(src_root)?;
-    for entry in entries{
-        let entry = entry?;
-        let path = entry.path();
-        if path.is_file() && path.extension().unwrap() == "rs"{
-            rust_src_files.push(path);
-        }
-        else if path.is_dir(){
-            get_rust_src_files(path,rust_src_files)?;
-        }
-    }
-    Ok(())
+/// Cargo.tomlの[lib]セクションからクレートルートのパスを取得する
+/// 
+/// # Arguments
+/// 
+/// * `toml_doc` - Cargo.tomlのDocumentMut
+/// 
+/// # Returns
+/// 
+/// クレートルートのパス（Cargo.tomlからの相対パス、既定は`src/lib.rs`）
+fn get_crate_root(toml_doc:&toml_edit::DocumentMut)->PathBuf{
+    toml_doc.get("lib")
+        .and_then(|lib| lib.get("path"))
+        .and_then(|path| path.as_str())
+        .map_or_else(|| PathBuf::from("src").join("lib.rs"), PathBuf::from)
 }
 
 
 #[cfg(test)]
 mod tests{
     use super::*;
-    use std::path::PathBuf;
 
-    const TEST_PROJECT_ROOT: &str = "tests\\test-project";
-    const TEST_PROJECT_ROOT_SINGLE: &str = "tests\\test-project\\single_project";
+    const TEST_PROJECT_ROOT: &str = "tests/test-project";
+    const TEST_PROJECT_ROOT_SINGLE: &str = "tests/test-project/single_project";
 
     #[test]
-    fn test_is_maturin_project(){
-        // テスト用のTOMLデータ
-        let maturin_toml = r#"
-        [build-system]
-        build-backend = "maturin"
-        "#;
+    fn test_get_workspace_members_path(){
+        let current_dir = env::current_dir().unwrap();
+        let project_root = current_dir.join(TEST_PROJECT_ROOT);
+        let pyproject_toml = std::fs::read_to_string(project_root.join("pyproject.toml")).unwrap();
+        let toml_doc = pyproject_toml.parse::<DocumentMut>().unwrap();
+        let members = get_workspace_members_path(&toml_doc,&project_root).unwrap();
+        assert_eq!(members.len(), 4);
+        assert_eq!(members[0], current_dir.join(TEST_PROJECT_ROOT).join("libs").join("lib_a"));
+        assert_eq!(members[1], current_dir.join(TEST_PROJECT_ROOT).join("libs").join("lib_b"));
+        assert_eq!(members[2], current_dir.join(TEST_PROJECT_ROOT).join("libs").join("lib_c"));
+        assert_eq!(members[3], current_dir.join(TEST_PROJECT_ROOT).join("single_project"));
+    }
 
-        let non_maturin_toml = r#"
-        [build-system]
-        build-backend = "setuptools"
-        "#;
+    #[test]
+    fn test_get_workspace_members_path_with_managers(){
+        let dir = env::temp_dir().join(format!("pystubgen-workspace-{}", std::process::id()));
+        for member in ["libs/a", "libs/b", "libs/legacy", "packages/x/py", "apps/poetry", "apps/pdm"] {
+            std::fs::create_dir_all(dir.join(member)).unwrap();
+            std::fs::write(dir.join(member).join("pyproject.toml"), "").unwrap();
+        }
+        // pyproject.tomlのないディレクトリはグロブに一致してもメンバーではない
+        std::fs::create_dir_all(dir.join("libs").join("docs")).unwrap();
 
-        // maturinプロジェクトのテスト
-        let toml_doc = maturin_toml.parse::<DocumentMut>().unwrap();
-        assert!(is_maturin_project(&toml_doc), "maturinプロジェクトとして認識されるべき");
+        let members = |toml: &str| {
+            get_workspace_members_path(&toml.parse::<DocumentMut>().unwrap(), &dir).unwrap()
+        };
+        let uv = members("[tool.uv.workspace]\nmembers = [\"libs/*\", \"packages/**/py\"]\nexclude = [\"libs/legacy\"]");
+        let rye = members("[tool.rye.workspace]\nmembers = [\"libs/[ab]\"]");
+        let hatch = members("[tool.hatch.workspace]\nmembers = [{ path = \"libs/a\" }, \"libs/b\"]");
+        let poetry = members("[tool.poetry.group.dev.dependencies]\nfoo = { path = \"apps/poetry\", develop = true }\nbar = \"^1.0\"");
+        let pdm = members("[tool.pdm.dev-dependencies]\ndev = [\"-e file:///${PROJECT_ROOT}/apps/pdm\", \"pytest\"]");
+        let nothing = members("[project]\nname = \"root\"");
+        std::fs::remove_dir_all(&dir).unwrap();
 
-        // 非maturinプロジェクトのテスト
-        let toml_doc = non_maturin_toml.parse::<DocumentMut>().unwrap();
-        assert!(!is_maturin_project(&toml_doc), "maturinプロジェクトとして認識されるべきではない");
+        assert_eq!(uv, vec![dir.join("libs").join("a"), dir.join("libs").join("b"), dir.join("packages").join("x").join("py")]);
+        assert_eq!(rye, vec![dir.join("libs").join("a"), dir.join("libs").join("b")]);
+        assert_eq!(hatch, vec![dir.join("libs").join("a"), dir.join("libs").join("b")]);
+        assert_eq!(poetry, vec![dir.join("apps").join("poetry")]);
+        assert_eq!(pdm, vec![dir.join("apps").join("pdm")]);
+        assert!(nothing.is_empty());
     }
 
     #[test]
-    fn test_get_workspace_members_path(){
+    fn test_scan_python_projects(){
         let current_dir = env::current_dir().unwrap();
-        let project_root = PathBuf::from(current_dir.join(TEST_PROJECT_ROOT));
-        let pyproject_toml = std::fs::read_to_string(project_root.join("pyproject.toml")).unwrap();
-        let toml_doc = pyproject_toml.parse::<DocumentMut>().unwrap();
-        let workspace_section = toml_doc.get("tool").unwrap().get("uv").unwrap().get("workspace").unwrap();
-        let members = get_workspace_members_path(workspace_section,project_root);
-        assert_eq!(members.len(), 4);
-        assert_eq!(members[0], PathBuf::from(current_dir.join(TEST_PROJECT_ROOT).join("libs").join("lib_a")));
-        assert_eq!(members[1], PathBuf::from(current_dir.join(TEST_PROJECT_ROOT).join("libs").join("lib_b")));
-        assert_eq!(members[2], PathBuf::from(current_dir.join(TEST_PROJECT_ROOT).join("libs").join("lib_c")));
-        assert_eq!(members[3], PathBuf::from(current_dir.join(TEST_PROJECT_ROOT).join("single_project")));
+        let mut project_infos = vec![];
+        scan_python_projects(&current_dir.join(TEST_PROJECT_ROOT), &mut project_infos).unwrap();
+        let names: Vec<&str> = project_infos.iter().map(|info| info.project_name.as_str()).collect();
+        assert_eq!(names, vec!["lib_c", "single_project"]);
     }
 
     #[test]
     fn test_get_project_info(){
         let current_dir = env::current_dir().unwrap();
-        let project_root = PathBuf::from(current_dir.join(TEST_PROJECT_ROOT));
+        let project_root = current_dir.join(TEST_PROJECT_ROOT);
         let mut project_infos = vec![];
-        get_maturin_project_info(project_root,&mut project_infos).unwrap();
+        get_workspace_project_info(project_root,&mut project_infos).unwrap();
         assert_eq!(project_infos.len(), 2);
+        assert_eq!(project_infos[1].crate_root, current_dir.join(TEST_PROJECT_ROOT_SINGLE).join("src").join("lib.rs"));
+        assert_eq!(project_infos[1].python_module, "single_project._core");
+        assert_eq!(project_infos[1].module_name, "_core");
+        assert_eq!(project_infos[1].output_dir, current_dir.join(TEST_PROJECT_ROOT_SINGLE).join("src").join("single_project"));
+        assert_eq!(project_infos[1].python_packages, vec!["single_project".to_string()]);
     }
 
     #[test]
-    fn test_get_rust_src_files(){
-        let current_dir = env::current_dir().unwrap();
-        let project_root = PathBuf::from(current_dir.join(TEST_PROJECT_ROOT_SINGLE).join("src"));
-        let mut rust_src_files = vec![];
-        get_rust_src_files(project_root,&mut rust_src_files).unwrap();
-        assert_eq!(rust_src_files.len(), 4);
+    fn test_get_project_info_in_cargo_workspace(){
+        let dir = env::temp_dir().join(format!("pystubgen-locator-{}", std::process::id()));
+        let crate_dir = dir.join("crates").join("py");
+        std::fs::create_dir_all(&crate_dir).unwrap();
+        std::fs::write(dir.join("Cargo.toml"), "[workspace]\nmembers = [\"crates/*\"]\n").unwrap();
+        std::fs::write(crate_dir.join("Cargo.toml"), "[package]\nname = \"my-pkg-py\"\n[lib]\npath = \"bindings.rs\"\n").unwrap();
+        let pyproject = r#"
+        [build-system]
+        build-backend = "maturin"
+
+        [tool.maturin]
+        manifest-path = "crates/py/Cargo.toml"
+        python-source = "python"
+        module-name = "my_pkg.native._core"
+        features = ["python"]
+        "#.parse::<DocumentMut>().unwrap();
+
+        let project_info = get_project_infos(&pyproject, &dir).unwrap().remove(0);
+        let maturin = "[build-system]\nbuild-backend = \"maturin\"".parse::<DocumentMut>().unwrap();
+        let workspace_root = get_project_infos(&maturin, &dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(project_info.project_name, "my-pkg-py");
+        assert_eq!(project_info.module_name, "_core");
+        assert_eq!(project_info.manifest_path, crate_dir.join("Cargo.toml"));
+        assert_eq!(project_info.crate_root, crate_dir.join("bindings.rs"));
+        assert_eq!(project_info.output_dir, dir.join("python").join("my_pkg").join("native"));
+        assert_eq!(project_info.features, vec!["python".to_string()]);
+        // 仮想マニフェストだけではクレートが決まらない
+        assert!(workspace_root.is_err());
+    }
+
+    #[test]
+    fn test_get_project_info_layouts(){
+        let dir = env::temp_dir().join(format!("pystubgen-layout-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("Cargo.toml"), "[package]\nname = \"fastmath\"\n").unwrap();
+        let pyproject = "[build-system]\nbuild-backend = \"maturin\"\n".parse::<DocumentMut>().unwrap();
+
+        // Pythonソースがない場合はトップレベルのモジュール
+        let pure_rust = get_project_infos(&pyproject, &dir).unwrap().remove(0);
+        // 同名のパッケージフォルダがある場合はその中
+        std::fs::create_dir_all(dir.join("fastmath")).unwrap();
+        let mixed = get_project_infos(&pyproject, &dir).unwrap().remove(0);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(pure_rust.layout, ProjectLayout::PureRust);
+        assert_eq!(pure_rust.output_dir, dir);
+        assert_eq!(pure_rust.module_name, "fastmath");
+        assert_eq!(mixed.layout, ProjectLayout::Mixed);
+        assert_eq!(mixed.output_dir, dir.join("fastmath"));
+    }
+
+    #[test]
+    fn test_get_project_infos_with_setuptools_rust(){
+        let dir = env::temp_dir().join(format!("pystubgen-setuptools-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("rust")).unwrap();
+        std::fs::write(dir.join("rust").join("Cargo.toml"), "[package]\nname = \"pkg-core\"\n").unwrap();
+        let pyproject = r#"
+        [build-system]
+        build-backend = "setuptools.build_meta"
+
+        [tool.setuptools.package-dir]
+        "" = "python"
+
+        [[tool.setuptools-rust.ext-modules]]
+        target = "pkg._core"
+        path = "rust/Cargo.toml"
+        "#.parse::<DocumentMut>().unwrap();
+
+        let project_infos = get_project_infos(&pyproject, &dir).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(project_infos.len(), 1);
+        assert_eq!(project_infos[0].project_name, "pkg-core");
+        assert_eq!(project_infos[0].module_name, "_core");
+        assert_eq!(project_infos[0].output_dir, dir.join("python").join("pkg"));
+        assert_eq!(project_infos[0].crate_root, dir.join("rust").join("src").join("lib.rs"));
+    }
+
+    #[test]
+    fn test_get_module_name(){
+        let lib_toml = "[package]\nname = \"my-ext\"\n[lib]\nname = \"_native\"".parse::<DocumentMut>().unwrap();
+        assert_eq!(get_module_name(&lib_toml), "_native");
+        let package_toml = "[package]\nname = \"my-ext\"".parse::<DocumentMut>().unwrap();
+        assert_eq!(get_module_name(&package_toml), "my_ext");
+    }
+
+    #[test]
+    fn test_get_crate_root(){
+        let default_toml = "[lib]\nname = \"_core\"".parse::<DocumentMut>().unwrap();
+        assert_eq!(get_crate_root(&default_toml), PathBuf::from("src").join("lib.rs"));
+
+        let custom_toml = "[lib]\npath = \"rust/bindings.rs\"".parse::<DocumentMut>().unwrap();
+        assert_eq!(get_crate_root(&custom_toml), PathBuf::from("rust/bindings.rs"));
     }
 }
\ No newline at end of file
@@ -0,0 +1,300 @@
+//! メッセージ出力モジュール
+//! 
+//! 進捗・診断・生成したファイル・最後のまとめを、詳細度（`-q`・`-v`・`-vv`）と出力形式に合わせて表示します。
+//! 人が読む形式では診断をrustcと同じ形で標準エラー出力に書き、
+//! `--message-format json`ではエディタやCIが読めるように1行に1つのJSONを標準出力に書きます。
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use serde::Serialize;
+use crate::stubgen::diagnostic::{Diagnostic, Severity};
+use crate::stubgen::generator::FileKind;
+
+/// 表示の詳細度
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Verbosity {
+    /// 警告とエラーだけ（`-q`）
+    Quiet,
+    /// 進捗と生成したファイル
+    #[default]
+    Normal,
+    /// プロジェクトの構成（`-v`）
+    Verbose,
+    /// モジュールごとの処理とキャッシュ（`-vv`）
+    Debug,
+}
+
+impl Verbosity {
+    /// コマンドラインの`-q`と`-v`の数から詳細度を決める
+    pub fn from_flags(quiet: bool, verbose: u8) -> Self {
+        match (quiet, verbose) {
+            (true, _) => Verbosity::Quiet,
+            (false, 0) => Verbosity::Normal,
+            (false, 1) => Verbosity::Verbose,
+            (false, _) => Verbosity::Debug,
+        }
+    }
+}
+
+/// メッセージの出力形式
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MessageFormat {
+    /// 人が読む形式
+    #[default]
+    Human,
+    /// 1行に1つのJSON
+    Json,
+}
+
+impl FromStr for MessageFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "human" => Ok(MessageFormat::Human),
+            "json" => Ok(MessageFormat::Json),
+            _ => Err(format!("unknown message format `{}` (expected human or json)", s)),
+        }
+    }
+}
+
+impl fmt::Display for MessageFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MessageFormat::Human => write!(f, "human"),
+            MessageFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
+/// 詳細度を持つ進捗メッセージ
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogMessage {
+    /// この詳細度以上で表示する
+    pub verbosity: Verbosity,
+    /// メッセージ
+    pub message: String,
+}
+
+impl LogMessage {
+    /// 進捗メッセージを作る
+    pub fn new(verbosity: Verbosity, message: impl Into<String>) -> Self {
+        LogMessage { verbosity, message: message.into() }
+    }
+}
+
+/// 最後に表示するまとめ
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Summary {
+    /// 処理したプロジェクトの数
+    pub projects: usize,
+    /// 失敗したプロジェクトの数
+    pub failed: usize,
+    /// スタブに出力した関数・定数・クラス・型エイリアスの数
+    pub emitted_items: usize,
+    /// 設定で除外した関数・定数・クラスの数
+    pub skipped_items: usize,
+    /// 警告の数
+    pub warnings: usize,
+}
+
+/// JSON形式で出力するメッセージ
+#[derive(Serialize)]
+#[serde(tag = "reason", rename_all = "kebab-case")]
+enum JsonMessage<'a> {
+    /// 進捗メッセージ
+    Log { level: Verbosity, message: &'a str },
+    /// 診断（`rendered`は人が読む形式）
+    Diagnostic {
+        #[serde(flatten)]
+        diagnostic: &'a Diagnostic,
+        rendered: String,
+    },
+    /// 生成したファイル
+    File { kind: FileKind, path: &'a Path, written: bool },
+    /// まとめ
+    Summary(&'a Summary),
+}
+
+/// 詳細度と出力形式に合わせてメッセージを表示する
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Logger {
+    /// 表示の詳細度
+    pub verbosity: Verbosity,
+    /// 出力形式
+    pub format: MessageFormat,
+}
+
+impl Logger {
+    /// 進捗メッセージを表示する（詳細度が足りなければ表示しない）
+    pub fn log(&self, verbosity: Verbosity, message: &str) {
+        if verbosity > self.verbosity {
+            return;
+        }
+        match self.format {
+            MessageFormat::Human => println!("{}", message),
+            MessageFormat::Json => self.emit(&JsonMessage::Log { level: verbosity, message }),
+        }
+    }
+
+    /// 進捗メッセージをまとめて表示する
+    pub fn log_all(&self, messages: &[LogMessage]) {
+        for message in messages {
+            self.log(message.verbosity, &message.message);
+        }
+    }
+
+    /// 診断を表示する（`-q`でも表示する）
+    pub fn diagnostic(&self, diagnostic: &Diagnostic) {
+        let rendered = render_diagnostic(diagnostic);
+        match self.format {
+            MessageFormat::Human => eprintln!("{}", rendered),
+            MessageFormat::Json => self.emit(&JsonMessage::Diagnostic { diagnostic, rendered }),
+        }
+    }
+
+    /// 場所を持たない警告を表示する
+    pub fn warn(&self, message: impl Into<String>) {
+        self.diagnostic(&Diagnostic::warning(message));
+    }
+
+    /// エラーを表示する
+    pub fn error(&self, message: impl Into<String>) {
+        self.diagnostic(&Diagnostic { severity: Severity::Error, message: message.into(), locations: vec![] });
+    }
+
+    /// 生成したファイルを表示する
+    pub fn file(&self, kind: FileKind, path: &Path, written: bool) {
+        match self.format {
+            MessageFormat::Human => {
+                let label = match kind {
+                    FileKind::Stub => "stub file",
+                    FileKind::PyTyped | FileKind::InitStub => "package file",
+                    FileKind::Docs => "API reference",
+                };
+                if written {
+                    self.log(Verbosity::Normal, &format!("Generating {}: {}", label, path.display()));
+                } else {
+                    let mut label = label.to_string();
+                    label[..1].make_ascii_uppercase();
+                    self.log(Verbosity::Normal, &format!("{} is up to date: {}", label, path.display()));
+                }
+            }
+            MessageFormat::Json => self.emit(&JsonMessage::File { kind, path, written }),
+        }
+    }
+
+    /// まとめを表示する（JSON形式では詳細度によらず出力する）
+    pub fn summary(&self, summary: &Summary) {
+        match self.format {
+            MessageFormat::Human => {
+                let mut message = format!("Finished: {} project(s)", summary.projects);
+                if summary.failed > 0 {
+                    message.push_str(&format!(" ({} failed)", summary.failed));
+                }
+                message.push_str(&format!(
+                    ", {} item(s) emitted, {} skipped, {} warning(s)",
+                    summary.emitted_items, summary.skipped_items, summary.warnings
+                ));
+                self.log(Verbosity::Normal, &message);
+            }
+            MessageFormat::Json => self.emit(&JsonMessage::Summary(summary)),
+        }
+    }
+
+    /// JSONを1行で書く
+    fn emit(&self, message: &JsonMessage) {
+        if let Ok(json) = serde_json::to_string(message) {
+            println!("{}", json);
+        }
+    }
+}
+
+/// 診断をrustcと同じ形式にする
+/// 
+/// 場所ごとに`-->`でファイルと行を示し、読めればその行のソースと、関数・定数の名前を添える。
+/// パスはカレントディレクトリからの相対パスにする。
+/// 
+/// # Arguments
+/// 
+/// * `diagnostic` - 診断
+/// 
+/// # Returns
+/// 
+/// * `String` - 表示する文字列
+pub fn render_diagnostic(diagnostic: &Diagnostic) -> String {
+    let mut out = format!("{}: {}", diagnostic.severity, diagnostic.message);
+    let width = diagnostic.locations.iter().map(|location| location.line.to_string().len()).max().unwrap_or(1);
+    for location in &diagnostic.locations {
+        let gutter = " ".repeat(width);
+        out.push_str(&format!("\n{}--> {}:{}", gutter, display_path(&location.file).display(), location.line));
+        let source = std::fs::read_to_string(&location.file).ok().and_then(|content| {
+            content.lines().nth(location.line.checked_sub(1)?).map(|line| line.trim_end().to_string())
+        });
+        if let Some(source) = source {
+            out.push_str(&format!("\n{} |\n{:>width$} | {}\n{} |", gutter, location.line, source, gutter, width = width));
+        }
+        out.push_str(&format!("\n{} = note: in `{}`", gutter, location.item));
+    }
+    out
+}
+
+/// カレントディレクトリの中のパスは相対パスにする
+fn display_path(path: &Path) -> PathBuf {
+    std::env::current_dir()
+        .ok()
+        .and_then(|dir| path.strip_prefix(dir).ok().map(Path::to_path_buf))
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verbosity_from_flags() {
+        assert_eq!(Verbosity::from_flags(true, 2), Verbosity::Quiet);
+        assert_eq!(Verbosity::from_flags(false, 0), Verbosity::Normal);
+        assert_eq!(Verbosity::from_flags(false, 1), Verbosity::Verbose);
+        assert_eq!(Verbosity::from_flags(false, 3), Verbosity::Debug);
+        assert!(Verbosity::Debug > Verbosity::Normal);
+    }
+
+    #[test]
+    fn test_render_diagnostic() {
+        let dir = std::env::temp_dir().join(format!("pystubgen-logging-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("lib.rs");
+        let source = "use pyo3::prelude::*;\n".repeat(9) + "fn make_foo() -> Foo {}   \n";
+        std::fs::write(&file, source).unwrap();
+
+        let diagnostic = Diagnostic::warning("unresolved type `Foo` was replaced with `Any`")
+            .with_location(&file, 10, "make_foo")
+            .with_location(dir.join("missing.rs"), 2, "take_foo");
+        assert_eq!(
+            render_diagnostic(&diagnostic),
+            format!(
+                "warning: unresolved type `Foo` was replaced with `Any`\n  --> {}:10\n   |\n10 | fn make_foo() -> Foo {{}}\n   |\n   = note: in `make_foo`\n  --> {}:2\n   = note: in `take_foo`",
+                file.display(),
+                dir.join("missing.rs").display()
+            )
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_json_message() {
+        let diagnostic = Diagnostic::warning("x").with_location("src/lib.rs", 3, "f");
+        let json = serde_json::to_string(&JsonMessage::Diagnostic { diagnostic: &diagnostic, rendered: "r".to_string() }).unwrap();
+        assert_eq!(
+            json,
+            r#"{"reason":"diagnostic","severity":"warning","message":"x","locations":[{"file":"src/lib.rs","line":3,"item":"f"}],"rendered":"r"}"#
+        );
+        let json = serde_json::to_string(&JsonMessage::File { kind: FileKind::InitStub, path: Path::new("a/__init__.pyi"), written: true }).unwrap();
+        assert_eq!(json, r#"{"reason":"file","kind":"init-stub","path":"a/__init__.pyi","written":true}"#);
+        let json = serde_json::to_string(&JsonMessage::Summary(&Summary { projects: 1, ..Default::default() })).unwrap();
+        assert_eq!(json, r#"{"reason":"summary","projects":1,"failed":0,"emitted_items":0,"skipped_items":0,"warnings":0}"#);
+    }
+}
@@ -0,0 +1,324 @@
+//! モジュールグラフ構築モジュール
+//! 
+//! クレートルート（`src/lib.rs`など）から`mod`宣言をたどり、クレートを構成するモジュールを集めます。
+//! `#[path = "..."]`によるファイル指定、インラインモジュール、`include!`にも対応します。
+//! `#[cfg]`で無効になっているモジュールやアイテムはこの段階で取り除きます。
+
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use crate::stubgen::cfg::CfgContext;
+
+/// クレートを構成する1つのモジュール（またはその一部）
+#[derive(Debug)]
+pub struct RustModule {
+    /// モジュールパス（`["crate", "foo", "bar"]`）
+    pub module_path: Vec<String>,
+    /// アイテムが書かれているファイル
+    pub file: PathBuf,
+    /// モジュール直下のアイテム
+    pub items: Vec<syn::Item>,
+}
+
+/// 別ファイルに書かれたモジュールと、そのファイルを読む位置の情報
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModuleFile {
+    /// モジュールパス（`include!`の場合は読み込んだ側と同じ）
+    pub module_path: Vec<String>,
+    /// モジュールのファイル
+    pub file: PathBuf,
+    /// 子モジュールのファイルを探すディレクトリ
+    pub dir: PathBuf,
+    /// `include!`のパスを解決するディレクトリ
+    pub file_dir: PathBuf,
+    /// インラインモジュールの中かどうか（`#[path]`の基準ディレクトリが変わる）
+    pub inline: bool,
+}
+
+impl ModuleFile {
+    /// クレートルートのファイルを表す
+    pub fn root(crate_root: &Path) -> ModuleFile {
+        let file_dir = parent_dir(crate_root);
+        ModuleFile {
+            module_path: vec!["crate".to_string()],
+            file: crate_root.to_path_buf(),
+            dir: file_dir.clone(),
+            file_dir,
+            inline: false,
+        }
+    }
+}
+
+/// 1つのファイルから見つかったモジュール
+#[derive(Debug)]
+pub enum ModuleEntry {
+    /// ファイル内に書かれたモジュール（インラインモジュールを含む）
+    Module(RustModule),
+    /// 別ファイルに書かれたモジュール（`mod foo;`や`include!`）
+    File(ModuleFile),
+}
+
+/// パース済みのクレートルートのアイテムから、すべてのモジュールを集める
+/// 
+/// マクロ展開後のソースのように、すべてのモジュールがインラインで書かれている場合にも使う。
+/// 
+/// # Arguments
+/// 
+/// * `crate_root` - クレートルートのファイル（`mod foo;`を探す基準になる）
+/// * `items` - クレートルートのアイテム
+/// * `cfg` - `#[cfg]`の評価に使うコンテキスト
+/// 
+/// # Returns
+/// 
+/// * `Result<Vec<RustModule>, Box<dyn Error>>` - 見つかったモジュール（宣言順）
+pub fn collect_crate_modules_from_items(
+    crate_root: &Path,
+    items: Vec<syn::Item>,
+    cfg: &CfgContext,
+) -> Result<Vec<RustModule>, Box<dyn Error>> {
+    let mut modules = vec![];
+    collect_entries(collect_file_modules(&ModuleFile::root(crate_root), items, cfg)?, cfg, &mut modules)?;
+    Ok(modules)
+}
+
+/// ファイル単位の収集結果を、別ファイルのモジュールを読み込みながら展開する
+fn collect_entries(entries: Vec<ModuleEntry>, cfg: &CfgContext, modules: &mut Vec<RustModule>) -> Result<(), Box<dyn Error>> {
+    for entry in entries {
+        match entry {
+            ModuleEntry::Module(module) => modules.push(module),
+            ModuleEntry::File(module_file) => {
+                let items = parse_file(&module_file.file)?;
+                collect_entries(collect_file_modules(&module_file, items, cfg)?, cfg, modules)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 1つのファイルのアイテムからモジュールを集める
+/// 
+/// 別ファイルのモジュールは読み込まず、`ModuleEntry::File`として宣言された位置に残す。
+/// 
+/// # Arguments
+/// 
+/// * `module_file` - アイテムが書かれているファイルの情報
+/// * `items` - ファイルのアイテム
+/// * `cfg` - `#[cfg]`の評価に使うコンテキスト
+/// 
+/// # Returns
+/// 
+/// * `Result<Vec<ModuleEntry>, Box<dyn Error>>` - 見つかったモジュール（宣言順）
+pub fn collect_file_modules(
+    module_file: &ModuleFile,
+    items: Vec<syn::Item>,
+    cfg: &CfgContext,
+) -> Result<Vec<ModuleEntry>, Box<dyn Error>> {
+    let mut entries = vec![];
+    collect_module(module_file.module_path.clone(), items, module_file, module_file.inline, &module_file.dir, cfg, &mut entries)?;
+    Ok(entries)
+}
+
+/// モジュールを登録し、その中の`mod`宣言と`include!`を集める
+fn collect_module(
+    module_path: Vec<String>,
+    items: Vec<syn::Item>,
+    module_file: &ModuleFile,
+    inline: bool,
+    dir: &Path,
+    cfg: &CfgContext,
+    entries: &mut Vec<ModuleEntry>,
+) -> Result<(), Box<dyn Error>> {
+    let mut items = items;
+    cfg.configure_items(&mut items);
+
+    let file = &module_file.file;
+    let index = entries.len();
+    entries.push(ModuleEntry::Module(RustModule { module_path: module_path.clone(), file: file.clone(), items: vec![] }));
+
+    let mut own_items = vec![];
+    for item in items {
+        match item {
+            syn::Item::Mod(item_mod) => {
+                let name = item_mod.ident.to_string();
+                let mut child_path = module_path.clone();
+                child_path.push(name.clone());
+                let path_attr = get_path_attribute(&item_mod.attrs);
+
+                match item_mod.content {
+                    // インラインモジュール（mod foo { ... }）
+                    Some((_, child_items)) => {
+                        let child_dir = match &path_attr {
+                            Some(path) => dir.join(path),
+                            None => dir.join(&name),
+                        };
+                        collect_module(child_path, child_items, module_file, true, &child_dir, cfg, entries)?;
+                    }
+                    // 別ファイルのモジュール（mod foo;）
+                    None => {
+                        let (child_file, child_dir) = match &path_attr {
+                            // #[path]で読み込んだファイルはmod.rsと同じ扱いになる
+                            Some(path) => {
+                                let base_dir = if inline { dir } else { module_file.file_dir.as_path() };
+                                let child_file = base_dir.join(path);
+                                let child_dir = parent_dir(&child_file);
+                                (child_file, child_dir)
+                            }
+                            None => find_module_file(dir, &name).ok_or_else(|| {
+                                format!(
+                                    "module `{}` declared in {} was not found in {}",
+                                    name,
+                                    file.display(),
+                                    dir.display()
+                                )
+                            })?,
+                        };
+                        let file_dir = parent_dir(&child_file);
+                        entries.push(ModuleEntry::File(ModuleFile {
+                            module_path: child_path,
+                            file: child_file,
+                            dir: child_dir,
+                            file_dir,
+                            inline: false,
+                        }));
+                    }
+                }
+            }
+            // include!("file.rs") は別ファイルに書かれた同じモジュールとして扱う
+            syn::Item::Macro(item_macro) if item_macro.mac.path.is_ident("include") => {
+                let Ok(include_path) = item_macro.mac.parse_body::<syn::LitStr>() else {
+                    // include!(concat!(env!("OUT_DIR"), ...)) などは解決できない
+                    continue;
+                };
+                entries.push(ModuleEntry::File(ModuleFile {
+                    module_path: module_path.clone(),
+                    file: module_file.file_dir.join(include_path.value()),
+                    dir: dir.to_path_buf(),
+                    file_dir: module_file.file_dir.clone(),
+                    inline,
+                }));
+            }
+            item => own_items.push(item),
+        }
+    }
+
+    if let ModuleEntry::Module(module) = &mut entries[index] {
+        module.items = own_items;
+    }
+    Ok(())
+}
+
+/// `mod foo;`に対応するファイルを探す（`foo.rs`または`foo/mod.rs`）
+/// 
+/// # Returns
+/// 
+/// * `Option<(PathBuf, PathBuf)>` - モジュールのファイルと、その子モジュールを探すディレクトリ
+fn find_module_file(dir: &Path, name: &str) -> Option<(PathBuf, PathBuf)> {
+    let file = dir.join(format!("{}.rs", name));
+    if file.is_file() {
+        return Some((file, dir.join(name)));
+    }
+    let mod_file = dir.join(name).join("mod.rs");
+    if mod_file.is_file() {
+        return Some((mod_file, dir.join(name)));
+    }
+    None
+}
+
+/// `#[path = "..."]`の値を取得する
+fn get_path_attribute(attrs: &[syn::Attribute]) -> Option<String> {
+    attrs.iter().find(|attr| attr.path().is_ident("path")).and_then(|attr| match &attr.meta {
+        syn::Meta::NameValue(name_value) => match &name_value.value {
+            syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(path), .. }) => Some(path.value()),
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+/// ファイルを読み込み、アイテムのリストにパースする
+fn parse_file(file: &Path) -> Result<Vec<syn::Item>, Box<dyn Error>> {
+    let file_content = read_file(file)?;
+    parse_source(file, &file_content)
+}
+
+/// ファイルの内容を読み込む
+pub fn read_file(file: &Path) -> Result<String, Box<dyn Error>> {
+    Ok(std::fs::read_to_string(file).map_err(|e| format!("failed to read {}: {}", file.display(), e))?)
+}
+
+/// 読み込んだファイルの内容をアイテムのリストにパースする
+pub fn parse_source(file: &Path, file_content: &str) -> Result<Vec<syn::Item>, Box<dyn Error>> {
+    let syn_file = syn::parse_file(file_content)
+        .map_err(|e| format!("failed to parse {}: {}", file.display(), e))?;
+    Ok(syn_file.items)
+}
+
+/// ファイルのあるディレクトリを取得する
+fn parent_dir(file: &Path) -> PathBuf {
+    file.parent().map(Path::to_path_buf).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    const TEST_PROJECT_ROOT_SINGLE: &str = "tests/test-project/single_project";
+
+    fn collect_crate_modules(crate_root: &Path, cfg: &CfgContext) -> Result<Vec<RustModule>, Box<dyn Error>> {
+        collect_crate_modules_from_items(crate_root, parse_file(crate_root)?, cfg)
+    }
+
+    #[test]
+    fn test_collect_crate_modules() {
+        let current_dir = env::current_dir().unwrap();
+        let src_dir = current_dir.join(TEST_PROJECT_ROOT_SINGLE).join("src");
+        let modules = collect_crate_modules(&src_dir.join("lib.rs"), &CfgContext::default()).unwrap();
+
+        let paths: Vec<String> = modules.iter().map(|m| m.module_path.join("::")).collect();
+        assert_eq!(paths, vec![
+            "crate",
+            "crate::test_libs",
+            "crate::test_libs::test_lib_code",
+            "crate::test_libs::test_lib_code_b",
+        ]);
+        assert_eq!(modules[2].file, src_dir.join("test_libs").join("test_lib_code.rs"));
+    }
+
+    #[test]
+    fn test_collect_crate_modules_with_path_and_inline() {
+        let dir = env::temp_dir().join(format!("pystubgen-modgraph-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("bindings")).unwrap();
+        std::fs::create_dir_all(dir.join("generated")).unwrap();
+        std::fs::write(dir.join("lib.rs"), r#"
+            #[path = "bindings/api.rs"]
+            mod api;
+            mod inline {
+                pub fn inner() {}
+            }
+            #[cfg(test)]
+            mod tests;
+            #[cfg(feature = "missing")]
+            mod missing;
+            include!("generated/extra.rs");
+        "#).unwrap();
+        std::fs::write(dir.join("bindings").join("api.rs"), "mod helper;").unwrap();
+        std::fs::write(dir.join("bindings").join("helper.rs"), "pub fn help() {}").unwrap();
+        std::fs::write(dir.join("generated").join("extra.rs"), "pub fn extra() {}").unwrap();
+
+        let modules = collect_crate_modules(&dir.join("lib.rs"), &CfgContext::default()).unwrap();
+        let found: Vec<(String, PathBuf)> = modules
+            .iter()
+            .map(|m| (m.module_path.join("::"), m.file.clone()))
+            .collect();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(found, vec![
+            ("crate".to_string(), dir.join("lib.rs")),
+            ("crate::api".to_string(), dir.join("bindings").join("api.rs")),
+            ("crate::api::helper".to_string(), dir.join("bindings").join("helper.rs")),
+            ("crate::inline".to_string(), dir.join("lib.rs")),
+            ("crate".to_string(), dir.join("generated").join("extra.rs")),
+        ]);
+    }
+}
@@ -0,0 +1,289 @@
+//! パッケージファイル生成モジュール
+//! 
+//! 拡張モジュールを含むPythonパッケージに`py.typed`マーカーを作成し、
+//! 拡張モジュールの公開名を再エクスポートする`__init__.pyi`を生成・検証します。
+//! 手で管理されている`__init__.pyi`（生成ヘッダーがないもの）は書き換えず、再エクスポート漏れを警告します。
+
+use std::path::Path;
+use crate::stubgen::analyzer::PythonSrcData;
+use crate::stubgen::diagnostic::Diagnostic;
+use crate::stubgen::generator::{FileKind, StubFile};
+use crate::stubgen::locator::{ProjectInfo, ProjectLayout};
+
+/// 生成した`__init__.pyi`の先頭に付けるヘッダー（これがないファイルは書き換えない）
+const GENERATED_HEADER: &str = "# Generated by cargo-pystubgen. Remove this line to maintain this file manually.";
+
+/// パッケージファイルの生成オプション
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PackageOptions {
+    /// `py.typed`マーカーを作成する
+    pub py_typed: bool,
+    /// `__init__.pyi`を生成・検証する
+    pub init_stub: bool,
+}
+
+/// パッケージファイルの処理結果
+#[derive(Debug, Default)]
+pub struct PackageReport {
+    /// 用意したファイル（既存の`py.typed`と手で管理されている`__init__.pyi`は今の内容のまま）
+    pub files: Vec<StubFile>,
+    /// 警告
+    pub warnings: Vec<Diagnostic>,
+}
+
+/// 拡張モジュールを含むパッケージの`py.typed`と`__init__.pyi`の内容を組み立てる（書き込みはしない）
+/// 
+/// # Arguments
+/// 
+/// * `project_info` - プロジェクト情報
+/// * `python_src_data` - 解析されたPython関数データ
+/// * `options` - パッケージファイルの生成オプション
+/// 
+/// # Returns
+/// 
+/// * `PackageReport` - 処理結果
+pub fn render_package_files(
+    project_info: &ProjectInfo,
+    python_src_data: &PythonSrcData,
+    options: PackageOptions,
+) -> PackageReport {
+    let mut report = PackageReport::default();
+    // トップレベルのモジュールはパッケージを持たない（py.typedはmaturinが扱う）
+    if project_info.layout == ProjectLayout::PureRust {
+        return report;
+    }
+    let package_dir = &project_info.output_dir;
+
+    if options.py_typed {
+        // 既存のマーカー（`partial`など）は書き換えない
+        let py_typed = package_dir.join("py.typed");
+        let content = std::fs::read_to_string(&py_typed).unwrap_or_default();
+        report.files.push(StubFile { path: py_typed, kind: FileKind::PyTyped, content });
+    }
+
+    if options.init_stub {
+        let init_stub = package_dir.join("__init__.pyi");
+        let names = public_names(python_src_data, &project_info.module_name);
+        let content = match std::fs::read_to_string(&init_stub) {
+            Ok(existing) if !existing.starts_with(GENERATED_HEADER) => {
+                report.warnings.extend(verify_init_stub(&existing, &init_stub, &project_info.module_name, &names));
+                existing
+            }
+            _ => {
+                let init_py = std::fs::read_to_string(package_dir.join("__init__.py")).unwrap_or_default();
+                render_init_stub(&project_info.module_name, &project_info.python_module, &names, &init_py)
+            }
+        };
+        report.files.push(StubFile { path: init_stub, kind: FileKind::InitStub, content });
+    }
+    report
+}
+
+/// 拡張モジュールの公開名を集める（スタブに出力される順、重複なし）
+fn public_names(python_src_data: &PythonSrcData, module_name: &str) -> Vec<String> {
+    let mut names: Vec<String> = vec![];
+    let constants = python_src_data.constants.iter().map(|constant| &constant.name);
+    let functions = python_src_data
+        .functions
+        .iter()
+        .map(|function| &function.name)
+        .filter(|name| *name != module_name);
+    for name in constants.chain(&python_src_data.classes).chain(functions) {
+        if !names.contains(name) {
+            names.push(name.clone());
+        }
+    }
+    names
+}
+
+/// `__init__.pyi`の内容を組み立てる
+/// 
+/// `__init__.py`にトップレベルで定義された関数・クラスとインポートも、本体を省略して引き継ぐ。
+/// 
+/// # Arguments
+/// 
+/// * `module_name` - 拡張モジュールの名前（`_core`）
+/// * `python_module` - 拡張モジュールの完全な名前（`my_pkg._core`）
+/// * `names` - 拡張モジュールの公開名
+/// * `init_py` - `__init__.py`の内容
+/// 
+/// # Returns
+/// 
+/// * `String` - `__init__.pyi`の内容
+fn render_init_stub(module_name: &str, python_module: &str, names: &[String], init_py: &str) -> String {
+    let mut out = format!("{}\nfrom .{} import *\n", GENERATED_HEADER, module_name);
+    // `_`で始まる名前は`import *`では取り込まれない
+    for name in names.iter().filter(|name| name.starts_with('_')) {
+        out.push_str(&format!("from .{} import {} as {}\n", module_name, name, name));
+    }
+
+    let (definitions, defined_names) = python_definitions(init_py, module_name, python_module);
+    for definition in &definitions {
+        out.push('\n');
+        out.push_str(definition);
+        out.push('\n');
+    }
+
+    let mut all: Vec<&String> = vec![];
+    for name in names.iter().chain(&defined_names) {
+        if !all.contains(&name) {
+            all.push(name);
+        }
+    }
+    out.push_str("\n__all__ = [\n");
+    for name in all {
+        out.push_str(&format!("    \"{}\",\n", name));
+    }
+    out.push_str("]\n");
+    out
+}
+
+/// `__init__.py`のトップレベルのインポート・関数・クラスを、スタブとして使える形で取り出す
+/// 
+/// # Returns
+/// 
+/// * `(Vec<String>, Vec<String>)` - スタブに書く定義と、定義された公開名
+fn python_definitions(init_py: &str, module_name: &str, python_module: &str) -> (Vec<String>, Vec<String>) {
+    let mut definitions = vec![];
+    let mut names = vec![];
+    let lines: Vec<&str> = init_py.lines().collect();
+    let mut index = 0;
+    while index < lines.len() {
+        let line = lines[index];
+        let statement_start = index;
+        index += 1;
+        let is_definition = ["def ", "async def ", "class "].iter().any(|prefix| line.starts_with(prefix));
+        let is_import = line.starts_with("import ") || line.starts_with("from ");
+        if !is_definition && !is_import {
+            continue;
+        }
+
+        // 複数行にわたる文（括弧が閉じるまで、定義は`:`まで）を読む
+        let mut depth = 0i32;
+        let mut end = statement_start;
+        loop {
+            let current = lines[end];
+            depth += current.matches(['(', '[']).count() as i32 - current.matches([')', ']']).count() as i32;
+            let finished = depth <= 0 && (!is_definition || current.trim_end().ends_with(':'));
+            if finished || end + 1 >= lines.len() {
+                break;
+            }
+            end += 1;
+        }
+        index = end + 1;
+        let statement = lines[statement_start..=end].join("\n");
+
+        if is_import {
+            let from_extension = [format!("from .{} ", module_name), format!("from {} ", python_module)]
+                .iter()
+                .any(|prefix| statement.starts_with(prefix.as_str()));
+            if !from_extension {
+                definitions.push(statement);
+            }
+            continue;
+        }
+
+        let name: String = line
+            .trim_start_matches("async ")
+            .trim_start_matches("def ")
+            .trim_start_matches("class ")
+            .chars()
+            .take_while(|c| c.is_alphanumeric() || *c == '_')
+            .collect();
+        if !name.starts_with('_') {
+            names.push(name);
+        }
+        definitions.push(format!("{}\n    ...", statement));
+    }
+    (definitions, names)
+}
+
+/// 手で管理されている`__init__.pyi`が拡張モジュールの公開名を再エクスポートしているか確認する
+/// 
+/// # Returns
+/// 
+/// * `Vec<Diagnostic>` - 再エクスポート漏れの警告
+fn verify_init_stub(content: &str, path: &Path, module_name: &str, names: &[String]) -> Vec<Diagnostic> {
+    let star_import = content.lines().any(|line| {
+        let line = line.trim();
+        line.starts_with("from ") && line.contains(&format!("{} import *", module_name))
+    });
+    let words: Vec<&str> = content
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|word| !word.is_empty())
+        .collect();
+
+    names
+        .iter()
+        // `import *`は`_`で始まらない名前だけを取り込む
+        .filter(|name| (!star_import || name.starts_with('_')) && !words.contains(&name.as_str()))
+        .map(|name| Diagnostic::warning(format!(
+            "{} does not re-export `{}` from `{}`",
+            path.display(),
+            name,
+            module_name
+        )))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stubgen::analyzer::{PythonConstantData, PythonFunctionData};
+
+    fn python_src_data() -> PythonSrcData {
+        PythonSrcData {
+            functions: vec![
+                PythonFunctionData {
+                    name: "hello_from_bin".to_string(),
+                    args: vec![],
+                    defaults: vec![],
+                    return_type: "str".to_string(),
+                    doc: String::new(),
+                },
+                PythonFunctionData {
+                    name: "_core".to_string(),
+                    args: vec![],
+                    defaults: vec![],
+                    return_type: "None".to_string(),
+                    doc: String::new(),
+                },
+            ],
+            constants: vec![PythonConstantData {
+                name: "__version__".to_string(),
+                ty: "str".to_string(),
+                is_final: false,
+                doc: String::new(),
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_render_init_stub() {
+        let init_py = "from single_project._core import hello_from_bin\nimport os\n\n\ndef main(\n    verbose: bool = False,\n) -> None:\n    print(hello_from_bin())\n";
+        let names = public_names(&python_src_data(), "_core");
+        assert_eq!(
+            render_init_stub("_core", "single_project._core", &names, init_py),
+            format!(
+                "{}\nfrom ._core import *\nfrom ._core import __version__ as __version__\n\
+                 \nimport os\n\
+                 \ndef main(\n    verbose: bool = False,\n) -> None:\n    ...\n\
+                 \n__all__ = [\n    \"__version__\",\n    \"hello_from_bin\",\n    \"main\",\n]\n",
+                GENERATED_HEADER
+            )
+        );
+    }
+
+    #[test]
+    fn test_verify_init_stub() {
+        let names = public_names(&python_src_data(), "_core");
+        let path = Path::new("__init__.pyi");
+        assert!(verify_init_stub("from ._core import *\nfrom ._core import __version__\n", path, "_core", &names).is_empty());
+        assert_eq!(
+            verify_init_stub("from ._core import *\n", path, "_core", &names),
+            vec![Diagnostic::warning("__init__.pyi does not re-export `__version__` from `_core`")]
+        );
+        assert_eq!(verify_init_stub("", path, "_core", &names).len(), 2);
+    }
+}
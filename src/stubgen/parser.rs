@@ -1,24 +1,99 @@
 use quote::ToTokens;
+use serde::{Deserialize, Serialize};
 use syn::Meta;
+use syn::ext::IdentExt;
+use syn::spanned::Spanned;
 use std::path::PathBuf;
+use crate::stubgen::modgraph::RustModule;
 
-#[derive(Debug,Default,PartialEq, Eq)]
+#[derive(Debug,Default,Clone,PartialEq, Eq, Serialize, Deserialize)]
 pub struct RustFunctionData{
     pub name: String,
+    /// 引数名（Pythonの引数名に直したもの）と型のペア
     pub args: Vec<(String, String)>,
+    /// 名前を持たないため引数名を生成した引数のパターン（`args`と同じ順。名前があれば`None`）
+    pub arg_patterns: Vec<Option<String>>,
+    /// 引数が書かれている行（`args`と同じ順）
+    pub arg_lines: Vec<usize>,
     pub return_type: String,
     pub attributes: Vec<String>,
     pub doc: String,
+    /// 定義されている行（1始まり）
+    pub line: usize,
+    /// `#[pyo3(signature = (...))]`のトークン列（括弧を含む）
+    pub signature: Option<String>,
+    /// `#[pyo3(text_signature = "...")]`の文字列
+    pub text_signature: Option<String>,
 }
 
+/// モジュールに追加される定数・属性の情報
+#[derive(Debug,Default,Clone,PartialEq, Eq, Serialize, Deserialize)]
+pub struct RustConstantData{
+    /// Python側で公開される名前
+    pub name: String,
+    /// 明示された型（`const X: u32` の場合のみ）
+    pub ty: Option<String>,
+    /// 値の式
+    pub value: String,
+    /// `#[pymodule_export] const` のように再代入されない定数かどうか
+    pub is_final: bool,
+    pub doc: String,
+    /// 定義されている行（1始まり）
+    pub line: usize,
+}
+
+/// 型定義の種類
+#[derive(Debug,Clone,PartialEq, Eq, Serialize, Deserialize)]
+pub enum RustTypeKind{
+    /// `#[pyclass]`が付いた構造体・列挙型（Python側の名前とドキュメントコメントを持つ）
+    PyClass{ python_name: String, doc: String },
+    /// 型エイリアス（`type Matrix = Vec<Vec<f64>>`）
+    Alias{ generics: Vec<String>, target: String },
+    /// Pythonに公開されていない構造体・列挙型
+    Other,
+}
+
+/// ソースファイル内で定義された型の情報
+#[derive(Debug,Clone,PartialEq, Eq, Serialize, Deserialize)]
+pub struct RustTypeData{
+    pub name: String,
+    pub kind: RustTypeKind,
+    /// 定義されている行（1始まり）
+    pub line: usize,
+}
+
+/// `use`宣言で導入された名前
+#[derive(Debug,Clone,PartialEq, Eq, Serialize, Deserialize)]
+pub struct RustImport{
+    /// モジュール内で使われる名前（グロブインポートの場合は`*`）
+    pub name: String,
+    /// 書かれたままのパス（`crate`・`self`・`super`で始まることもある）
+    pub path: Vec<String>,
+}
+
+#[derive(Debug,Default,Clone,PartialEq, Eq, Serialize, Deserialize)]
 pub struct RustSrcData{
+    /// 解析したソースファイルのパス
+    pub file: PathBuf,
+    /// モジュールパス（`["crate", "foo"]`）
+    pub module_path: Vec<String>,
     pub functions: Vec<RustFunctionData>,
+    pub constants: Vec<RustConstantData>,
+    pub types: Vec<RustTypeData>,
+    pub imports: Vec<RustImport>,
 }
 
-pub fn parse_rust_src_file(rust_src_file: &PathBuf) -> RustSrcData{
-    let file_content = std::fs::read_to_string(rust_src_file).unwrap();
-    let syn_file = syn::parse_str::<syn::File>(&file_content).unwrap();
-    let functions = syn_file.items.iter().filter_map(|item|{
+/// モジュールグラフの1モジュールを解析する
+/// 
+/// # Arguments
+/// 
+/// * `module` - 解析するモジュール
+/// 
+/// # Returns
+/// 
+/// * `RustSrcData` - モジュール内の関数・定数・型定義の情報
+pub fn parse_rust_module(module: &RustModule) -> RustSrcData{
+    let functions = module.items.iter().filter_map(|item|{
         if let syn::Item::Fn(item_fn) = item{
             Some(parse_function_data(item_fn))
         }
@@ -26,18 +101,192 @@ pub fn parse_rust_src_file(rust_src_file: &PathBuf) -> RustSrcData{
             None
         }
     }).collect();
+    let mut constants = vec![];
+    parse_module_constants(&module.items, &mut constants);
+    let types = module.items.iter().filter_map(parse_type_data).collect();
+    let mut imports = vec![];
+    for item in &module.items{
+        if let syn::Item::Use(item_use) = item{
+            parse_use_tree(&item_use.tree, vec![], &mut imports);
+        }
+    }
     RustSrcData{
-        functions: functions,
+        file: module.file.clone(),
+        module_path: module.module_path.clone(),
+        functions,
+        constants,
+        types,
+        imports,
+    }
+}
+
+/// `use`宣言のツリーを展開して、導入される名前とパスの組にする
+pub fn parse_use_tree(tree: &syn::UseTree, prefix: Vec<String>, imports: &mut Vec<RustImport>){
+    match tree{
+        syn::UseTree::Path(use_path) => {
+            let mut prefix = prefix;
+            prefix.push(use_path.ident.to_string());
+            parse_use_tree(&use_path.tree, prefix, imports);
+        }
+        // use foo::{self} はモジュール自身を導入する
+        syn::UseTree::Name(use_name) if use_name.ident == "self" => {
+            if let Some(name) = prefix.last(){
+                imports.push(RustImport{ name: name.clone(), path: prefix });
+            }
+        }
+        syn::UseTree::Name(use_name) => {
+            let mut path = prefix;
+            path.push(use_name.ident.to_string());
+            imports.push(RustImport{ name: use_name.ident.to_string(), path });
+        }
+        syn::UseTree::Rename(use_rename) => {
+            let mut path = prefix;
+            path.push(use_rename.ident.to_string());
+            imports.push(RustImport{ name: use_rename.rename.to_string(), path });
+        }
+        syn::UseTree::Glob(_) => imports.push(RustImport{ name: "*".to_string(), path: prefix }),
+        syn::UseTree::Group(group) => {
+            for tree in &group.items{
+                parse_use_tree(tree, prefix.clone(), imports);
+            }
+        }
+    }
+}
+
+/// 構造体・列挙型・型エイリアスの定義を解析する
+/// 
+/// # Arguments
+/// 
+/// * `item` - 解析するアイテム
+/// 
+/// # Returns
+/// 
+/// 型定義であれば`Some(RustTypeData)`
+pub fn parse_type_data(item: &syn::Item) -> Option<RustTypeData>{
+    let (ident, attrs) = match item{
+        syn::Item::Struct(item_struct) => (&item_struct.ident, &item_struct.attrs),
+        syn::Item::Enum(item_enum) => (&item_enum.ident, &item_enum.attrs),
+        syn::Item::Type(item_type) => {
+            let generics = item_type.generics.type_params()
+                .map(|param| param.ident.to_string())
+                .collect();
+            return Some(RustTypeData{
+                name: item_type.ident.to_string(),
+                kind: RustTypeKind::Alias{ generics, target: item_type.ty.to_token_stream().to_string() },
+                line: item_type.ident.span().start().line,
+            });
+        }
+        _ => return None,
+    };
+    let kind = match attrs.iter().find(|attr| is_attribute(attr, &["pyclass"])){
+        Some(attr) => RustTypeKind::PyClass{
+            python_name: parse_pyclass_name(attr).unwrap_or_else(|| ident.to_string()),
+            doc: parse_doc(attrs),
+        },
+        None => RustTypeKind::Other,
+    };
+    Some(RustTypeData{ name: ident.to_string(), kind, line: ident.span().start().line })
+}
+
+/// `#[pyclass(name = "Foo")]`からPython側の名前を取得する
+fn parse_pyclass_name(attr: &syn::Attribute) -> Option<String>{
+    let Meta::List(list) = &attr.meta else { return None };
+    let metas = list.parse_args_with(
+        syn::punctuated::Punctuated::<Meta, syn::Token![,]>::parse_terminated
+    ).ok()?;
+    metas.iter().find_map(|meta| match meta{
+        Meta::NameValue(name_value) if name_value.path.is_ident("name") => match &name_value.value{
+            syn::Expr::Lit(syn::ExprLit{ lit: syn::Lit::Str(name), .. }) => Some(name.value()),
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+/// `#[pymodule]`の関数本体の`m.add(...)`と`#[pymodule_export] const`を収集する
+/// 
+/// 宣言的モジュール（`#[pymodule] mod foo { ... }`）の中身は、
+/// モジュールグラフで別のモジュールとして渡される。
+/// 
+/// # Arguments
+/// 
+/// * `items` - 探索するアイテムのリスト
+/// * `constants` - 見つかった定数を格納するベクター
+pub fn parse_module_constants(items: &[syn::Item], constants: &mut Vec<RustConstantData>){
+    for item in items{
+        match item{
+            syn::Item::Fn(item_fn) if has_attribute(&item_fn.attrs, &["pymodule", "pymodule_init"]) => {
+                parse_module_add_calls(&item_fn.block.stmts, constants);
+            }
+            syn::Item::Const(item_const) if has_attribute(&item_const.attrs, &["pymodule_export"]) => {
+                constants.push(RustConstantData{
+                    name: item_const.ident.to_string(),
+                    ty: Some(item_const.ty.to_token_stream().to_string()),
+                    value: item_const.expr.to_token_stream().to_string(),
+                    is_final: true,
+                    doc: parse_doc(&item_const.attrs),
+                    line: item_const.ident.span().start().line,
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+/// 関数本体から`m.add("name", value)`の呼び出しを探す
+fn parse_module_add_calls(stmts: &[syn::Stmt], constants: &mut Vec<RustConstantData>){
+    for stmt in stmts{
+        let syn::Stmt::Expr(expr, _) = stmt else { continue };
+        let Some(call) = find_method_call(expr, "add") else { continue };
+        if call.args.len() != 2{
+            continue;
+        }
+        if let syn::Expr::Lit(syn::ExprLit{ lit: syn::Lit::Str(name), .. }) = &call.args[0]{
+            constants.push(RustConstantData{
+                name: name.value(),
+                ty: None,
+                value: call.args[1].to_token_stream().to_string(),
+                is_final: false,
+                doc: String::new(),
+                line: name.span().start().line,
+            });
+        }
     }
 }
 
+/// `?`や括弧を取り除きながら、指定した名前のメソッド呼び出しを探す
+fn find_method_call<'a>(expr: &'a syn::Expr, method: &str) -> Option<&'a syn::ExprMethodCall>{
+    match expr{
+        syn::Expr::MethodCall(call) if call.method == method => Some(call),
+        syn::Expr::MethodCall(call) => find_method_call(&call.receiver, method),
+        syn::Expr::Try(expr_try) => find_method_call(&expr_try.expr, method),
+        syn::Expr::Paren(paren) => find_method_call(&paren.expr, method),
+        _ => None,
+    }
+}
+
+/// アトリビュートのリストに指定した名前（パスの末尾）のものが含まれているかを確認する
+pub fn has_attribute(attrs: &[syn::Attribute], names: &[&str]) -> bool{
+    attrs.iter().any(|attr| is_attribute(attr, names))
+}
+
+/// アトリビュートが指定した名前（パスの末尾）のいずれかであるかを確認する
+pub fn is_attribute(attr: &syn::Attribute, names: &[&str]) -> bool{
+    attr.path().segments.last().is_some_and(|seg| names.iter().any(|name| seg.ident == name))
+}
+
 pub fn parse_function_data(item: &syn::ItemFn) -> RustFunctionData{
     RustFunctionData{
         name: parse_function_name(item),
         args: parse_function_args(item),
+        arg_patterns: parse_function_arg_patterns(item),
+        arg_lines: parse_function_arg_lines(item),
         return_type: parse_function_return_type(item),
         attributes: parse_function_attributes(item),
         doc: parse_function_doc(item),
+        line: item.sig.ident.span().start().line,
+        signature: parse_pyo3_option(&item.attrs, "signature"),
+        text_signature: parse_pyo3_option(&item.attrs, "text_signature"),
     }
 }
 
@@ -45,17 +294,90 @@ pub fn parse_function_name(item: &syn::ItemFn) -> String{
     item.sig.ident.to_string()
 }
 
+/// 引数名と型のペアを取得する
+/// 
+/// PyO3と同じく`mut`・`ref`は取り除き、`r#type`は`type`とする。
+/// `_`や分割代入のように名前を持たない引数は、Pythonから見える引数（`self`と`Python<'py>`を除く）の位置から
+/// `arg0`・`arg1`…と名付ける。
 pub fn parse_function_args(item: &syn::ItemFn) -> Vec<(String, String)>{
+    let mut index = 0;
     item.sig.inputs.iter().map(|arg|match arg{
-        syn::FnArg::Typed(arg) => (arg.pat.to_token_stream().to_string(), arg.ty.to_token_stream().to_string()),
+        syn::FnArg::Typed(arg) if is_python_token(&arg.ty) => {
+            (pattern_name(&arg.pat).unwrap_or_else(|| "py".to_string()), arg.ty.to_token_stream().to_string())
+        }
+        syn::FnArg::Typed(arg) => {
+            let name = pattern_name(&arg.pat).unwrap_or_else(|| format!("arg{}", index));
+            index += 1;
+            (name, arg.ty.to_token_stream().to_string())
+        }
         _ => ("self".to_string(), "self".to_string()),
     })
     .collect()
 }
 
+/// `Python<'py>`型（PyO3が渡すトークンで、Python側の引数には現れない）かどうかを判定する
+pub fn is_python_token(ty: &syn::Type) -> bool {
+    matches!(ty, syn::Type::Path(type_path)
+        if type_path.path.segments.last().is_some_and(|seg| seg.ident == "Python"))
+}
+
+/// 名前を持たない引数のパターンを取得する
+pub fn parse_function_arg_patterns(item: &syn::ItemFn) -> Vec<Option<String>>{
+    item.sig.inputs.iter().map(|arg| match arg{
+        syn::FnArg::Typed(arg) if pattern_name(&arg.pat).is_none() => Some(arg.pat.to_token_stream().to_string()),
+        _ => None,
+    })
+    .collect()
+}
+
+/// 引数のパターンからPythonの引数名を取得する（`mut x`・`ref x`・`x @ ..`は`x`。名前を持たなければ`None`）
+fn pattern_name(pat: &syn::Pat) -> Option<String>{
+    match pat {
+        syn::Pat::Ident(pat_ident) => Some(python_arg_name(&pat_ident.ident.unraw().to_string())),
+        syn::Pat::Paren(paren) => pattern_name(&paren.pat),
+        _ => None,
+    }
+}
+
+/// Pythonのキーワード（引数名にできない）
+const PYTHON_KEYWORDS: &[&str] = &[
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue",
+    "def", "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import", "in",
+    "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while", "with", "yield",
+];
+
+/// Rustの引数名を、PyO3が使うPythonの引数名にする
+/// 
+/// PyO3と同じく`r#`を取り除く（`r#from` → `from`）。Pythonのキーワードはスタブに書けないため、解析時に扱う。
+/// 
+/// # Arguments
+/// 
+/// * `name` - Rustの引数名（`r#type`など）
+/// 
+/// # Returns
+/// 
+/// * `String` - Pythonの引数名
+pub fn python_arg_name(name: &str) -> String{
+    name.strip_prefix("r#").unwrap_or(name).to_string()
+}
+
+/// Pythonのキーワード（`from`・`lambda`など、引数名にできない名前）かどうか
+pub fn is_python_keyword(name: &str) -> bool{
+    PYTHON_KEYWORDS.contains(&name)
+}
+
+/// 引数が書かれている行を取得する
+pub fn parse_function_arg_lines(item: &syn::ItemFn) -> Vec<usize>{
+    item.sig.inputs.iter().map(|arg| match arg{
+        syn::FnArg::Typed(arg) => arg.pat.span().start().line,
+        syn::FnArg::Receiver(receiver) => receiver.self_token.span.start().line,
+    })
+    .collect()
+}
+
 pub fn parse_function_return_type(item: &syn::ItemFn) -> String{
     match &item.sig.output {
-        syn::ReturnType::Default => "None".to_string(),
+        syn::ReturnType::Default => "()".to_string(),
         syn::ReturnType::Type(_, ty) => ty.to_token_stream().to_string(),
     }
 }
@@ -66,25 +388,66 @@ pub fn parse_function_attributes(item: &syn::ItemFn) -> Vec<String> {
             Meta::Path(path) if !attr.path().is_ident("doc") => {
                 Some(path.segments.last().unwrap().ident.to_string())
             }
+            // `#[pyfunction(signature = ...)]`のように引数を取る場合
+            Meta::List(list) if is_attribute(attr, &["pyfunction"]) => {
+                Some(list.path.segments.last().unwrap().ident.to_string())
+            }
             _ => None,
         })
         .collect()
 }
 
+/// `#[pyo3(...)]`・`#[pyfunction(...)]`のオプションの値を取得する
+/// 
+/// # Arguments
+/// 
+/// * `attrs` - アトリビュートのリスト
+/// * `key` - オプション名（`signature`は括弧を含むトークン列、`text_signature`は文字列の中身を返す）
+/// 
+/// # Returns
+/// 
+/// * `Option<String>` - オプションの値（書かれていなければ`None`）
+pub fn parse_pyo3_option(attrs: &[syn::Attribute], key: &str) -> Option<String> {
+    attrs.iter()
+        .filter(|attr| is_attribute(attr, &["pyo3", "pyfunction"]))
+        .filter_map(|attr| attr.meta.require_list().ok())
+        .find_map(|list| {
+            let tokens: Vec<proc_macro2::TokenTree> = list.tokens.clone().into_iter().collect();
+            tokens.windows(3).find_map(|window| match window {
+                [proc_macro2::TokenTree::Ident(ident), proc_macro2::TokenTree::Punct(punct), value]
+                    if ident == key && punct.as_char() == '=' =>
+                {
+                    match value {
+                        proc_macro2::TokenTree::Group(group) => Some(group.to_string()),
+                        proc_macro2::TokenTree::Literal(literal) => {
+                            syn::parse_str::<syn::LitStr>(&literal.to_string()).ok().map(|lit| lit.value())
+                        }
+                        _ => None,
+                    }
+                }
+                _ => None,
+            })
+        })
+}
+
 
 pub fn parse_function_doc(item: &syn::ItemFn) -> String{
+    parse_doc(&item.attrs)
+}
+
+/// ドキュメントコメント（`#[doc = "..."]`）を連結して取得する
+pub fn parse_doc(attrs: &[syn::Attribute]) -> String{
     let mut doc = String::new();
-    item.attrs.iter().for_each(|attr|{
+    attrs.iter().for_each(|attr|{
         match &attr.meta {
-            Meta::NameValue(name_value) =>match &name_value.value {
-                syn::Expr::Lit(lit_str) => {
+            Meta::NameValue(name_value) =>{
+                if let syn::Expr::Lit(lit_str) = &name_value.value {
                     let doc_str = match &lit_str.lit {
                         syn::Lit::Str(lit_str) => lit_str.value(),
                         _ => "".to_string(),
                     };
                     doc.push_str(&doc_str);
                 }
-                _ => {}
             }
             Meta::Path(_) => {},
             Meta::List(_) => {},
@@ -131,6 +494,28 @@ mod tests {
         let function_data = parse_function_data(&item);
 
         assert_eq!(function_data.args, vec![("a".to_string(), "i32".to_string()), ("b".to_string(), "i32".to_string())]);
+        assert_eq!(function_data.arg_patterns, vec![None, None]);
+    }
+
+    #[test]
+    fn test_parse_function_arg_patterns(){
+        use super::*;
+        let item: syn::ItemFn = syn::parse_str(
+            "fn f(mut count: i32, ref label: String, _unused: i32, _: i32, (x, y): (f64, f64), r#type: &str, from: u8, lambda: u8) {}"
+        ).unwrap();
+        let function_data = parse_function_data(&item);
+
+        let names: Vec<&str> = function_data.args.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["count", "label", "_unused", "arg3", "arg4", "type", "from", "lambda"]);
+        assert_eq!(function_data.arg_patterns, vec![
+            None, None, None, Some("_".to_string()), Some("(x , y)".to_string()), None, None, None,
+        ]);
+
+        // Python<'py>は位置に数えない
+        let item: syn::ItemFn = syn::parse_str("fn f(_: Python<'_>, _: i32, (a, b): (i32, i32)) {}").unwrap();
+        let function_data = parse_function_data(&item);
+        let names: Vec<&str> = function_data.args.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["py", "arg0", "arg1"]);
     }
 
     #[test]
@@ -142,6 +527,21 @@ mod tests {
         assert_eq!(function_data.attributes, vec!["pyfunction".to_string(), "testattribute".to_string()]);
     }
 
+    #[test]
+    fn test_parse_pyo3_options(){
+        use super::*;
+        let item: syn::ItemFn = syn::parse_str(r#"
+            #[pyfunction(signature = (a, b = 1, *args))]
+            #[pyo3(name = "join", text_signature = "(a, b=1, *args)")]
+            fn join_all(a: i32, b: i32, args: Vec<String>) -> String { todo!() }
+        "#).unwrap();
+        let function_data = parse_function_data(&item);
+
+        assert_eq!(function_data.attributes, vec!["pyfunction".to_string()]);
+        assert_eq!(function_data.signature.as_deref(), Some("(a , b = 1 , * args)"));
+        assert_eq!(function_data.text_signature.as_deref(), Some("(a, b=1, *args)"));
+    }
+
     #[test]
     fn test_parse_function_comments(){
         use super::*;
@@ -151,5 +551,89 @@ mod tests {
         assert_eq!(function_data.doc, " testcode add function second line");
     }
 
-   
+    #[test]
+    fn test_parse_module_constants(){
+        use super::*;
+        let file: syn::File = syn::parse_str(
+        r#"
+        #[pymodule]
+        fn _core(m: &Bound<'_, PyModule>) -> PyResult<()> {
+            m.add_function(wrap_pyfunction!(add, m)?)?;
+            m.add("__version__", env!("CARGO_PKG_VERSION"))?;
+            m.add("MAX_SIZE", 1024)?;
+            Ok(())
+        }
+
+        #[pymodule]
+        mod declarative {
+            /// 係数
+            #[pymodule_export]
+            const FACTOR: u32 = 3;
+        }
+        "#).unwrap();
+        let mut constants = vec![];
+        parse_module_constants(&file.items, &mut constants);
+        if let syn::Item::Mod(syn::ItemMod{ content: Some((_, items)), .. }) = &file.items[1]{
+            parse_module_constants(items, &mut constants);
+        }
+
+        assert_eq!(constants.len(), 3);
+        assert_eq!(constants[0].name, "__version__");
+        assert_eq!(constants[0].value, "env ! (\"CARGO_PKG_VERSION\")");
+        assert!(!constants[0].is_final);
+        assert_eq!(constants[1].name, "MAX_SIZE");
+        assert_eq!(constants[2], RustConstantData{
+            name: "FACTOR".to_string(),
+            ty: Some("u32".to_string()),
+            value: "3".to_string(),
+            is_final: true,
+            doc: " 係数".to_string(),
+            line: 14,
+        });
+    }
+
+    #[test]
+    fn test_parse_use_tree(){
+        use super::*;
+        let item: syn::ItemUse = syn::parse_str("use crate::types::{Point, inner::{self, Matrix as M}, prelude::*};").unwrap();
+        let mut imports = vec![];
+        parse_use_tree(&item.tree, vec![], &mut imports);
+
+        let path = |segments: &[&str]| segments.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        assert_eq!(imports, vec![
+            RustImport{ name: "Point".to_string(), path: path(&["crate", "types", "Point"]) },
+            RustImport{ name: "inner".to_string(), path: path(&["crate", "types", "inner"]) },
+            RustImport{ name: "M".to_string(), path: path(&["crate", "types", "inner", "Matrix"]) },
+            RustImport{ name: "*".to_string(), path: path(&["crate", "types", "prelude"]) },
+        ]);
+    }
+
+    #[test]
+    fn test_parse_type_data(){
+        use super::*;
+        let file: syn::File = syn::parse_str(
+        r#"
+        #[pyclass(name = "Point", frozen)]
+        struct RustPoint { x: f64 }
+
+        /// 色
+        #[pyclass]
+        enum Color { Red }
+
+        struct Internal;
+
+        type Pair<T> = (T, T);
+        "#).unwrap();
+        let types: Vec<RustTypeData> = file.items.iter().filter_map(parse_type_data).collect();
+
+        assert_eq!(types, vec![
+            RustTypeData{ name: "RustPoint".to_string(), kind: RustTypeKind::PyClass{ python_name: "Point".to_string(), doc: String::new() }, line: 3 },
+            RustTypeData{ name: "Color".to_string(), kind: RustTypeKind::PyClass{ python_name: "Color".to_string(), doc: " 色".to_string() }, line: 7 },
+            RustTypeData{ name: "Internal".to_string(), kind: RustTypeKind::Other, line: 9 },
+            RustTypeData{ name: "Pair".to_string(), kind: RustTypeKind::Alias{
+                generics: vec!["T".to_string()],
+                target: "(T , T)".to_string(),
+            }, line: 11 },
+        ]);
+    }
 }
@@ -0,0 +1,216 @@
+//! シグネチャ解析モジュール
+//! 
+//! `#[pyo3(signature = (...))]`と`#[pyo3(text_signature = "...")]`を、引数名・デフォルト値の
+//! 共通の形式に変換します。PyO3はこのどちらかから`__text_signature__`を作るため、
+//! 両方が書かれている場合は食い違いを検出できます。
+
+use std::fmt;
+use crate::stubgen::parser::python_arg_name;
+
+/// シグネチャの引数
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignatureParam {
+    /// 引数名（`*args`・`**kwargs`は`*`を含む。区切りの`*`・`/`はそのまま）
+    pub name: String,
+    /// Pythonの式に変換したデフォルト値（単純なリテラル以外は`...`）
+    pub default: Option<String>,
+}
+
+impl SignatureParam {
+    /// 区切り（`*`・`/`）かどうか
+    pub fn is_marker(&self) -> bool {
+        self.name == "*" || self.name == "/"
+    }
+}
+
+impl fmt::Display for SignatureParam {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.default {
+            Some(default) => write!(f, "{}={}", self.name, default),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+/// 引数のリストを`(a, b=1, *, c)`の形式にする
+pub fn format_params(params: &[SignatureParam]) -> String {
+    let params: Vec<String> = params.iter().map(ToString::to_string).collect();
+    format!("({})", params.join(", "))
+}
+
+/// 2つのシグネチャが同じ引数を受け取るかどうか（デフォルト値は有無だけを比べる）
+pub fn same_params(a: &[SignatureParam], b: &[SignatureParam]) -> bool {
+    a.len() == b.len()
+        && a.iter().zip(b).all(|(a, b)| a.name == b.name && a.default.is_some() == b.default.is_some())
+}
+
+/// `#[pyo3(signature = (...))]`の括弧の中身を解析する
+/// 
+/// # Arguments
+/// 
+/// * `tokens` - 括弧を含むトークン列の文字列（`(a , b = 1 , * args)`など）
+/// 
+/// # Returns
+/// 
+/// * `Vec<SignatureParam>` - 引数（デフォルト値はRustの式からPythonの式に変換する）
+pub fn parse_rust_signature(tokens: &str) -> Vec<SignatureParam> {
+    let mut params = parse_params(tokens, rust_default);
+    // 引数名は関数の引数と同じく`r#`を取り除く
+    for param in params.iter_mut().filter(|param| !param.is_marker()) {
+        let name = param.name.trim_start_matches('*');
+        let stars = &param.name[..param.name.len() - name.len()];
+        param.name = format!("{}{}", stars, python_arg_name(name));
+    }
+    params
+}
+
+/// `text_signature`の文字列を解析する
+/// 
+/// `$module`・`$self`などPyO3が補う引数は除く。
+/// 
+/// # Arguments
+/// 
+/// * `text` - `(a, b=1, *, c=None)`の形式の文字列
+/// 
+/// # Returns
+/// 
+/// * `Option<Vec<SignatureParam>>` - 引数（括弧で囲まれていなければ`None`）
+pub fn parse_text_signature(text: &str) -> Option<Vec<SignatureParam>> {
+    let text = text.trim();
+    (text.starts_with('(') && text.ends_with(')')).then(|| parse_params(text, python_default))
+}
+
+/// 括弧で囲まれた引数リストを、トップレベルのカンマで区切って解析する
+fn parse_params(text: &str, convert_default: fn(&str) -> String) -> Vec<SignatureParam> {
+    let inner = text.trim().strip_prefix('(').and_then(|text| text.strip_suffix(')')).unwrap_or(text);
+    split_top_level(inner)
+        .into_iter()
+        .filter_map(|param| {
+            let (name, default) = match param.split_once('=') {
+                Some((name, default)) => (name, Some(convert_default(default.trim()))),
+                None => (param.as_str(), None),
+            };
+            // `* args`のようにトークンの間に空白が入る
+            let name: String = name.chars().filter(|c| !c.is_whitespace()).collect();
+            (!name.is_empty() && !name.starts_with('$')).then_some(SignatureParam { name, default })
+        })
+        .collect()
+}
+
+/// 括弧・文字列の外側のカンマで区切る
+fn split_top_level(text: &str) -> Vec<String> {
+    let mut parts = vec![];
+    let mut current = String::new();
+    let mut depth = 0;
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+    for c in text.chars() {
+        if let Some(q) = quote {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == q {
+                quote = None;
+            }
+        } else {
+            match c {
+                '"' | '\'' => quote = Some(c),
+                '(' | '[' | '{' => depth += 1,
+                ')' | ']' | '}' => depth -= 1,
+                ',' if depth == 0 => {
+                    parts.push(std::mem::take(&mut current).trim().to_string());
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        current.push(c);
+    }
+    if !current.trim().is_empty() {
+        parts.push(current.trim().to_string());
+    }
+    parts
+}
+
+/// Rustの式のデフォルト値をPythonの式にする（単純なリテラル以外は`...`）
+fn rust_default(expr: &str) -> String {
+    let expr: String = if expr.starts_with('"') {
+        expr.to_string()
+    } else {
+        expr.chars().filter(|c| !c.is_whitespace()).collect()
+    };
+    match expr.as_str() {
+        "true" => "True".to_string(),
+        "false" => "False".to_string(),
+        _ => python_default(&expr),
+    }
+}
+
+/// Pythonの式のデフォルト値のうち、スタブにそのまま書ける単純なリテラルを残す（それ以外は`...`）
+fn python_default(expr: &str) -> String {
+    let is_number = {
+        let digits = expr.strip_prefix('-').unwrap_or(expr);
+        digits.starts_with(|c: char| c.is_ascii_digit())
+            && digits.chars().all(|c| c.is_ascii_digit() || matches!(c, '.' | '_' | 'e' | 'E'))
+    };
+    let is_string = expr.len() >= 2
+        && ((expr.starts_with('"') && expr.ends_with('"')) || (expr.starts_with('\'') && expr.ends_with('\'')));
+    if is_number || is_string || matches!(expr, "None" | "True" | "False") {
+        expr.to_string()
+    } else {
+        "...".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn param(name: &str, default: Option<&str>) -> SignatureParam {
+        SignatureParam { name: name.to_string(), default: default.map(str::to_string) }
+    }
+
+    #[test]
+    fn test_parse_rust_signature() {
+        let params = parse_rust_signature(r#"(a , b = 1 , * args , c = None , d = "x, y" , e = vec ! [1 , 2] , f = false , ** kwargs)"#);
+        assert_eq!(params, vec![
+            param("a", None),
+            param("b", Some("1")),
+            param("*args", None),
+            param("c", Some("None")),
+            param("d", Some("\"x, y\"")),
+            param("e", Some("...")),
+            param("f", Some("False")),
+            param("**kwargs", None),
+        ]);
+        assert_eq!(parse_rust_signature("()"), vec![]);
+        assert_eq!(
+            parse_rust_signature("(r#type , from = 0 , * r#in)"),
+            vec![param("type", None), param("from", Some("0")), param("*in", None)]
+        );
+    }
+
+    #[test]
+    fn test_parse_text_signature() {
+        let params = parse_text_signature("($module, a, /, b=-1.5, *, c=[], d='x')").unwrap();
+        assert_eq!(params, vec![
+            param("a", None),
+            param("/", None),
+            param("b", Some("-1.5")),
+            param("*", None),
+            param("c", Some("...")),
+            param("d", Some("'x'")),
+        ]);
+        assert_eq!(format_params(&params), "(a, /, b=-1.5, *, c=..., d='x')");
+        assert_eq!(parse_text_signature("a, b"), None);
+    }
+
+    #[test]
+    fn test_same_params() {
+        let rust = parse_rust_signature("(a , b = 1 , * , c = None)");
+        assert!(same_params(&rust, &parse_text_signature("(a, b=2, *, c=None)").unwrap()));
+        assert!(!same_params(&rust, &parse_text_signature("(a, b, *, c=None)").unwrap()));
+        assert!(!same_params(&rust, &parse_text_signature("(a, b=1, c=None)").unwrap()));
+    }
+}
@@ -0,0 +1,226 @@
+//! シンボルテーブルモジュール
+//! 
+//! クレート内の全モジュールから型定義と`use`宣言を集め、型名の解決に使用します。
+//! 型エイリアスの展開先や、`#[pyclass]`のPython側の名前を引くことができます。
+
+use std::collections::HashMap;
+use crate::stubgen::parser::*;
+
+/// `use`のたどり直しを打ち切る深さ（循環した再エクスポート対策）
+const MAX_IMPORT_DEPTH: usize = 16;
+
+/// シンボルテーブルに登録される型の情報
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Symbol {
+    /// Pythonに公開されるクラス
+    PyClass { python_name: String },
+    /// 型エイリアス（展開先は定義されたモジュールを基準に解決する）
+    Alias { generics: Vec<String>, target: Box<syn::Type>, module_path: Vec<String> },
+    /// Pythonに公開されていない型
+    Private,
+}
+
+/// クレート全体の型定義を保持するテーブル
+#[derive(Debug, Default)]
+pub struct SymbolTable {
+    /// 完全パス（`crate::foo::Bar`）からシンボルへの対応
+    symbols: HashMap<String, Symbol>,
+    /// 型名から完全パスへの対応
+    names: HashMap<String, Vec<String>>,
+    /// モジュールパスから、そのモジュールの`use`宣言への対応
+    imports: HashMap<String, Vec<RustImport>>,
+}
+
+impl SymbolTable {
+    /// パース済みの全モジュールからシンボルテーブルを構築する
+    /// 
+    /// # Arguments
+    /// 
+    /// * `rust_src_data` - パースされたRustソースコードのリスト
+    /// 
+    /// # Returns
+    /// 
+    /// * `SymbolTable` - 構築されたシンボルテーブル
+    pub fn build<'a>(rust_src_data: impl IntoIterator<Item = &'a RustSrcData>) -> SymbolTable {
+        let mut table = SymbolTable::default();
+        for src_data in rust_src_data {
+            for type_data in &src_data.types {
+                table.insert(&src_data.module_path, type_data);
+            }
+            table
+                .imports
+                .entry(src_data.module_path.join("::"))
+                .or_default()
+                .extend(src_data.imports.iter().cloned());
+        }
+        table
+    }
+
+    /// 型定義を登録する
+    /// 
+    /// # Arguments
+    /// 
+    /// * `module_path` - 型が定義されているモジュールのパス
+    /// * `type_data` - 型定義
+    pub fn insert(&mut self, module_path: &[String], type_data: &RustTypeData) {
+        let symbol = match &type_data.kind {
+            RustTypeKind::PyClass { python_name, .. } => Symbol::PyClass { python_name: python_name.clone() },
+            RustTypeKind::Alias { generics, target } => match syn::parse_str::<syn::Type>(target) {
+                Ok(target) => Symbol::Alias {
+                    generics: generics.clone(),
+                    target: Box::new(target),
+                    module_path: module_path.to_vec(),
+                },
+                Err(_) => Symbol::Private,
+            },
+            RustTypeKind::Other => Symbol::Private,
+        };
+        let full_path = format!("{}::{}", module_path.join("::"), type_data.name);
+        // 同じパスに複数の定義がある場合（cfgで切り替えているなど）はPythonに公開されているものを優先する
+        if matches!(self.symbols.get(&full_path), Some(Symbol::PyClass { .. })) {
+            return;
+        }
+        let paths = self.names.entry(type_data.name.clone()).or_default();
+        if !paths.contains(&full_path) {
+            paths.push(full_path.clone());
+        }
+        self.symbols.insert(full_path, symbol);
+    }
+
+    /// モジュール内で書かれた型パスをシンボルに解決する
+    /// 
+    /// モジュールパス・`use`宣言（グロブを含む）の順に探し、見つからなければ
+    /// クレート内で名前が一意に決まる型を使う。
+    /// 
+    /// # Arguments
+    /// 
+    /// * `module_path` - 型パスが書かれているモジュールのパス
+    /// * `segments` - 型パスの各要素（`["super", "Point"]`など）
+    /// 
+    /// # Returns
+    /// 
+    /// * `Option<&Symbol>` - 解決できたシンボル
+    pub fn resolve(&self, module_path: &[String], segments: &[String]) -> Option<&Symbol> {
+        if let Some(symbol) = self
+            .absolute_path(module_path, segments, 0)
+            .and_then(|path| self.symbols.get(&path.join("::")))
+        {
+            return Some(symbol);
+        }
+
+        let name = segments.last()?;
+        if segments.len() == 1 {
+            for import in self.imports_of(module_path).iter().filter(|import| import.name == "*") {
+                let Some(mut path) = self.absolute_path(module_path, &import.path, 0) else { continue };
+                path.push(name.clone());
+                if let Some(symbol) = self.symbols.get(&path.join("::")) {
+                    return Some(symbol);
+                }
+            }
+        }
+
+        match self.names.get(name).map(Vec::as_slice) {
+            Some([path]) => self.symbols.get(path),
+            _ => None,
+        }
+    }
+
+    /// モジュール内で書かれたパスを`crate`から始まる完全パスにする
+    fn absolute_path(&self, module_path: &[String], segments: &[String], depth: usize) -> Option<Vec<String>> {
+        if depth > MAX_IMPORT_DEPTH {
+            return None;
+        }
+        let (first, rest) = segments.split_first()?;
+        match first.as_str() {
+            "crate" => Some(segments.to_vec()),
+            "self" => Some([module_path, rest].concat()),
+            "super" => {
+                let supers = segments.iter().take_while(|segment| *segment == "super").count();
+                let base = module_path.get(..module_path.len().checked_sub(supers)?)?;
+                Some([base, &segments[supers..]].concat())
+            }
+            _ => match self.imports_of(module_path).iter().find(|import| &import.name == first) {
+                // use foo; のように自分自身を指すものはたどらない
+                Some(import) if import.path.len() > 1 => {
+                    let imported = self.absolute_path(module_path, &import.path, depth + 1)?;
+                    Some([imported.as_slice(), rest].concat())
+                }
+                _ => Some([module_path, segments].concat()),
+            },
+        }
+    }
+
+    /// モジュールの`use`宣言を取得する
+    fn imports_of(&self, module_path: &[String]) -> &[RustImport] {
+        self.imports.get(&module_path.join("::")).map_or(&[], Vec::as_slice)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn path(segments: &[&str]) -> Vec<String> {
+        segments.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn src_data(module_path: &[&str], types: Vec<RustTypeData>, imports: Vec<RustImport>) -> RustSrcData {
+        RustSrcData {
+            file: PathBuf::from("lib.rs"),
+            module_path: path(module_path),
+            functions: vec![],
+            constants: vec![],
+            types,
+            imports,
+        }
+    }
+
+    #[test]
+    fn test_build_symbol_table() {
+        let src_data = src_data(&["crate"], vec![
+            RustTypeData {
+                name: "RustPoint".to_string(),
+                kind: RustTypeKind::PyClass { python_name: "Point".to_string(), doc: String::new() },
+                line: 0,
+            },
+            RustTypeData {
+                name: "Matrix".to_string(),
+                kind: RustTypeKind::Alias { generics: vec![], target: "Vec<Vec<f64>>".to_string() },
+                line: 0,
+            },
+        ], vec![]);
+        let table = SymbolTable::build([&src_data]);
+
+        let root = path(&["crate"]);
+        assert_eq!(table.resolve(&root, &path(&["RustPoint"])), Some(&Symbol::PyClass { python_name: "Point".to_string() }));
+        assert!(matches!(table.resolve(&root, &path(&["Matrix"])), Some(Symbol::Alias { .. })));
+        assert_eq!(table.resolve(&root, &path(&["Unknown"])), None);
+    }
+
+    #[test]
+    fn test_resolve_across_modules() {
+        let config = |kind| RustTypeData { name: "Config".to_string(), kind, line: 0 };
+        let modules = [
+            src_data(&["crate", "api"], vec![config(RustTypeKind::PyClass { python_name: "ApiConfig".to_string(), doc: String::new() })], vec![]),
+            src_data(&["crate", "internal"], vec![config(RustTypeKind::Other)], vec![]),
+            src_data(&["crate", "api", "handlers"], vec![], vec![]),
+            src_data(&["crate", "bindings"], vec![], vec![
+                RustImport { name: "Config".to_string(), path: path(&["crate", "api", "Config"]) },
+            ]),
+            src_data(&["crate", "glob"], vec![], vec![
+                RustImport { name: "*".to_string(), path: path(&["super", "internal"]) },
+            ]),
+        ];
+        let table = SymbolTable::build(&modules);
+        let api = Some(&Symbol::PyClass { python_name: "ApiConfig".to_string() });
+
+        assert_eq!(table.resolve(&path(&["crate", "api"]), &path(&["Config"])), api);
+        assert_eq!(table.resolve(&path(&["crate", "api", "handlers"]), &path(&["super", "Config"])), api);
+        assert_eq!(table.resolve(&path(&["crate", "bindings"]), &path(&["Config"])), api);
+        assert_eq!(table.resolve(&path(&["crate"]), &path(&["internal", "Config"])), Some(&Symbol::Private));
+        assert_eq!(table.resolve(&path(&["crate", "glob"]), &path(&["Config"])), Some(&Symbol::Private));
+        // 同名の型が複数あり、どれを指すか決められない
+        assert_eq!(table.resolve(&path(&["crate"]), &path(&["Config"])), None);
+    }
+}
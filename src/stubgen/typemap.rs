@@ -1,80 +1,419 @@
 // src/stubgen/type_map.rs
 
-use syn::{Type, PathArguments, GenericArgument};
-
-/// Rustの型をPython stub(.pyi)の型に変換する（PyO3のルールに基づく）
-pub fn map_type(ty: &Type) -> String {
-    match ty {
-        Type::Path(type_path) => {
-            let ident = type_path.path.segments.last().unwrap().ident.to_string();
-
-            match ident.as_str() {
-                // 数値型
-                "i8" | "i16" | "i32" | "i64" | "isize" |
-                "u8" | "u16" | "u32" | "u64" | "usize" => "int".to_string(),
-
-                // 浮動小数点
-                "f32" | "f64" => "float".to_string(),
-
-                // 論理値
-                "bool" => "bool".to_string(),
-
-                // 文字列
-                "String" => "str".to_string(),
-                "PyString" => "str".to_string(),
-                "Py<PyString>" => "str".to_string(),
-
-                // Pythonオブジェクトそのまま
-                "PyAny" => "Any".to_string(),
-                "PyObject" => "Any".to_string(),
-                "PyResult" => {
-                    // PyResult<T> の T を再帰的に処理
-                    extract_generic_type(type_path, 0).map_or("Any".to_string(), |inner| map_type(inner))
-                }
+use std::collections::{BTreeMap, HashMap};
+use quote::ToTokens;
+use syn::{Expr, Lit, Type, PathArguments, GenericArgument};
+use crate::stubgen::analyzer::PythonTypeAlias;
+use crate::stubgen::config::PythonVersion;
+use crate::stubgen::symbols::*;
+
+/// シンボルテーブルを参照しながらRustの型をPythonの型に変換する
+pub struct TypeMapper<'a> {
+    symbols: &'a SymbolTable,
+    /// 型パスを解決する基準のモジュール（エイリアス展開中は定義元のモジュールを積む）
+    scopes: Vec<Vec<String>>,
+    /// 展開中のジェネリックなエイリアスの型引数（型パラメータ名 → Pythonの型）
+    bindings: Vec<HashMap<String, String>>,
+    /// 展開中のエイリアス名（循環参照の検出用）
+    expanding: Vec<String>,
+    /// 解決できずに`Any`にした型名
+    unresolved: Vec<String>,
+    /// 変換に対応していない書き方のため`Any`にした型（`impl Trait`・`dyn Trait`・関数ポインタなど）
+    unsupported: Vec<String>,
+    /// Rustの型（パスまたは型名）からPythonの型への上書き
+    type_map: Option<&'a BTreeMap<String, String>>,
+    /// 対象とするPythonのバージョン（未指定なら`list[int]`・`X | None`を使い、エイリアスは展開する）
+    python_version: Option<PythonVersion>,
+    /// `type`文で定義する型エイリアス（Python 3.12以降）
+    aliases: Vec<PythonTypeAlias>,
+}
+
+impl<'a> TypeMapper<'a> {
+    pub fn new(symbols: &'a SymbolTable) -> Self {
+        TypeMapper {
+            symbols,
+            scopes: vec![vec!["crate".to_string()]],
+            bindings: vec![],
+            expanding: vec![],
+            unresolved: vec![],
+            unsupported: vec![],
+            type_map: None,
+            python_version: None,
+            aliases: vec![],
+        }
+    }
+
+    /// 型パスを解決する基準のモジュールを設定する
+    pub fn set_module(&mut self, module_path: &[String]) {
+        self.scopes = vec![module_path.to_vec()];
+    }
+
+    /// 組み込みの変換より優先する型の対応を設定する（`chrono::DateTime` → `datetime.datetime`など）
+    pub fn set_type_map(&mut self, type_map: &'a BTreeMap<String, String>) {
+        self.type_map = Some(type_map);
+    }
+
+    /// 対象とするPythonのバージョンを設定する（型の書き方とエイリアスの扱いが変わる）
+    pub fn set_python_version(&mut self, python_version: Option<PythonVersion>) {
+        self.python_version = python_version;
+    }
+
+    /// これまでに`type`文で定義することにした型エイリアスを取り出す
+    pub fn take_aliases(&mut self) -> Vec<PythonTypeAlias> {
+        std::mem::take(&mut self.aliases)
+    }
+
+    /// これまでに解決できなかった型名を取り出す
+    pub fn take_unresolved(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.unresolved)
+    }
+
+    /// これまでに変換に対応していなかった型を取り出す
+    pub fn take_unsupported(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.unsupported)
+    }
 
-                // オプション型
-                "Option" => {
-                    extract_generic_type(type_path, 0).map_or("Any | None".to_string(), |inner| {
-                        format!("{} | None", map_type(inner))
-                    })
+    /// Rustの型をPython stub(.pyi)の型に変換する（PyO3のルールに基づく）
+    pub fn map_type(&mut self, ty: &Type) -> String {
+        match ty {
+            Type::Path(type_path) => {
+                let ident = type_path.path.segments.last().unwrap().ident.to_string();
+                if let Some(python_type) = self.map_override(type_path, &ident) {
+                    return python_type;
                 }
 
-                // ベクタ型
-                "Vec" => {
-                    extract_generic_type(type_path, 0).map_or("list[Any]".to_string(), |inner| {
-                        format!("list[{}]", map_type(inner))
-                    })
+                match ident.as_str() {
+                    // 数値型
+                    "i8" | "i16" | "i32" | "i64" | "i128" | "isize" |
+                    "u8" | "u16" | "u32" | "u64" | "u128" | "usize" => "int".to_string(),
+
+                    // 浮動小数点
+                    "f32" | "f64" => "float".to_string(),
+
+                    // 論理値
+                    "bool" => "bool".to_string(),
+
+                    // 文字列
+                    "String" | "str" | "char" => "str".to_string(),
+                    "PyString" => "str".to_string(),
+
+                    // Pythonの組み込み型
+                    "PyBytes" => "bytes".to_string(),
+                    "PyInt" | "PyLong" => "int".to_string(),
+                    "PyFloat" => "float".to_string(),
+                    "PyBool" => "bool".to_string(),
+                    "PyList" => self.generic("list", &["Any"]),
+                    "PyDict" => self.generic("dict", &["Any", "Any"]),
+                    "PySet" => self.generic("set", &["Any"]),
+                    "PyTuple" => self.generic("tuple", &["Any", "..."]),
+
+                    // Pythonオブジェクトそのまま
+                    "PyAny" => "Any".to_string(),
+                    "PyObject" => "Any".to_string(),
+                    "PyResult" | "Result" => {
+                        // PyResult<T> の T を再帰的に処理
+                        self.map_generic(type_path, 0)
+                    }
+
+                    // スマートポインタ・GILに紐づく参照は中身の型として扱う
+                    "Bound" | "Borrowed" | "Py" | "PyRef" | "PyRefMut" | "Box" => {
+                        self.map_generic(type_path, 0)
+                    }
+
+                    // オプション型
+                    "Option" => {
+                        let inner = self.map_generic(type_path, 0);
+                        self.optional(&inner)
+                    }
+
+                    // どちらかの型（PyO3のeitherフィーチャー）
+                    "Either" => {
+                        let left = self.map_generic(type_path, 0);
+                        let right = self.map_generic(type_path, 1);
+                        self.union(&[&left, &right])
+                    }
+
+                    // ベクタ型
+                    "Vec" => {
+                        let inner = self.map_generic(type_path, 0);
+                        self.generic("list", &[&inner])
+                    }
+
+                    // 集合
+                    "HashSet" | "BTreeSet" => {
+                        let inner = self.map_generic(type_path, 0);
+                        self.generic("set", &[&inner])
+                    }
+
+                    // ハッシュマップ
+                    "HashMap" | "BTreeMap" => {
+                        let key = self.map_generic(type_path, 0);
+                        let val = self.map_generic(type_path, 1);
+                        self.generic("dict", &[&key, &val])
+                    }
+
+                    // その他：クレート内の型定義から解決する
+                    _ => self.map_symbol(type_path, ident),
                 }
+            }
+
+            // 空のタプルは戻り値なし
+            Type::Tuple(t) if t.elems.is_empty() => "None".to_string(),
+            Type::Tuple(t) => {
+                let elements = t.elems.iter().map(|e| self.map_type(e)).collect::<Vec<String>>();
+                self.generic("tuple", &elements.iter().map(String::as_str).collect::<Vec<&str>>())
+            }
+
+            // 配列・スライス
+            Type::Array(a) => {
+                let inner = self.map_type(&a.elem);
+                self.generic("list", &[&inner])
+            }
+            Type::Slice(s) => {
+                let inner = self.map_type(&s.elem);
+                self.generic("list", &[&inner])
+            }
 
-                // ハッシュマップ
-                "HashMap" => {
-                    let key_type = extract_generic_type(type_path, 0);
-                    let val_type = extract_generic_type(type_path, 1);
-                    let key = key_type.map_or("Any".to_string(), |k| map_type(k));
-                    let val = val_type.map_or("Any".to_string(), |v| map_type(v));
-                    format!("dict[{}, {}]", key, val)
+            // 参照型（&T）
+            Type::Reference(r) => self.map_type(&r.elem),
+            Type::Paren(p) => self.map_type(&p.elem),
+            Type::Group(g) => self.map_type(&g.elem),
+
+            // 未対応の型は Any 扱い
+            _ => {
+                let rust_type = type_to_string(ty);
+                if !self.unsupported.contains(&rust_type) {
+                    self.unsupported.push(rust_type);
                 }
+                "Any".to_string()
+            }
+        }
+    }
+
+    /// 型の上書きを探す（パス全体の一致を型名だけの一致より優先する）
+    fn map_override(&self, type_path: &syn::TypePath, ident: &str) -> Option<String> {
+        let type_map = self.type_map?;
+        let path = type_path.path.segments
+            .iter()
+            .map(|seg| seg.ident.to_string())
+            .collect::<Vec<String>>()
+            .join("::");
+        type_map.get(&path).or_else(|| type_map.get(ident)).cloned()
+    }
+
+    /// 組み込みのジェネリック型を書く（Python 3.8以前は`typing`の`List`などを使う）
+    fn generic(&self, name: &str, args: &[&str]) -> String {
+        let builtin = self.python_version.is_none_or(PythonVersion::supports_builtin_generics);
+        let name = match name {
+            "list" if !builtin => "List",
+            "dict" if !builtin => "Dict",
+            "set" if !builtin => "Set",
+            "tuple" if !builtin => "Tuple",
+            name => name,
+        };
+        format!("{}[{}]", name, args.join(", "))
+    }
+
+    /// `None`を許す型を書く（Python 3.9以前は`Optional[X]`）
+    fn optional(&self, inner: &str) -> String {
+        if self.python_version.is_none_or(PythonVersion::supports_union_operator) {
+            format!("{} | None", inner)
+        } else {
+            format!("Optional[{}]", inner)
+        }
+    }
+
+    /// 共用型を書く（Python 3.9以前は`Union[X, Y]`）
+    fn union(&self, members: &[&str]) -> String {
+        if self.python_version.is_none_or(PythonVersion::supports_union_operator) {
+            members.join(" | ")
+        } else {
+            format!("Union[{}]", members.join(", "))
+        }
+    }
 
-                // その他：とりあえず型名をそのまま返す
-                _ => ident,
+    /// ジェネリック引数を変換する（引数がなければ`Any`）
+    fn map_generic(&mut self, type_path: &syn::TypePath, index: usize) -> String {
+        extract_generic_type(type_path, index).map_or("Any".to_string(), |inner| self.map_type(inner))
+    }
+
+    /// 組み込みでない型名をシンボルテーブルから解決する
+    fn map_symbol(&mut self, type_path: &syn::TypePath, ident: String) -> String {
+        // 展開中のエイリアスの型パラメータ
+        if type_path.path.segments.len() == 1 {
+            if let Some(bound) = self.bindings.last().and_then(|bindings| bindings.get(&ident)) {
+                return bound.clone();
             }
         }
 
-        
+        let segments = type_path.path.segments
+            .iter()
+            .map(|seg| seg.ident.to_string())
+            .collect::<Vec<String>>();
+        let scope = self.scopes.last().unwrap();
+        match self.symbols.resolve(scope, &segments) {
+            Some(Symbol::PyClass { python_name }) => python_name.clone(),
+            // Python 3.12以降は展開せずに`type`文で定義したエイリアスを参照する
+            Some(Symbol::Alias { generics, target, module_path })
+                if self.python_version.is_some_and(PythonVersion::supports_type_statement) =>
+            {
+                let args = (0..generics.len()).map(|index| self.map_generic(type_path, index)).collect::<Vec<String>>();
+                self.define_alias(&ident, generics, target, module_path);
+                if args.is_empty() {
+                    ident
+                } else {
+                    format!("{}[{}]", ident, args.join(", "))
+                }
+            }
+            Some(Symbol::Alias { generics, target, module_path }) if !self.expanding.contains(&ident) => {
+                let bindings = generics
+                    .iter()
+                    .enumerate()
+                    .map(|(index, name)| (name.clone(), self.map_generic(type_path, index)))
+                    .collect();
+                self.bindings.push(bindings);
+                self.scopes.push(module_path.clone());
+                self.expanding.push(ident);
+                let result = self.map_type(target);
+                self.expanding.pop();
+                self.scopes.pop();
+                self.bindings.pop();
+                result
+            }
+            _ => {
+                if !self.unresolved.contains(&ident) {
+                    self.unresolved.push(ident);
+                }
+                "Any".to_string()
+            }
+        }
+    }
+
+    /// 型エイリアスを`type`文として定義する（定義中のエイリアス自身への参照は名前のまま残す）
+    fn define_alias(&mut self, name: &str, generics: &[String], target: &Type, module_path: &[String]) {
+        if self.expanding.iter().any(|expanding| expanding == name) || self.aliases.iter().any(|alias| alias.name == name) {
+            return;
+        }
+        // 型パラメータはPythonの型パラメータとしてそのまま書く
+        self.bindings.push(generics.iter().map(|param| (param.clone(), param.clone())).collect());
+        self.scopes.push(module_path.to_vec());
+        self.expanding.push(name.to_string());
+        let target = self.map_type(target);
+        self.expanding.pop();
+        self.scopes.pop();
+        self.bindings.pop();
+        self.aliases.push(PythonTypeAlias { name: name.to_string(), params: generics.to_vec(), target });
+    }
+
+    /// Rustの式からPythonの型を推論する（`m.add("name", expr)`の値などに使用）
+    /// 
+    /// 推論できない式は`Any`になる。
+    pub fn infer_expr_type(&mut self, expr: &Expr) -> String {
+        match expr {
+            Expr::Lit(expr_lit) => match &expr_lit.lit {
+                Lit::Str(_) | Lit::Char(_) => "str".to_string(),
+                Lit::ByteStr(_) => "bytes".to_string(),
+                Lit::Int(_) | Lit::Byte(_) => "int".to_string(),
+                Lit::Float(_) => "float".to_string(),
+                Lit::Bool(_) => "bool".to_string(),
+                _ => "Any".to_string(),
+            },
+
+            // 文字列を生成するマクロ
+            Expr::Macro(expr_macro) => {
+                let name = expr_macro.mac.path.segments.last().unwrap().ident.to_string();
+                match name.as_str() {
+                    "env" | "concat" | "format" | "stringify" | "include_str" => "str".to_string(),
+                    "option_env" => self.optional("str"),
+                    "vec" => {
+                        let elements = expr_macro.mac.parse_body_with(
+                            syn::punctuated::Punctuated::<Expr, syn::Token![,]>::parse_terminated,
+                        );
+                        let inner = match elements {
+                            Ok(elements) => self.infer_sequence_type(elements.iter()),
+                            Err(_) => "Any".to_string(),
+                        };
+                        self.generic("list", &[&inner])
+                    }
+                    _ => "Any".to_string(),
+                }
+            }
+
+            Expr::Array(array) => {
+                let inner = self.infer_sequence_type(array.elems.iter());
+                self.generic("list", &[&inner])
+            }
+            Expr::Tuple(tuple) => {
+                let elements = tuple.elems.iter().map(|e| self.infer_expr_type(e)).collect::<Vec<String>>();
+                self.generic("tuple", &elements.iter().map(String::as_str).collect::<Vec<&str>>())
+            }
+
+            // -1 や 1 + 2 などは左辺の型に従う（比較・論理演算は bool）
+            Expr::Unary(unary) if !matches!(unary.op, syn::UnOp::Not(_)) => self.infer_expr_type(&unary.expr),
+            Expr::Unary(_) => "bool".to_string(),
+            Expr::Binary(binary) if is_bool_op(&binary.op) => "bool".to_string(),
+            Expr::Binary(binary) => self.infer_expr_type(&binary.left),
+            Expr::Paren(paren) => self.infer_expr_type(&paren.expr),
+            Expr::Group(group) => self.infer_expr_type(&group.expr),
+            Expr::Reference(reference) => self.infer_expr_type(&reference.expr),
+            Expr::Cast(cast) => self.map_type(&cast.ty),
+
+            // "foo".to_string() / String::from("foo")
+            Expr::MethodCall(call) if matches!(call.method.to_string().as_str(), "to_string" | "to_owned" | "into") => {
+                self.infer_expr_type(&call.receiver)
+            }
+            // String::new() / Point::new(...) は型が分かるときだけ使う
+            Expr::Call(call) => match &*call.func {
+                Expr::Path(path) if path.path.segments.len() >= 2 => {
+                    let mut type_path = path.path.clone();
+                    type_path.segments.pop();
+                    type_path.segments.pop_punct();
+                    self.map_known_type(&Type::Path(syn::TypePath { qself: None, path: type_path }))
+                }
+                _ => "Any".to_string(),
+            },
 
-        Type::Tuple(t) => {
-            let elements = t.elems.iter().map(|e| map_type(e)).collect::<Vec<String>>();
-            format!("tuple[{}]", elements.join(", "))
+            _ => "Any".to_string(),
         }
+    }
 
-        // 参照型（&T）
-        Type::Reference(r) => map_type(&r.elem),
+    /// 組み込みの型・上書きした型・クレート内で解決できる型だけを変換する（解決できなければ`Any`とし、警告にもしない）
+    fn map_known_type(&mut self, ty: &Type) -> String {
+        let unresolved = self.unresolved.len();
+        let python_type = self.map_type(ty);
+        if self.unresolved.len() > unresolved {
+            self.unresolved.truncate(unresolved);
+            return "Any".to_string();
+        }
+        python_type
+    }
 
-        // 未対応の型は Any 扱い
-        _ => "Any".to_string(),
+    /// 配列要素の型を推論する（最初の要素の型を使う）
+    fn infer_sequence_type<'e>(&mut self, mut elements: impl Iterator<Item = &'e Expr>) -> String {
+        elements.next().map_or("Any".to_string(), |e| self.infer_expr_type(e))
     }
 }
 
+/// 結果が`bool`になる演算子（比較・論理演算）かどうか
+fn is_bool_op(op: &syn::BinOp) -> bool {
+    use syn::BinOp;
+    matches!(op, BinOp::Eq(_) | BinOp::Ne(_) | BinOp::Lt(_) | BinOp::Le(_) | BinOp::Gt(_) | BinOp::Ge(_) | BinOp::And(_) | BinOp::Or(_))
+}
+
+/// 型をRustの書き方に近い文字列にする（`impl Into < String >` → `impl Into<String>`）
+fn type_to_string(ty: &Type) -> String {
+    ty.to_token_stream()
+        .to_string()
+        .replace(" < ", "<")
+        .replace(" <", "<")
+        .replace("< ", "<")
+        .replace(" >", ">")
+        .replace(" ,", ",")
+        .replace(" :: ", "::")
+        .replace("& ", "&")
+        .replace("fn (", "fn(")
+}
+
 /// Generic typeを抽出するヘルパー関数（Vec<T>などのTを取得）
 fn extract_generic_type(type_path: &syn::TypePath, index: usize) -> Option<&Type> {
     type_path.path.segments.last().and_then(|seg| {
@@ -96,6 +435,15 @@ fn extract_generic_type(type_path: &syn::TypePath, index: usize) -> Option<&Type
 #[cfg(test)]
 mod tests{
     use super::*;
+    use crate::stubgen::parser::{RustTypeData, RustTypeKind};
+
+    fn map_type(ty: &Type) -> String {
+        TypeMapper::new(&SymbolTable::default()).map_type(ty)
+    }
+
+    fn infer_expr_type(expr: &Expr) -> String {
+        TypeMapper::new(&SymbolTable::default()).infer_expr_type(expr)
+    }
     
 
     #[test]
@@ -128,5 +476,175 @@ mod tests{
         let result = map_type(&ty);
         assert_eq!(result, "tuple[int, int]");
     }
-    
-}
\ No newline at end of file
+
+    #[test]
+    fn test_infer_expr_type(){
+        let cases = [
+            (r#"env!("CARGO_PKG_VERSION")"#, "str"),
+            ("1024", "int"),
+            ("-1.5", "float"),
+            ("true", "bool"),
+            (r#""text".to_string()"#, "str"),
+            ("vec![1, 2, 3]", "list[int]"),
+            ("(1, \"a\")", "tuple[int, str]"),
+            ("String::from(\"a\")", "str"),
+            ("3 as f64", "float"),
+            ("some_function()", "Any"),
+            ("1 < 2", "bool"),
+            ("FLAG && 1 != 2", "bool"),
+            ("2 * 3", "int"),
+            ("Unknown::new()", "Any"),
+        ];
+        for (code, expected) in cases {
+            let expr = syn::parse_str(code).unwrap();
+            assert_eq!(infer_expr_type(&expr), expected, "{}", code);
+        }
+    }
+
+    #[test]
+    fn test_map_type_with_symbols(){
+        let root = vec!["crate".to_string()];
+        let mut symbols = SymbolTable::default();
+        symbols.insert(&root, &RustTypeData{
+            name: "RustPoint".to_string(),
+            kind: RustTypeKind::PyClass{ python_name: "Point".to_string(), doc: String::new() },
+            line: 0,
+        });
+        symbols.insert(&root, &RustTypeData{
+            name: "Matrix".to_string(),
+            kind: RustTypeKind::Alias{ generics: vec![], target: "Vec<Vec<f64>>".to_string() },
+            line: 0,
+        });
+        symbols.insert(&root, &RustTypeData{
+            name: "Pair".to_string(),
+            kind: RustTypeKind::Alias{ generics: vec!["T".to_string()], target: "(T, T)".to_string() },
+            line: 0,
+        });
+        symbols.insert(&root, &RustTypeData{
+            name: "Internal".to_string(),
+            kind: RustTypeKind::Other,
+            line: 0,
+        });
+
+        let mut mapper = TypeMapper::new(&symbols);
+        let cases = [
+            ("Vec<RustPoint>", "list[Point]"),
+            ("Matrix", "list[list[float]]"),
+            ("Pair<Option<Matrix>>", "tuple[list[list[float]] | None, list[list[float]] | None]"),
+            ("PyResult<Bound<'_, RustPoint>>", "Point"),
+            ("Vec<Internal>", "list[Any]"),
+            ("HashMap<String, Unknown>", "dict[str, Any]"),
+        ];
+        for (code, expected) in cases {
+            let ty = syn::parse_str(code).unwrap();
+            assert_eq!(mapper.map_type(&ty), expected, "{}", code);
+        }
+        assert_eq!(mapper.take_unresolved(), vec!["Internal".to_string(), "Unknown".to_string()]);
+
+        // コンストラクタの呼び出しは解決できる型だけを使い、解決できなくても警告にしない
+        for (code, expected) in [("RustPoint::new(1.0)", "Point"), ("crate::RustPoint::default()", "Point"), ("Internal::new()", "Any"), ("Vec::<i32>::new()", "list[int]")] {
+            let expr = syn::parse_str(code).unwrap();
+            assert_eq!(mapper.infer_expr_type(&expr), expected, "{}", code);
+        }
+        assert!(mapper.take_unresolved().is_empty());
+    }
+
+    #[test]
+    fn test_map_type_unsupported(){
+        let symbols = SymbolTable::default();
+        let mut mapper = TypeMapper::new(&symbols);
+        for code in ["impl Into<String>", "Vec<&dyn std::fmt::Debug>", "fn(i32) -> i32", "impl Into<String>"] {
+            let ty = syn::parse_str(code).unwrap();
+            assert!(mapper.map_type(&ty).contains("Any"), "{}", code);
+        }
+        assert_eq!(mapper.take_unsupported(), vec!["impl Into<String>", "dyn std::fmt::Debug", "fn(i32) -> i32"]);
+        assert!(mapper.take_unsupported().is_empty());
+    }
+
+    #[test]
+    fn test_map_type_with_type_map(){
+        let symbols = SymbolTable::default();
+        let type_map = BTreeMap::from([
+            ("chrono::DateTime".to_string(), "datetime.datetime".to_string()),
+            ("Uuid".to_string(), "uuid.UUID".to_string()),
+            ("i64".to_string(), "SupportsInt".to_string()),
+        ]);
+        let mut mapper = TypeMapper::new(&symbols);
+        mapper.set_type_map(&type_map);
+        let cases = [
+            ("Vec<chrono::DateTime<Utc>>", "list[datetime.datetime]"),
+            ("Option<uuid::Uuid>", "uuid.UUID | None"),
+            ("i64", "SupportsInt"),
+            ("DateTime<Utc>", "Any"),
+        ];
+        for (code, expected) in cases {
+            let ty = syn::parse_str(code).unwrap();
+            assert_eq!(mapper.map_type(&ty), expected, "{}", code);
+        }
+    }
+
+    #[test]
+    fn test_map_type_for_python_versions(){
+        let symbols = SymbolTable::default();
+        let cases = [
+            ("Option<Vec<i32>>", "Optional[List[int]]", "Optional[list[int]]", "list[int] | None"),
+            ("HashMap<String, (i32, f64)>", "Dict[str, Tuple[int, float]]", "dict[str, tuple[int, float]]", "dict[str, tuple[int, float]]"),
+            ("Either<i32, String>", "Union[int, str]", "Union[int, str]", "int | str"),
+            ("Bound<'_, PySet>", "Set[Any]", "set[Any]", "set[Any]"),
+        ];
+        for (code, py38, py39, py310) in cases {
+            let ty = syn::parse_str(code).unwrap();
+            for (minor, expected) in [(8, py38), (9, py39), (10, py310)] {
+                let mut mapper = TypeMapper::new(&symbols);
+                mapper.set_python_version(Some(PythonVersion { major: 3, minor }));
+                assert_eq!(mapper.map_type(&ty), expected, "{} (3.{})", code, minor);
+            }
+        }
+    }
+
+    #[test]
+    fn test_map_type_with_type_statement(){
+        let root = vec!["crate".to_string()];
+        let mut symbols = SymbolTable::default();
+        symbols.insert(&root, &RustTypeData{
+            name: "Matrix".to_string(),
+            kind: RustTypeKind::Alias{ generics: vec![], target: "Vec<Vec<f64>>".to_string() },
+            line: 0,
+        });
+        symbols.insert(&root, &RustTypeData{
+            name: "Pair".to_string(),
+            kind: RustTypeKind::Alias{ generics: vec!["T".to_string()], target: "(T, T)".to_string() },
+            line: 0,
+        });
+        symbols.insert(&root, &RustTypeData{
+            name: "Tree".to_string(),
+            kind: RustTypeKind::Alias{ generics: vec![], target: "Vec<Tree>".to_string() },
+            line: 0,
+        });
+        let mut mapper = TypeMapper::new(&symbols);
+        mapper.set_python_version(Some(PythonVersion { major: 3, minor: 12 }));
+        let ty = syn::parse_str("(Pair<Matrix>, Option<Tree>)").unwrap();
+        assert_eq!(mapper.map_type(&ty), "tuple[Pair[Matrix], Tree | None]");
+        assert_eq!(mapper.take_aliases(), vec![
+            PythonTypeAlias { name: "Matrix".to_string(), params: vec![], target: "list[list[float]]".to_string() },
+            PythonTypeAlias { name: "Pair".to_string(), params: vec!["T".to_string()], target: "tuple[T, T]".to_string() },
+            PythonTypeAlias { name: "Tree".to_string(), params: vec![], target: "list[Tree]".to_string() },
+        ]);
+        assert!(mapper.take_unresolved().is_empty());
+    }
+
+    #[test]
+    fn test_map_type_with_recursive_alias(){
+        let root = vec!["crate".to_string()];
+        let mut symbols = SymbolTable::default();
+        symbols.insert(&root, &RustTypeData{
+            name: "Tree".to_string(),
+            kind: RustTypeKind::Alias{ generics: vec![], target: "Vec<Tree>".to_string() },
+            line: 0,
+        });
+        let mut mapper = TypeMapper::new(&symbols);
+        let ty = syn::parse_str("Tree").unwrap();
+        assert_eq!(mapper.map_type(&ty), "list[Any]");
+        assert_eq!(mapper.take_unresolved(), vec!["Tree".to_string()]);
+    }
+}
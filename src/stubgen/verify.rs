@@ -0,0 +1,445 @@
+//! スタブ検証モジュール
+//! 
+//! 拡張モジュールをローカルでビルドしてPythonからインポートし、実際に公開されている名前、
+//! `__text_signature__`、docstringを調べて、生成済みの`.pyi`と食い違う項目を報告します。
+//! 静的な解析では分からない、マクロやcfgによる公開名の違いを見つけるために使います。
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use serde::Deserialize;
+use crate::stubgen::backend::BuildBackend;
+use crate::stubgen::diagnostic::{Diagnostic, Severity};
+use crate::stubgen::locator::ProjectInfo;
+use crate::stubgen::logging::Verbosity;
+use crate::stubgen::{locate_projects, GenerateOptions};
+
+/// インポートしたモジュールの公開名を調べ、JSONで出力するスクリプト
+const INTROSPECT_SCRIPT: &str = r#"
+import importlib, inspect, json, sys
+module = importlib.import_module(sys.argv[1])
+entries = {}
+for name in dir(module):
+    value = getattr(module, name)
+    if inspect.ismodule(value):
+        kind = "module"
+    elif inspect.isclass(value):
+        kind = "class"
+    elif callable(value):
+        kind = "function"
+    else:
+        kind = "value"
+    callable_kind = kind in ("class", "function")
+    entries[name] = {
+        "kind": kind,
+        "text_signature": getattr(value, "__text_signature__", None) if callable_kind else None,
+        "doc": getattr(value, "__doc__", None) if callable_kind else None,
+    }
+json.dump(entries, sys.stdout)
+"#;
+
+/// 検証のオプション
+#[derive(Debug, Default, Clone)]
+pub struct VerifyOptions {
+    /// 使うPythonインタプリタ（未指定なら仮想環境を探す）
+    pub python: Option<PathBuf>,
+    /// ビルドせず、インストール済みのモジュールを使う
+    pub no_build: bool,
+}
+
+/// 実行時のモジュールから取得した公開名の情報
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+struct RuntimeEntry {
+    /// `function`・`class`・`value`・`module`
+    kind: String,
+    /// `__text_signature__`（`(a, b=None)`など）
+    text_signature: Option<String>,
+    /// `__doc__`
+    doc: Option<String>,
+}
+
+/// スタブに書かれた公開名の種類
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum StubKind {
+    /// 関数（引数名のリスト）
+    Function(Vec<String>),
+    /// クラス
+    Class,
+    /// 変数・定数
+    Value,
+}
+
+/// スタブと実行時のモジュールの食い違い
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mismatch {
+    /// モジュールにあるがスタブにない
+    Missing { name: String, kind: String },
+    /// スタブにあるがモジュールにない
+    Extra { name: String },
+    /// 種類が異なる
+    Kind { name: String, stub: String, runtime: String },
+    /// 引数が異なる
+    Signature { name: String, stub: Vec<String>, runtime: Vec<String> },
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mismatch::Missing { name, kind } => write!(f, "missing from stub: `{}` ({})", name, kind),
+            Mismatch::Extra { name } => write!(f, "not exported by the module: `{}`", name),
+            Mismatch::Kind { name, stub, runtime } => {
+                write!(f, "kind mismatch: `{}` is a {} in the stub but a {} at runtime", name, stub, runtime)
+            }
+            Mismatch::Signature { name, stub, runtime } => write!(
+                f,
+                "signature mismatch: `{}` takes ({}) in the stub but ({}) at runtime",
+                name,
+                stub.join(", "),
+                runtime.join(", ")
+            ),
+        }
+    }
+}
+
+/// 生成済みのスタブを、ビルドした拡張モジュールと照らし合わせる
+/// 
+/// # Arguments
+/// 
+/// * `options` - スタブ生成のオプション（プロジェクトの探し方と設定の上書き）
+/// * `verify_options` - 検証のオプション
+/// 
+/// # Returns
+/// 
+/// * `Result<(), Box<dyn Error>>` - 処理結果（食い違いがあればエラー）
+pub fn verify_stubs(options: &GenerateOptions, verify_options: &VerifyOptions) -> Result<(), Box<dyn Error>> {
+    let logger = options.logger();
+    let project_infos = locate_projects(options)?;
+    options.overrides.check_projects(&project_infos)?;
+
+    let mut mismatches = 0;
+    let mut failed = 0;
+    // ビルドは重いため、プロジェクトは順に処理する
+    for project_info in &project_infos {
+        let project_info = options.overrides.apply(project_info);
+        logger.log(
+            Verbosity::Normal,
+            &format!("Verifying project: {} ({})", project_info.project_name, project_info.python_module),
+        );
+        match verify_project(&project_info, verify_options) {
+            Ok(found) if found.is_empty() => logger.log(Verbosity::Normal, "  Stub matches the built module"),
+            Ok(found) => {
+                for mismatch in &found {
+                    logger.diagnostic(&Diagnostic { severity: Severity::Error, message: mismatch.to_string(), locations: vec![] });
+                }
+                mismatches += found.len();
+            }
+            Err(e) => {
+                logger.error(format!("failed to verify {}: {}", project_info.project_name, e));
+                failed += 1;
+            }
+        }
+    }
+    if failed > 0 {
+        return Err(format!("{} project(s) could not be verified", failed).into());
+    }
+    if mismatches > 0 {
+        return Err(format!("{} mismatch(es) between stubs and built modules", mismatches).into());
+    }
+    Ok(())
+}
+
+/// 1つのプロジェクトのスタブを検証する
+fn verify_project(project_info: &ProjectInfo, verify_options: &VerifyOptions) -> Result<Vec<Mismatch>, Box<dyn Error>> {
+    let stub_path = project_info.output_dir.join(format!("{}.pyi", project_info.module_name));
+    let stub = std::fs::read_to_string(&stub_path)
+        .map_err(|e| format!("failed to read {} ({}); run `cargo pystubgen` first", stub_path.display(), e))?;
+
+    let python = verify_options.python.clone().unwrap_or_else(|| find_python(&project_info.project_root));
+    if !verify_options.no_build {
+        build_module(project_info, &python)?;
+    }
+    let runtime = introspect_module(&python, &project_info.python_module)?;
+    Ok(compare_entries(&parse_stub(&stub), &runtime))
+}
+
+/// 使うPythonインタプリタを探す
+/// 
+/// 有効な仮想環境（`VIRTUAL_ENV`）、プロジェクトかその親ディレクトリの`.venv`、`python3`の順に使う。
+pub fn find_python(project_root: &Path) -> PathBuf {
+    if let Some(venv) = std::env::var_os("VIRTUAL_ENV") {
+        return venv_python(Path::new(&venv));
+    }
+    project_root
+        .ancestors()
+        .map(|dir| venv_python(&dir.join(".venv")))
+        .find(|python| python.is_file())
+        .unwrap_or_else(|| PathBuf::from(if cfg!(windows) { "python" } else { "python3" }))
+}
+
+/// 仮想環境の中のPythonインタプリタのパス
+fn venv_python(venv: &Path) -> PathBuf {
+    if cfg!(windows) {
+        venv.join("Scripts").join("python.exe")
+    } else {
+        venv.join("bin").join("python")
+    }
+}
+
+/// 拡張モジュールをビルドし、Pythonの環境にインストールする
+/// 
+/// maturinのプロジェクトは`maturin develop`、それ以外は`pip install -e`を使う。
+pub fn build_module(project_info: &ProjectInfo, python: &Path) -> Result<(), Box<dyn Error>> {
+    let mut command = match project_info.build_backend {
+        BuildBackend::Maturin => {
+            let mut command = Command::new("maturin");
+            command.arg("develop").current_dir(&project_info.project_root);
+            // maturinは有効な仮想環境にインストールする
+            if let Some(venv) = python.parent().and_then(Path::parent).filter(|venv| venv.join("pyvenv.cfg").is_file()) {
+                command.env("VIRTUAL_ENV", venv);
+            }
+            command
+        }
+        BuildBackend::SetuptoolsRust | BuildBackend::ScikitBuild => {
+            let mut command = Command::new(python);
+            command.args(["-m", "pip", "install", "--quiet", "-e"]).arg(&project_info.project_root);
+            command
+        }
+    };
+    run_command(&mut command).map_err(|e| format!("failed to build the extension module: {}", e))?;
+    Ok(())
+}
+
+/// モジュールをインポートして公開名を調べる
+fn introspect_module(python: &Path, python_module: &str) -> Result<BTreeMap<String, RuntimeEntry>, Box<dyn Error>> {
+    let mut command = Command::new(python);
+    command.args(["-c", INTROSPECT_SCRIPT, python_module]);
+    let output = run_command(&mut command).map_err(|e| format!("failed to import {}: {}", python_module, e))?;
+    Ok(serde_json::from_str(&output)?)
+}
+
+/// コマンドを実行し、標準出力を返す
+fn run_command(command: &mut Command) -> Result<String, Box<dyn Error>> {
+    let output = command
+        .output()
+        .map_err(|e| format!("failed to run {}: {}", command.get_program().to_string_lossy(), e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let message = stderr.lines().rev().find(|line| !line.trim().is_empty()).unwrap_or("failed");
+        return Err(message.trim().to_string().into());
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// スタブのトップレベルに書かれた公開名を集める
+/// 
+/// # Arguments
+/// 
+/// * `stub` - `.pyi`の内容
+/// 
+/// # Returns
+/// 
+/// * `Vec<(String, StubKind)>` - 名前と種類（書かれた順）
+fn parse_stub(stub: &str) -> Vec<(String, StubKind)> {
+    let mut entries = vec![];
+    let lines: Vec<&str> = stub.lines().collect();
+    let mut index = 0;
+    while index < lines.len() {
+        let line = lines[index];
+        index += 1;
+        if line.starts_with([' ', '\t', '#', '@']) || line.trim().is_empty() {
+            continue;
+        }
+
+        if let Some(def) = line.strip_prefix("def ").or_else(|| line.strip_prefix("async def ")) {
+            // 引数が複数行にわたる定義は括弧が閉じるまでつなげる
+            let mut signature = def.to_string();
+            while signature.matches('(').count() > signature.matches(')').count() && index < lines.len() {
+                signature.push_str(lines[index].trim());
+                index += 1;
+            }
+            let name = identifier(&signature);
+            let params = signature
+                .split_once('(')
+                .and_then(|(_, rest)| rest.rsplit_once(')'))
+                .map_or(vec![], |(params, _)| signature_params(params));
+            entries.push((name, StubKind::Function(params)));
+        } else if let Some(class) = line.strip_prefix("class ") {
+            entries.push((identifier(class), StubKind::Class));
+        } else if line.starts_with("import ") || line.starts_with("from ") || is_type_statement(line) {
+            continue;
+        } else {
+            let name = identifier(line);
+            let rest = line[name.len()..].trim_start();
+            if !name.is_empty() && name != "__all__" && (rest.starts_with(':') || rest.starts_with('=')) {
+                entries.push((name, StubKind::Value));
+            }
+        }
+    }
+    entries
+}
+
+/// 先頭の識別子を取り出す
+fn identifier(text: &str) -> String {
+    text.trim_start().chars().take_while(|c| c.is_alphanumeric() || *c == '_').collect()
+}
+
+/// `type X = ...`（PEP 695の型エイリアス）かどうか
+fn is_type_statement(line: &str) -> bool {
+    line.strip_prefix("type ").is_some_and(|rest| {
+        let name = identifier(rest);
+        !name.is_empty() && rest.trim_start()[name.len()..].trim_start().starts_with(['=', '['])
+    })
+}
+
+/// 引数リストから引数名を取り出す（`/`・`*`の区切りと`$module`は除く）
+/// 
+/// # Arguments
+/// 
+/// * `params` - 括弧の中身（`a: int, b: str = ..., *, c`など）
+/// 
+/// # Returns
+/// 
+/// * `Vec<String>` - 引数名（`*args`・`**kwargs`は`*`を残す）
+fn signature_params(params: &str) -> Vec<String> {
+    let mut names = vec![];
+    let mut depth = 0;
+    let mut current = String::new();
+    for c in params.chars().chain([',']) {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            ',' if depth == 0 => {
+                let name: String = current
+                    .trim()
+                    .chars()
+                    .take_while(|c| c.is_alphanumeric() || matches!(c, '_' | '*' | '$'))
+                    .collect();
+                if !name.is_empty() && name != "*" && !name.starts_with('$') {
+                    names.push(name);
+                }
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    names
+}
+
+/// 実行時の関数の引数名を取り出す
+/// 
+/// `__text_signature__`がなければ、PyO3がdocstringの先頭に書く`name(a, b)\n--\n\n`を使う。
+fn runtime_params(name: &str, entry: &RuntimeEntry) -> Option<Vec<String>> {
+    let signature = entry.text_signature.clone().or_else(|| {
+        let doc = entry.doc.as_deref()?;
+        let (first_line, rest) = doc.split_once('\n')?;
+        rest.starts_with("--").then_some(())?;
+        first_line.strip_prefix(name).map(str::to_string)
+    })?;
+    let params = signature.trim().strip_prefix('(')?.rsplit_once(')')?.0;
+    Some(signature_params(params))
+}
+
+/// スタブと実行時のモジュールの公開名を比べる
+/// 
+/// # Arguments
+/// 
+/// * `stub` - スタブの公開名
+/// * `runtime` - 実行時のモジュールの公開名
+/// 
+/// # Returns
+/// 
+/// * `Vec<Mismatch>` - 食い違い（スタブの順、続いてスタブにない名前の順）
+fn compare_entries(stub: &[(String, StubKind)], runtime: &BTreeMap<String, RuntimeEntry>) -> Vec<Mismatch> {
+    let mut mismatches = vec![];
+    for (name, kind) in stub {
+        let Some(entry) = runtime.get(name) else {
+            mismatches.push(Mismatch::Extra { name: name.clone() });
+            continue;
+        };
+        let stub_kind = match kind {
+            StubKind::Function(_) => "function",
+            StubKind::Class => "class",
+            StubKind::Value => "value",
+        };
+        if stub_kind != entry.kind {
+            mismatches.push(Mismatch::Kind { name: name.clone(), stub: stub_kind.to_string(), runtime: entry.kind.clone() });
+            continue;
+        }
+        if let (StubKind::Function(params), Some(runtime_params)) = (kind, runtime_params(name, entry)) {
+            if *params != runtime_params {
+                mismatches.push(Mismatch::Signature { name: name.clone(), stub: params.clone(), runtime: runtime_params });
+            }
+        }
+    }
+
+    for (name, entry) in runtime {
+        // 非公開の名前とサブモジュールはスタブになくてもよい
+        if name.starts_with('_') || entry.kind == "module" || stub.iter().any(|(stub_name, _)| stub_name == name) {
+            continue;
+        }
+        mismatches.push(Mismatch::Missing { name: name.clone(), kind: entry.kind.clone() });
+    }
+    mismatches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn runtime_entry(kind: &str, text_signature: Option<&str>, doc: Option<&str>) -> RuntimeEntry {
+        RuntimeEntry {
+            kind: kind.to_string(),
+            text_signature: text_signature.map(str::to_string),
+            doc: doc.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_parse_stub() {
+        let stub = "from typing import Any\n\ntype Pair[T] = tuple[T, T]\n\n__version__: str\n\n# 足し算\ndef add(a: int, b: dict[str, int] = ..., *, scale: float) -> int:\n    ...\n\ndef join(\n    *parts: str,\n    **options: Any,\n) -> str:\n    ...\n\nclass Point:\n    x: float\n";
+        assert_eq!(parse_stub(stub), vec![
+            ("__version__".to_string(), StubKind::Value),
+            ("add".to_string(), StubKind::Function(vec!["a".to_string(), "b".to_string(), "scale".to_string()])),
+            ("join".to_string(), StubKind::Function(vec!["*parts".to_string(), "**options".to_string()])),
+            ("Point".to_string(), StubKind::Class),
+        ]);
+    }
+
+    #[test]
+    fn test_runtime_params() {
+        let with_signature = runtime_entry("function", Some("($module, a, b=None, /, *, c)"), None);
+        assert_eq!(runtime_params("add", &with_signature), Some(vec!["a".to_string(), "b".to_string(), "c".to_string()]));
+        let from_doc = runtime_entry("function", None, Some("add(a, b)\n--\n\n足し算"));
+        assert_eq!(runtime_params("add", &from_doc), Some(vec!["a".to_string(), "b".to_string()]));
+        assert_eq!(runtime_params("add", &runtime_entry("function", None, Some("足し算"))), None);
+    }
+
+    #[test]
+    fn test_compare_entries() {
+        let stub = vec![
+            ("add".to_string(), StubKind::Function(vec!["a".to_string(), "b".to_string()])),
+            ("removed".to_string(), StubKind::Function(vec![])),
+            ("Point".to_string(), StubKind::Value),
+            ("__version__".to_string(), StubKind::Value),
+        ];
+        let runtime = BTreeMap::from([
+            ("add".to_string(), runtime_entry("function", Some("(a, b, c)"), None)),
+            ("Point".to_string(), runtime_entry("class", Some("(x, y)"), None)),
+            ("__version__".to_string(), runtime_entry("value", None, None)),
+            ("__doc__".to_string(), runtime_entry("value", None, None)),
+            ("helpers".to_string(), runtime_entry("module", None, None)),
+            ("scale".to_string(), runtime_entry("function", None, None)),
+        ]);
+        let messages: Vec<String> = compare_entries(&stub, &runtime).iter().map(ToString::to_string).collect();
+        assert_eq!(messages, vec![
+            "signature mismatch: `add` takes (a, b) in the stub but (a, b, c) at runtime",
+            "not exported by the module: `removed`",
+            "kind mismatch: `Point` is a value in the stub but a class at runtime",
+            "missing from stub: `scale` (function)",
+        ]);
+    }
+}
@@ -0,0 +1,280 @@
+//! ソース監視モジュール
+//! 
+//! 検出したプロジェクトのRustソース、Cargo.toml、pyproject.tomlの変更を監視し、
+//! 変更のあったプロジェクトのスタブファイルだけを再生成します。
+//! エディタでの保存は複数のイベントになることが多いため、一定時間まとめてから処理します。
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+use notify::{Event, RecursiveMode, Watcher};
+use crate::stubgen::locator::ProjectInfo;
+use crate::stubgen::logging::{Logger, Verbosity};
+use crate::stubgen::{generate_projects, locate_projects, print_report, GenerateOptions};
+
+/// 最後のイベントからこの時間だけ変更がなければ再生成する
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// 変更を監視するマニフェストのファイル名
+const MANIFEST_FILES: &[&str] = &["Cargo.toml", "pyproject.toml"];
+
+/// ソースの変更を監視し、変更のあったプロジェクトのスタブを再生成し続ける
+/// 
+/// # Arguments
+/// 
+/// * `options` - スタブ生成のオプション
+/// 
+/// # Returns
+/// 
+/// * `Result<(), Box<dyn Error>>` - 監視を続けられなくなった場合のエラー
+pub fn watch_projects(options: &GenerateOptions) -> Result<(), Box<dyn Error>> {
+    let logger = options.logger();
+    let mut project_infos = locate_projects(options)?;
+    let mut source_files = HashMap::new();
+    // 最初に一度すべて生成する（失敗しても監視は続ける）
+    regenerate(&project_infos, options, &mut source_files)?;
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    let mut watched = vec![];
+    watch_project_paths(&mut watcher, &project_infos, &source_files, &mut watched)?;
+    logger.log(
+        Verbosity::Normal,
+        &format!("Watching {} project(s) for changes (press Ctrl+C to stop)", project_infos.len()),
+    );
+
+    loop {
+        let changed = wait_for_changes(&rx, &logger)?;
+        if changed.is_empty() {
+            continue;
+        }
+
+        // マニフェストが変わった場合はプロジェクトの構成が変わっている可能性がある
+        if changed.iter().any(|path| is_manifest(path)) {
+            match locate_projects(options) {
+                Ok(infos) => project_infos = infos,
+                Err(e) => {
+                    logger.error(format!("failed to locate projects: {}", e));
+                    continue;
+                }
+            }
+        }
+
+        let affected: Vec<ProjectInfo> = project_infos
+            .iter()
+            .filter(|project_info| {
+                let files = source_files.get(&project_info.manifest_path).map_or(&[][..], Vec::as_slice);
+                changed.iter().any(|path| affects_project(project_info, files, path))
+            })
+            .cloned()
+            .collect();
+        if affected.is_empty() {
+            continue;
+        }
+
+        logger.log(Verbosity::Normal, &format!("Changed: {}", summarize_paths(&changed)));
+        regenerate(&affected, options, &mut source_files)?;
+        // #[path]・include!で新しく読むようになったファイルも監視する
+        watch_project_paths(&mut watcher, &project_infos, &source_files, &mut watched)?;
+    }
+}
+
+/// プロジェクトのスタブを再生成して結果を表示し、生成に使ったファイルを記録する
+/// 
+/// プロジェクトは並列に処理するため、かかった時間はまとめて表示する。
+fn regenerate(
+    project_infos: &[ProjectInfo],
+    options: &GenerateOptions,
+    source_files: &mut HashMap<PathBuf, Vec<PathBuf>>,
+) -> Result<(), Box<dyn Error>> {
+    let logger = options.logger();
+    let start = Instant::now();
+    let results = generate_projects(project_infos, options)?;
+    let elapsed = start.elapsed();
+    for (result, project_info) in results.into_iter().zip(project_infos) {
+        match result {
+            Ok(report) => {
+                print_report(&report, &logger);
+                source_files.insert(project_info.manifest_path.clone(), report.source_files);
+            }
+            Err(e) => logger.error(format!("failed to generate stubs for {}: {}", project_info.project_name, e)),
+        }
+    }
+    logger.log(
+        Verbosity::Normal,
+        &format!("Regenerated {} project(s) in {:.2}s", project_infos.len(), elapsed.as_secs_f64()),
+    );
+    Ok(())
+}
+
+/// プロジェクトのソースディレクトリ・マニフェストのあるディレクトリ・生成に使ったファイルのあるディレクトリを監視対象に加える
+fn watch_project_paths(
+    watcher: &mut impl Watcher,
+    project_infos: &[ProjectInfo],
+    source_files: &HashMap<PathBuf, Vec<PathBuf>>,
+    watched: &mut Vec<PathBuf>,
+) -> Result<(), Box<dyn Error>> {
+    let mut targets = vec![];
+    if let Ok(current_dir) = std::env::current_dir() {
+        // ワークスペースのpyproject.toml
+        targets.push((current_dir, RecursiveMode::NonRecursive));
+    }
+    for project_info in project_infos {
+        targets.push((source_dir(project_info).to_path_buf(), RecursiveMode::Recursive));
+        targets.push((manifest_dir(project_info).to_path_buf(), RecursiveMode::NonRecursive));
+        targets.push((project_info.project_root.clone(), RecursiveMode::NonRecursive));
+        // ソースディレクトリの外から#[path]・include!で読み込んだファイル（エディタは保存時にファイルを置き換えるため、ディレクトリを監視する）
+        for file in source_files.get(&project_info.manifest_path).into_iter().flatten() {
+            if let Some(dir) = file.parent().filter(|dir| !dir.starts_with(source_dir(project_info))) {
+                targets.push((dir.to_path_buf(), RecursiveMode::NonRecursive));
+            }
+        }
+    }
+    for (dir, mode) in targets {
+        if watched.contains(&dir) || !dir.is_dir() {
+            continue;
+        }
+        watcher.watch(&dir, mode)?;
+        watched.push(dir);
+    }
+    Ok(())
+}
+
+/// 変更イベントを待ち、続けて届いたイベントをまとめて返す
+/// 
+/// # Arguments
+/// 
+/// * `rx` - ファイル監視のイベントを受け取るチャネル
+/// * `logger` - ファイル監視のエラーを表示するロガー
+/// 
+/// # Returns
+/// 
+/// * `Result<Vec<PathBuf>, Box<dyn Error>>` - 変更されたソースとマニフェストのパス（重複なし）
+fn wait_for_changes(rx: &Receiver<notify::Result<Event>>, logger: &Logger) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let mut changed = vec![];
+    collect_changed_paths(rx.recv()?, &mut changed, logger);
+    loop {
+        match rx.recv_timeout(DEBOUNCE) {
+            Ok(event) => collect_changed_paths(event, &mut changed, logger),
+            Err(RecvTimeoutError::Timeout) => return Ok(changed),
+            Err(RecvTimeoutError::Disconnected) => return Err("file watcher stopped unexpectedly".into()),
+        }
+    }
+}
+
+/// イベントから、再生成が必要なファイルのパスを集める
+fn collect_changed_paths(event: notify::Result<Event>, changed: &mut Vec<PathBuf>, logger: &Logger) {
+    match event {
+        // 読み込みだけのイベントは無視する
+        Ok(event) if event.kind.is_access() => {}
+        Ok(event) => {
+            for path in event.paths {
+                if is_watched_file(&path) && !changed.contains(&path) {
+                    changed.push(path);
+                }
+            }
+        }
+        Err(e) => logger.warn(format!("file watcher error: {}", e)),
+    }
+}
+
+/// 再生成のきっかけになるファイルかどうか（生成した.pyiやキャッシュは含まない）
+fn is_watched_file(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "rs") || is_manifest(path)
+}
+
+/// Cargo.tomlまたはpyproject.tomlかどうか
+fn is_manifest(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| MANIFEST_FILES.contains(&name))
+}
+
+/// 変更されたファイルがプロジェクトのスタブに影響するかどうか
+/// 
+/// # Arguments
+/// 
+/// * `project_info` - プロジェクト情報
+/// * `source_files` - 前回の生成に使ったファイル
+/// * `path` - 変更されたファイル
+fn affects_project(project_info: &ProjectInfo, source_files: &[PathBuf], path: &Path) -> bool {
+    if is_manifest(path) {
+        // プロジェクト自身のCargo.toml・pyproject.tomlか、それを含むワークスペースのマニフェスト
+        return path.parent().is_some_and(|dir| {
+            dir == manifest_dir(project_info) || project_info.project_root.starts_with(dir)
+        });
+    }
+    path.starts_with(source_dir(project_info)) || source_files.iter().any(|file| file == path)
+}
+
+/// クレートルートのあるディレクトリ（`src`）
+fn source_dir(project_info: &ProjectInfo) -> &Path {
+    project_info.crate_root.parent().unwrap_or(Path::new("."))
+}
+
+/// Cargo.tomlのあるディレクトリ
+fn manifest_dir(project_info: &ProjectInfo) -> &Path {
+    project_info.manifest_path.parent().unwrap_or(Path::new("."))
+}
+
+/// 変更されたファイルを短く表示する
+fn summarize_paths(paths: &[PathBuf]) -> String {
+    let current_dir = std::env::current_dir().unwrap_or_default();
+    let names: Vec<String> = paths
+        .iter()
+        .map(|path| path.strip_prefix(&current_dir).unwrap_or(path).display().to_string())
+        .collect();
+    match names.len() {
+        0..=3 => names.join(", "),
+        n => format!("{} and {} more", names[..3].join(", "), n - 3),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{AccessKind, EventKind, ModifyKind};
+
+    fn project_info() -> ProjectInfo {
+        ProjectInfo {
+            project_name: "lib_a".to_string(),
+            module_name: "_core".to_string(),
+            python_module: "lib_a._core".to_string(),
+            output_dir: PathBuf::from("/ws/libs/lib_a/src/lib_a"),
+            project_root: PathBuf::from("/ws/libs/lib_a"),
+            crate_root: PathBuf::from("/ws/libs/lib_a/src/lib.rs"),
+            manifest_path: PathBuf::from("/ws/libs/lib_a/Cargo.toml"),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_affects_project() {
+        let project_info = project_info();
+        let source_files = vec![PathBuf::from("/ws/shared/generated.rs")];
+        assert!(affects_project(&project_info, &source_files, Path::new("/ws/libs/lib_a/src/api/mod.rs")));
+        assert!(affects_project(&project_info, &source_files, Path::new("/ws/libs/lib_a/Cargo.toml")));
+        assert!(affects_project(&project_info, &source_files, Path::new("/ws/pyproject.toml")));
+        assert!(!affects_project(&project_info, &source_files, Path::new("/ws/libs/lib_b/src/lib.rs")));
+        assert!(!affects_project(&project_info, &source_files, Path::new("/ws/libs/lib_b/pyproject.toml")));
+        // #[path]でソースディレクトリの外から読み込んだファイル
+        assert!(affects_project(&project_info, &source_files, Path::new("/ws/shared/generated.rs")));
+        assert!(!affects_project(&project_info, &source_files, Path::new("/ws/shared/other.rs")));
+    }
+
+    #[test]
+    fn test_wait_for_changes() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let modify = |path: &str| Ok(Event::new(EventKind::Modify(ModifyKind::Any)).add_path(PathBuf::from(path)));
+        tx.send(modify("/ws/src/lib.rs")).unwrap();
+        tx.send(modify("/ws/src/lib.rs")).unwrap();
+        tx.send(modify("/ws/src/pkg/_core.pyi")).unwrap();
+        tx.send(Ok(Event::new(EventKind::Access(AccessKind::Any)).add_path(PathBuf::from("/ws/src/api.rs")))).unwrap();
+        tx.send(modify("/ws/Cargo.toml")).unwrap();
+
+        let changed = wait_for_changes(&rx, &Logger::default()).unwrap();
+        assert_eq!(changed, vec![PathBuf::from("/ws/src/lib.rs"), PathBuf::from("/ws/Cargo.toml")]);
+    }
+}
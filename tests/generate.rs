@@ -0,0 +1,27 @@
+//! テスト用プロジェクトに対するスタブ生成の結合テスト
+
+use cargo_pystubgen::{FileKind, StubGenerator};
+
+/// 生成したファイル（スタブ・`py.typed`・`__init__.pyi`）は、リポジトリにあるものと同じになる
+#[test]
+fn test_generate_test_project() {
+    let stubs = StubGenerator::new("tests/test-project")
+        .no_cache(true)
+        .py_typed(true)
+        .init_stub(true)
+        .generate()
+        .unwrap();
+    let names: Vec<&str> = stubs.projects.iter().map(|project| project.project_name.as_str()).collect();
+    assert!(names.contains(&"single_project"), "{:?}", names);
+
+    // リポジトリにないファイル（スタブを生成していないプロジェクトのものなど）は比べない
+    let mut compared = vec![];
+    for file in stubs.files().filter(|file| file.path.is_file()) {
+        let existing = std::fs::read_to_string(&file.path).unwrap();
+        assert_eq!(file.content, existing, "{} is out of date", file.path.display());
+        compared.push(file.kind);
+    }
+    for kind in [FileKind::Stub, FileKind::PyTyped, FileKind::InitStub] {
+        assert!(compared.contains(&kind), "no {:?} file was compared", kind);
+    }
+}
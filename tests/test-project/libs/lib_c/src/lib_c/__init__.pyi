@@ -0,0 +1,11 @@
+# Generated by cargo-pystubgen. Remove this line to maintain this file manually.
+from ._core import *
+
+def main() -> None:
+    ...
+
+__all__ = [
+    "hello_from_bin",
+    "hello_from_bin_2",
+    "main",
+]
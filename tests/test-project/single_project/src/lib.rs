@@ -25,6 +25,8 @@ fn _core(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(test_libs::test_lib_code::test_option_types, m)?)?;
     m.add_function(wrap_pyfunction!(test_libs::test_lib_code_b::test_lib_code_b_fn, m)?)?;
     m.add_function(wrap_pyfunction!(test_libs::test_lib_code_b::test_lib_code_b_fn_2, m)?)?; 
+    m.add("__version__", env!("CARGO_PKG_VERSION"))?;
+    m.add("DEFAULT_SCALE", 2.0)?;
 
     Ok(())
 }
@@ -0,0 +1,26 @@
+# Generated by cargo-pystubgen. Remove this line to maintain this file manually.
+from ._core import *
+from ._core import __version__ as __version__
+
+def main() -> None:
+    ...
+
+__all__ = [
+    "__version__",
+    "DEFAULT_SCALE",
+    "TestStruct",
+    "hello_from_bin",
+    "test_lib_code_fn",
+    "test_add_two_numbers",
+    "test_lib_code_fn_2",
+    "test_numeric_types",
+    "test_string_types",
+    "test_collection_types",
+    "test_tuple_types",
+    "test_hashmap_types",
+    "test_option_types",
+    "test_custom_struct",
+    "test_lib_code_b_fn",
+    "test_lib_code_b_fn_2",
+    "main",
+]
@@ -1,3 +1,9 @@
+__version__: str
+DEFAULT_SCALE: float
+
+class TestStruct:
+    ...
+
 def hello_from_bin() -> str:
     ...
 
@@ -32,7 +38,7 @@ def test_hashmap_types(map: dict[str, int]) -> dict[str, int]:
     ...
 
 #  オプション型を使用した関数
-def test_option_types(maybe_number: int | None, maybe_text: str | None) -> tuple[int | None, str | None]:
+def test_option_types(maybe_number: int | None = None, maybe_text: str | None = None) -> tuple[int | None, str | None]:
     ...
 
 def test_custom_struct(struct_instance: TestStruct) -> TestStruct: